@@ -0,0 +1,251 @@
+use crate::errors::{JudgeClientError, JudgeClientResult};
+use crate::models::*;
+use reqwest::blocking::Client;
+use reqwest::StatusCode;
+use serde::Serialize;
+use std::thread;
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+pub struct JudgeClientConfig {
+    pub connect_timeout: Duration,
+    pub judge_timeout: Duration,
+    pub ping_timeout: Duration,
+    pub max_retries: u32,
+    pub retry_interval: Duration,
+}
+
+impl Default for JudgeClientConfig {
+    fn default() -> Self {
+        Self {
+            connect_timeout: Duration::from_secs(5),
+            judge_timeout: Duration::from_secs(120),
+            ping_timeout: Duration::from_secs(5),
+            max_retries: 2,
+            retry_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct JudgeClient {
+    config: JudgeClientConfig,
+    client: Client,
+}
+
+impl JudgeClient {
+    pub fn new(config: JudgeClientConfig) -> JudgeClientResult<Self> {
+        let client = Client::builder()
+            .connect_timeout(config.connect_timeout)
+            .build()?;
+
+        Ok(Self { config, client })
+    }
+
+    /// Sends a judge task to `{url}/judge`.
+    ///
+    /// A returned `RawJudgeResult` is always well formed: `data` is a list of
+    /// `RawJudgeResultData` if `err` is none, and the error reason otherwise.
+    pub fn judge(
+        &self,
+        url: &str,
+        token: &str,
+        settings: &JudgeSettings,
+    ) -> JudgeClientResult<RawJudgeResult> {
+        let body =
+            self.post_with_retry(url, "judge", token, settings, self.config.judge_timeout)?;
+
+        parse_judge_result(&body)
+    }
+
+    /// Checks `{url}/ping`, returns the server info reported by the judge server.
+    pub fn ping(&self, url: &str, token: &str) -> JudgeClientResult<serde_json::Value> {
+        let body = self.post_with_retry(
+            url,
+            "ping",
+            token,
+            &serde_json::json!({}),
+            self.config.ping_timeout,
+        )?;
+
+        let raw = parse_raw_result(&body)?;
+        if let Some(err) = raw.err {
+            return Err(JudgeClientError::RequestFailed(err));
+        }
+
+        Ok(raw.data)
+    }
+
+    /// Compiles a special judge on `{url}/compile_spj`,
+    /// judge servers keep the executable until `spj_version` changes.
+    pub fn compile_spj(
+        &self,
+        url: &str,
+        token: &str,
+        request: &CompileSpjRequest,
+    ) -> JudgeClientResult<()> {
+        let body = self.post_with_retry(
+            url,
+            "compile_spj",
+            token,
+            request,
+            self.config.judge_timeout,
+        )?;
+
+        parse_compile_spj_result(&body)
+    }
+
+    fn post_with_retry<T: Serialize>(
+        &self,
+        url: &str,
+        endpoint: &str,
+        token: &str,
+        body: &T,
+        timeout: Duration,
+    ) -> JudgeClientResult<String> {
+        let mut retries = 0;
+        loop {
+            match self.post(url, endpoint, token, body, timeout) {
+                Err(e) if e.is_retryable() && retries < self.config.max_retries => {
+                    retries += 1;
+                    warn!(
+                        "Request to {}/{} failed: {}, retrying ({}/{})",
+                        url, endpoint, e, retries, self.config.max_retries
+                    );
+                    thread::sleep(self.config.retry_interval);
+                }
+                res => return res,
+            }
+        }
+    }
+
+    fn post<T: Serialize>(
+        &self,
+        url: &str,
+        endpoint: &str,
+        token: &str,
+        body: &T,
+        timeout: Duration,
+    ) -> JudgeClientResult<String> {
+        let response = self
+            .client
+            .post(format!("{}/{}", url.trim_end_matches('/'), endpoint))
+            .header("X-Judge-Server-Token", token)
+            .timeout(timeout)
+            .json(body)
+            .send()?;
+
+        match response.status() {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(JudgeClientError::AuthRejected),
+            status if !status.is_success() => Err(JudgeClientError::ServerError(status.as_u16())),
+            _ => Ok(response.text()?),
+        }
+    }
+}
+
+fn parse_raw_result(body: &str) -> JudgeClientResult<RawJudgeResult> {
+    let raw: RawJudgeResult = serde_json::from_str(body.trim())
+        .map_err(|e| JudgeClientError::MalformedResponse(e.to_string()))?;
+
+    // judge server answers a wrong token with a normal response
+    if raw.err.as_deref() == Some("TokenVerificationFailed") {
+        return Err(JudgeClientError::AuthRejected);
+    }
+
+    Ok(raw)
+}
+
+fn parse_judge_result(body: &str) -> JudgeClientResult<RawJudgeResult> {
+    let raw = parse_raw_result(body)?;
+
+    if raw.err.is_some() {
+        if !raw.data.is_string() {
+            let hint = "error reason is not a string".to_owned();
+            return Err(JudgeClientError::MalformedResponse(hint));
+        }
+    } else {
+        serde_json::from_value::<Vec<RawJudgeResultData>>(raw.data.clone())
+            .map_err(|e| JudgeClientError::MalformedResponse(e.to_string()))?;
+    }
+
+    Ok(raw)
+}
+
+fn parse_compile_spj_result(body: &str) -> JudgeClientResult<()> {
+    let raw = parse_raw_result(body)?;
+
+    let message = match raw.data.as_str() {
+        Some(message) => message.to_owned(),
+        None => raw.data.to_string(),
+    };
+    match raw.err.as_deref() {
+        None => Ok(()),
+        Some("SPJCompileError") | Some("CompileError") => {
+            Err(JudgeClientError::CompileError(message))
+        }
+        Some(err) => Err(JudgeClientError::RequestFailed(format!(
+            "{}: {}",
+            err, message
+        ))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_finished_result() {
+        let body = r#"{"err": null, "data": [{"cpu_time": 1, "real_time": 2, "memory": 1024,
+            "signal": 0, "exit_code": 0, "error": 0, "result": 0, "test_case": "1",
+            "output_md5": null, "output": null}]}"#;
+        let raw = parse_judge_result(body).unwrap();
+        assert!(raw.err.is_none());
+    }
+
+    #[test]
+    fn parse_compile_error() {
+        let body = r#"{"err": "CompileError", "data": "main.c:1: error"}"#;
+        let raw = parse_judge_result(body).unwrap();
+        assert_eq!(raw.err.as_deref(), Some("CompileError"));
+    }
+
+    #[test]
+    fn parse_rejected_token() {
+        let body = r#"{"err": "TokenVerificationFailed", "data": "invalid token"}"#;
+        match parse_judge_result(body) {
+            Err(JudgeClientError::AuthRejected) => (),
+            res => panic!("unexpected {:?}", res),
+        }
+    }
+
+    #[test]
+    fn parse_malformed_result() {
+        for body in &["", "not json", r#"{"err": null, "data": "oops"}"#] {
+            match parse_judge_result(body) {
+                Err(JudgeClientError::MalformedResponse(_)) => (),
+                res => panic!("unexpected {:?}", res),
+            }
+        }
+    }
+
+    #[test]
+    fn parse_spj_compile_error() {
+        let body = r#"{"err": "SPJCompileError", "data": "spj.cpp:3: error"}"#;
+        match parse_compile_spj_result(body) {
+            Err(JudgeClientError::CompileError(message)) => assert_eq!(message, "spj.cpp:3: error"),
+            res => panic!("unexpected {:?}", res),
+        }
+
+        let body = r#"{"err": null, "data": "success"}"#;
+        assert!(parse_compile_spj_result(body).is_ok());
+    }
+
+    #[test]
+    fn retry_only_unsent_requests() {
+        assert!(JudgeClientError::ConnectionRefused.is_retryable());
+        assert!(!JudgeClientError::ServerError(500).is_retryable());
+        assert!(!JudgeClientError::ServerError(502).is_retryable());
+        assert!(!JudgeClientError::Timeout.is_retryable());
+    }
+}
@@ -0,0 +1,52 @@
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum JudgeClientError {
+    #[error("Judge server timed out")]
+    Timeout,
+
+    #[error("Judge server rejected the token")]
+    AuthRejected,
+
+    #[error("Malformed response from judge server: {0}")]
+    MalformedResponse(String),
+
+    #[error("Connection refused by judge server")]
+    ConnectionRefused,
+
+    #[error("Request to judge server failed: {0}")]
+    RequestFailed(String),
+
+    /// The judge server answered with an error status, it may have run the task already.
+    #[error("Judge server responded with status {0}")]
+    ServerError(u16),
+
+    #[error("Compile error: {0}")]
+    CompileError(String),
+}
+
+impl JudgeClientError {
+    /// Whether sending the same request again may succeed.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            JudgeClientError::ConnectionRefused | JudgeClientError::RequestFailed(_)
+        )
+    }
+}
+
+impl From<reqwest::Error> for JudgeClientError {
+    fn from(error: reqwest::Error) -> JudgeClientError {
+        if error.is_timeout() {
+            JudgeClientError::Timeout
+        } else if error.is_connect() {
+            JudgeClientError::ConnectionRefused
+        } else if error.is_decode() {
+            JudgeClientError::MalformedResponse(error.to_string())
+        } else {
+            JudgeClientError::RequestFailed(error.to_string())
+        }
+    }
+}
+
+pub type JudgeClientResult<V> = std::result::Result<V, JudgeClientError>;
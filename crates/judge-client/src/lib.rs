@@ -0,0 +1,11 @@
+#[macro_use]
+extern crate serde_derive;
+#[macro_use]
+extern crate log;
+
+pub mod client;
+pub mod errors;
+pub mod models;
+
+pub use client::{JudgeClient, JudgeClientConfig};
+pub use errors::{JudgeClientError, JudgeClientResult};
@@ -0,0 +1,148 @@
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompileConfig {
+    pub src_name: String,
+    pub exe_name: String,
+    pub max_cpu_time: i32,
+    pub max_real_time: i32,
+    pub max_memory: i32,
+    pub compile_command: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RunConfig {
+    pub command: String,
+    pub seccomp_rule: Option<String>,
+    pub env: Vec<String>,
+    pub memory_limit_check_only: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LanguageConfig {
+    pub compile: CompileConfig,
+    pub run: RunConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpjConfig {
+    pub exe_name: String,
+    pub command: String,
+    pub seccomp_rule: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpjCompileConfig {
+    pub src_name: String,
+    pub exe_name: String,
+    pub max_cpu_time: i32,
+    pub max_real_time: i32,
+    pub max_memory: i32,
+    pub compile_command: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CompileSpjRequest {
+    pub src: String,
+    pub spj_version: String,
+    pub spj_compile_config: SpjCompileConfig,
+}
+
+/// Interactor of an interactive problem, the judge server runs it
+/// alongside the submission with their stdin and stdout connected.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InteractorConfig {
+    pub src: String,
+    pub version: String,
+    pub compile_config: SpjCompileConfig,
+    pub run_config: SpjConfig,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestCase {
+    pub input: String,
+    pub output: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JudgeSettings {
+    pub language_config: LanguageConfig,
+    pub src: String,
+    pub max_cpu_time: i32,
+    pub max_memory: i32,
+    pub test_case_id: Option<String>,
+    pub test_case: Option<Vec<TestCase>>,
+    pub spj_version: Option<String>,
+    pub spj_config: Option<SpjConfig>,
+    pub spj_compile_config: Option<SpjCompileConfig>,
+    pub spj_src: Option<String>,
+    pub output: bool,
+    /// Asks the judge server to report each finished test case,
+    /// only sent when set since not every judge server accepts it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub progress_callback: Option<ProgressCallback>,
+    /// Asks the judge server to skip remaining test cases after the first failed one
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stop_on_failure: bool,
+    /// Judges by talking to the interactor instead of comparing outputs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interactor: Option<InteractorConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProgressCallback {
+    /// Where the judge server posts a `JudgeProgressReport` for each finished test case
+    pub url: String,
+    pub submission_id: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JudgeProgressReport {
+    pub submission_id: String,
+    pub data: RawJudgeResultData,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawJudgeResult {
+    pub err: Option<String>,
+    pub data: serde_json::Value,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RawJudgeResultData {
+    pub cpu_time: i32,
+    pub real_time: i32,
+    pub memory: i32,
+    pub signal: i32,
+    pub exit_code: i32,
+    pub error: i32,
+    pub result: i32,
+    pub test_case: String,
+    pub output_md5: Option<String>,
+    pub output: Option<String>,
+    /// Exit code of the interactor on interactive problems
+    #[serde(default)]
+    pub interactor_exit_code: Option<i32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn optional_settings_are_not_sent_by_default() {
+        let settings: JudgeSettings = serde_json::from_str(
+            r#"{"language_config": {"compile": {"src_name": "main.c", "exe_name": "main",
+            "max_cpu_time": 3000, "max_real_time": 5000, "max_memory": 134217728,
+            "compile_command": "gcc"}, "run": {"command": "{exe_path}", "seccomp_rule": null,
+            "env": [], "memory_limit_check_only": 0}}, "src": "", "max_cpu_time": 1000,
+            "max_memory": 134217728, "test_case_id": "1", "test_case": null,
+            "spj_version": null, "spj_config": null, "spj_compile_config": null,
+            "spj_src": null, "output": false}"#,
+        )
+        .unwrap();
+
+        let value = serde_json::to_value(&settings).unwrap();
+        assert!(value.get("progress_callback").is_none());
+        assert!(value.get("stop_on_failure").is_none());
+        assert!(value.get("interactor").is_none());
+    }
+}
@@ -12,6 +12,45 @@ pub struct Opt {
     #[structopt(short, long, env = "JUDGE_ACTOR_COUNT", default_value = "1")]
     pub judge_actor_count: usize,
 
+    /// Seconds a submission may stay Pending before it is considered stale,
+    /// stale submissions are put back to the queue every minute
+    #[structopt(long, env = "JUDGE_PENDING_TIMEOUT_IN_SEC", default_value = "600")]
+    pub judge_pending_timeout_in_sec: i64,
+
+    /// Name of this server instance, instances sharing a database need different names,
+    /// submissions left Pending by a previous run of the same instance are put back on startup
+    #[structopt(long, env = "JUDGE_INSTANCE_ID", default_value = "default")]
+    pub judge_instance_id: String,
+
+    /// Seconds to wait for a judge server to finish a submission
+    #[structopt(long, env = "JUDGE_TIMEOUT_IN_SEC", default_value = "120")]
+    pub judge_timeout_in_sec: u64,
+
+    /// Seconds to wait when connecting to or pinging a judge server
+    #[structopt(long, env = "JUDGE_CONNECT_TIMEOUT_IN_SEC", default_value = "5")]
+    pub judge_connect_timeout_in_sec: u64,
+
+    /// Times to retry a judge request when the judge server is unreachable
+    #[structopt(long, env = "JUDGE_RETRY_TIMES", default_value = "2")]
+    pub judge_retry_times: u32,
+
+    /// Times a submission may be sent to judge servers before it is finished as a system error
+    #[structopt(long, env = "JUDGE_MAX_ATTEMPTS", default_value = "3")]
+    pub judge_max_attempts: i32,
+
+    /// Url of `/judge_servers/progress` reachable from judge servers,
+    /// judge servers report finished test cases to it if set
+    #[structopt(long, env = "JUDGE_PROGRESS_CALLBACK_URL")]
+    pub judge_progress_callback_url: Option<String>,
+
+    /// Megabytes an uploaded archive may take, both compressed and extracted
+    #[structopt(long, env = "ARCHIVE_MAX_SIZE_IN_MB", default_value = "1024")]
+    pub archive_max_size_in_mb: u64,
+
+    /// Files an uploaded archive may contain
+    #[structopt(long, env = "ARCHIVE_MAX_FILE_COUNT", default_value = "10000")]
+    pub archive_max_file_count: usize,
+
     /// Domain
     #[structopt(long, env = "DOMAIN", default_value = "localhost")]
     pub domain: String,
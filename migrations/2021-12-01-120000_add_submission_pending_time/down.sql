@@ -0,0 +1,3 @@
+DROP INDEX submissions_state_submit_time_idx;
+
+ALTER TABLE submissions DROP COLUMN pending_time;
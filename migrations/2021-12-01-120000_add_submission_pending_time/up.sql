@@ -0,0 +1,3 @@
+ALTER TABLE submissions ADD COLUMN pending_time TIMESTAMP;
+
+CREATE INDEX submissions_state_submit_time_idx ON submissions (state, submit_time);
@@ -0,0 +1 @@
+DROP TABLE judge_servers;
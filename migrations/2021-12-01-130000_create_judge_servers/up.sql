@@ -0,0 +1,7 @@
+CREATE TABLE judge_servers (
+    id SERIAL PRIMARY KEY,
+    name TEXT NOT NULL,
+    token TEXT NOT NULL UNIQUE,
+    state TEXT NOT NULL DEFAULT 'Enabled',
+    created_time TIMESTAMP NOT NULL
+);
@@ -0,0 +1 @@
+DROP TABLE rejudge_records;
@@ -0,0 +1,16 @@
+CREATE TABLE rejudge_records (
+    id SERIAL PRIMARY KEY,
+    rejudge_id UUID NOT NULL,
+    submission_id UUID NOT NULL,
+    operator_id INTEGER NOT NULL,
+    rejudge_time TIMESTAMP NOT NULL,
+    old_is_accepted BOOLEAN,
+    old_err TEXT,
+    old_out_results TEXT[],
+    new_is_accepted BOOLEAN,
+    new_err TEXT,
+    new_out_results TEXT[],
+    finish_time TIMESTAMP
+);
+
+CREATE INDEX rejudge_records_submission_id_idx ON rejudge_records (submission_id);
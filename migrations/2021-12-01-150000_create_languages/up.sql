@@ -0,0 +1,15 @@
+CREATE TABLE languages (
+    name TEXT PRIMARY KEY,
+    description TEXT NOT NULL,
+    config TEXT NOT NULL,
+    time_multiplier DOUBLE PRECISION NOT NULL DEFAULT 1,
+    memory_multiplier DOUBLE PRECISION NOT NULL DEFAULT 1,
+    is_enabled BOOLEAN NOT NULL DEFAULT TRUE
+);
+
+INSERT INTO languages (name, description, config) VALUES
+    ('c', 'C (GCC, C99)', '{"compile": {"src_name": "main.c", "exe_name": "main", "max_cpu_time": 3000, "max_real_time": 5000, "max_memory": 134217728, "compile_command": "/usr/bin/gcc -DONLINE_JUDGE -O2 -w -fmax-errors=3 -std=c99 {src_path} -lm -o {exe_path}"}, "run": {"command": "{exe_path}", "seccomp_rule": "c_cpp", "env": ["LANG=en_US.UTF-8", "LANGUAGE=en_US:en", "LC_ALL=en_US.UTF-8"], "memory_limit_check_only": 0}}'),
+    ('cpp', 'C++ (G++, C++11)', '{"compile": {"src_name": "main.cpp", "exe_name": "main", "max_cpu_time": 3000, "max_real_time": 5000, "max_memory": 134217728, "compile_command": "/usr/bin/g++ -DONLINE_JUDGE -O2 -w -fmax-errors=3 -std=c++11 {src_path} -lm -o {exe_path}"}, "run": {"command": "{exe_path}", "seccomp_rule": "c_cpp", "env": ["LANG=en_US.UTF-8", "LANGUAGE=en_US:en", "LC_ALL=en_US.UTF-8"], "memory_limit_check_only": 0}}'),
+    ('java', 'Java', '{"compile": {"src_name": "Main.java", "exe_name": "Main", "max_cpu_time": 5000, "max_real_time": 10000, "max_memory": -1, "compile_command": "/usr/bin/javac {src_path} -d {exe_dir} -encoding UTF8"}, "run": {"command": "/usr/bin/java -cp {exe_dir} -XX:MaxRAM={max_memory}k -Djava.security.manager -Dfile.encoding=UTF-8 -Djava.security.policy==/etc/java_policy -Djava.awt.headless=true Main", "seccomp_rule": null, "env": ["LANG=en_US.UTF-8", "LANGUAGE=en_US:en", "LC_ALL=en_US.UTF-8"], "memory_limit_check_only": 1}}'),
+    ('py2', 'Python 2', '{"compile": {"src_name": "solution.py", "exe_name": "solution.pyc", "max_cpu_time": 3000, "max_real_time": 5000, "max_memory": 134217728, "compile_command": "/usr/bin/python -m py_compile {src_path}"}, "run": {"command": "/usr/bin/python {exe_path}", "seccomp_rule": "general", "env": ["LANG=en_US.UTF-8", "LANGUAGE=en_US:en", "LC_ALL=en_US.UTF-8"], "memory_limit_check_only": 0}}'),
+    ('py3', 'Python 3', '{"compile": {"src_name": "solution.py", "exe_name": "__pycache__/solution.cpython-36.pyc", "max_cpu_time": 3000, "max_real_time": 5000, "max_memory": 134217728, "compile_command": "/usr/bin/python3 -m py_compile {src_path}"}, "run": {"command": "/usr/bin/python3 {exe_path}", "seccomp_rule": "general", "env": ["LANG=en_US.UTF-8", "LANGUAGE=en_US:en", "LC_ALL=en_US.UTF-8", "PYTHONIOENCODING=UTF-8"], "memory_limit_check_only": 0}}');
@@ -0,0 +1,7 @@
+UPDATE problems SET settings = (
+    (settings::JSONB - 'language_limits')
+    || jsonb_build_object(
+        'other_max_cpu_time', COALESCE(settings::JSONB #> '{language_limits,java,max_cpu_time}', settings::JSONB -> 'high_performance_max_cpu_time'),
+        'other_max_memory', COALESCE(settings::JSONB #> '{language_limits,java,max_memory}', settings::JSONB -> 'high_performance_max_memory')
+    )
+)::TEXT;
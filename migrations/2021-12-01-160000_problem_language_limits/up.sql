@@ -0,0 +1,17 @@
+-- Keep the limits languages other than c and cpp were judged with
+UPDATE problems SET settings = (
+    (old.settings - 'other_max_cpu_time' - 'other_max_memory')
+    || jsonb_build_object('language_limits', jsonb_build_object('java', old.other_limit, 'py2', old.other_limit, 'py3', old.other_limit))
+)::TEXT
+FROM (
+    SELECT
+        id,
+        settings::JSONB AS settings,
+        jsonb_build_object(
+            'max_cpu_time', settings::JSONB -> 'other_max_cpu_time',
+            'max_memory', settings::JSONB -> 'other_max_memory'
+        ) AS other_limit
+    FROM problems
+    WHERE settings::JSONB ? 'other_max_cpu_time'
+) AS old
+WHERE problems.id = old.id;
@@ -0,0 +1,3 @@
+DROP TABLE checkers;
+
+ALTER TABLE languages DROP COLUMN checker_config;
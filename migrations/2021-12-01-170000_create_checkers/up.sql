@@ -0,0 +1,13 @@
+ALTER TABLE languages ADD COLUMN checker_config TEXT;
+
+-- testlib.h is expected in the default include path of judge servers
+UPDATE languages SET checker_config = '{"compile": {"src_name": "spj-{spj_version}.c", "exe_name": "spj-{spj_version}", "max_cpu_time": 3000, "max_real_time": 5000, "max_memory": 1073741824, "compile_command": "/usr/bin/gcc -DONLINE_JUDGE -O2 -w -fmax-errors=3 -std=c99 {src_path} -lm -o {exe_path}"}, "run": {"exe_name": "spj-{spj_version}", "command": "{exe_path} {in_file_path} {user_out_file_path}", "seccomp_rule": "c_cpp"}}' WHERE name = 'c';
+UPDATE languages SET checker_config = '{"compile": {"src_name": "spj-{spj_version}.cpp", "exe_name": "spj-{spj_version}", "max_cpu_time": 10000, "max_real_time": 20000, "max_memory": 1073741824, "compile_command": "/usr/bin/g++ -DONLINE_JUDGE -O2 -w -fmax-errors=3 -std=c++14 {src_path} -lm -o {exe_path}"}, "run": {"exe_name": "spj-{spj_version}", "command": "{exe_path} {in_file_path} {user_out_file_path}", "seccomp_rule": "c_cpp"}}' WHERE name = 'cpp';
+
+CREATE TABLE checkers (
+    problem_id INTEGER PRIMARY KEY,
+    language TEXT NOT NULL,
+    src TEXT NOT NULL,
+    version TEXT NOT NULL,
+    update_time TIMESTAMP NOT NULL
+);
@@ -0,0 +1 @@
+ALTER TABLE contests ADD COLUMN unfreeze_time TIMESTAMP;
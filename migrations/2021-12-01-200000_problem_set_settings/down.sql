@@ -0,0 +1,2 @@
+ALTER TABLE submissions DROP COLUMN is_late;
+ALTER TABLE problem_sets DROP COLUMN settings;
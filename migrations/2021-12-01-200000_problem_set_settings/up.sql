@@ -0,0 +1,2 @@
+ALTER TABLE problem_sets ADD COLUMN settings TEXT NOT NULL DEFAULT '{}';
+ALTER TABLE submissions ADD COLUMN is_late BOOLEAN NOT NULL DEFAULT FALSE;
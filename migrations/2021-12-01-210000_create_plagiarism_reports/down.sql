@@ -0,0 +1 @@
+DROP TABLE plagiarism_reports;
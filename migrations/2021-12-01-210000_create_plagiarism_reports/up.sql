@@ -0,0 +1,13 @@
+CREATE TABLE plagiarism_reports (
+    id SERIAL PRIMARY KEY,
+    region TEXT NOT NULL,
+    problem_id INTEGER NOT NULL,
+    only_accepted BOOLEAN NOT NULL,
+    threshold DOUBLE PRECISION NOT NULL,
+    submission_count INTEGER NOT NULL,
+    pairs TEXT NOT NULL,
+    operator_id INTEGER NOT NULL,
+    create_time TIMESTAMP NOT NULL
+);
+
+CREATE INDEX plagiarism_reports_region_idx ON plagiarism_reports (region);
@@ -0,0 +1,4 @@
+ALTER TABLE submissions DROP COLUMN test_case_version;
+ALTER TABLE problems DROP COLUMN test_case_version;
+
+DROP TABLE test_case_versions;
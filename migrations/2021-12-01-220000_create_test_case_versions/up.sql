@@ -0,0 +1,11 @@
+CREATE TABLE test_case_versions (
+    problem_id INTEGER NOT NULL,
+    version TEXT NOT NULL,
+    files TEXT NOT NULL,
+    test_case_count INTEGER NOT NULL,
+    create_time TIMESTAMP NOT NULL,
+    PRIMARY KEY (problem_id, version)
+);
+
+ALTER TABLE problems ADD COLUMN test_case_version TEXT;
+ALTER TABLE submissions ADD COLUMN test_case_version TEXT;
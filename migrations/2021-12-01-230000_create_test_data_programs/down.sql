@@ -0,0 +1,3 @@
+ALTER TABLE test_case_versions DROP COLUMN report;
+
+DROP TABLE test_data_programs;
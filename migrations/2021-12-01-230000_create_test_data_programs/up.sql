@@ -0,0 +1,10 @@
+CREATE TABLE test_data_programs (
+    problem_id INTEGER NOT NULL,
+    kind TEXT NOT NULL,
+    language TEXT NOT NULL,
+    src TEXT NOT NULL,
+    update_time TIMESTAMP NOT NULL,
+    PRIMARY KEY (problem_id, kind)
+);
+
+ALTER TABLE test_case_versions ADD COLUMN report TEXT;
@@ -0,0 +1 @@
+ALTER TABLE submissions ADD COLUMN judge_instance TEXT;
@@ -0,0 +1 @@
+ALTER TABLE submissions ADD COLUMN judge_attempts INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1 @@
+ALTER TABLE rejudge_records DROP COLUMN skip_reason;
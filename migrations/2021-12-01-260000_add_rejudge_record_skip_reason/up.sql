@@ -0,0 +1 @@
+ALTER TABLE rejudge_records ADD COLUMN skip_reason TEXT;
@@ -0,0 +1,6 @@
+-- tokens can not be recovered from their digests, judge servers have to be given new tokens
+ALTER TABLE judge_servers ADD COLUMN token TEXT;
+UPDATE judge_servers SET token = token_digest;
+ALTER TABLE judge_servers ALTER COLUMN token SET NOT NULL;
+ALTER TABLE judge_servers ADD CONSTRAINT judge_servers_token_key UNIQUE (token);
+ALTER TABLE judge_servers DROP COLUMN token_digest;
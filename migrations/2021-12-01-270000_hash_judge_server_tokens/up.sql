@@ -0,0 +1,6 @@
+ALTER TABLE judge_servers ADD COLUMN token_digest TEXT;
+-- judge servers send sha256(token) as their header, the header is hashed again to be stored
+UPDATE judge_servers SET token_digest = encode(sha256(convert_to(encode(sha256(convert_to(token, 'UTF8')), 'hex'), 'UTF8')), 'hex');
+ALTER TABLE judge_servers ALTER COLUMN token_digest SET NOT NULL;
+ALTER TABLE judge_servers ADD CONSTRAINT judge_servers_token_digest_key UNIQUE (token_digest);
+ALTER TABLE judge_servers DROP COLUMN token;
@@ -0,0 +1 @@
+UPDATE languages SET checker_config = replace(checker_config, '"command": "{exe_path} {in_file_path} {user_out_file_path} {ans_file_path}"', '"command": "{exe_path} {in_file_path} {user_out_file_path}"') WHERE name IN ('c', 'cpp');
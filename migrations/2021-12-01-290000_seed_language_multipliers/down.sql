@@ -0,0 +1,3 @@
+UPDATE languages SET time_multiplier = 1, memory_multiplier = 1
+WHERE (name = 'java' AND time_multiplier = 2 AND memory_multiplier = 2)
+    OR (name IN ('py2', 'py3') AND time_multiplier = 3 AND memory_multiplier = 2);
@@ -0,0 +1,6 @@
+-- java and python run slower and take more memory than c and cpp, problems without
+-- their own limits give them the high performance limits multiplied by these
+UPDATE languages SET time_multiplier = 2, memory_multiplier = 2
+WHERE name = 'java' AND time_multiplier = 1 AND memory_multiplier = 1;
+UPDATE languages SET time_multiplier = 3, memory_multiplier = 2
+WHERE name IN ('py2', 'py3') AND time_multiplier = 1 AND memory_multiplier = 1;
@@ -1,6 +1,8 @@
 use crate::models::access_control_list::AccessControlListColumn;
+use crate::models::problem_sets::ProblemSetSettings;
 use crate::models::users::LoggedUser;
 use crate::services::group::utils::is_in_group;
+use crate::services::problem_set::utils::get_settings as get_problem_set_settings;
 use actix_web::web;
 use diesel::prelude::*;
 use server_core::database::{db_connection, Pool};
@@ -47,6 +49,60 @@ pub fn is_manager(conn: &PgConnection, user_id: i32, region: String) -> ServiceR
     }
 }
 
+// have right to manage submissions and reports in region
+pub fn check_manage_right(
+    pool: web::Data<Pool>,
+    logged_user: LoggedUser,
+    region: String,
+) -> ServiceResult<()> {
+    let conn = &db_connection(&pool)?;
+
+    if let Some(user) = logged_user.0 {
+        if user.role == "sup" || user.role == "admin" || is_manager(conn, user.id, region)? {
+            Ok(())
+        } else {
+            let hint = "No permission.".to_string();
+            Err(ServiceError::BadRequest(hint))
+        }
+    } else {
+        Err(ServiceError::Unauthorized)
+    }
+}
+
+fn check_problem_set_right(
+    conn: &PgConnection,
+    logged_user: LoggedUser,
+    region: String,
+    settings: &ProblemSetSettings,
+    is_solving: bool,
+) -> ServiceResult<()> {
+    if let Some(user) = &logged_user.0 {
+        if user.role == "sup" || user.role == "admin" || is_manager(conn, user.id, region.clone())?
+        {
+            return Ok(());
+        }
+    }
+
+    let cur_time = get_cur_naive_date_time();
+    if !settings.is_open(cur_time) {
+        let hint = "Problem set is not open yet.".to_owned();
+        return Err(ServiceError::UnauthorizedWithHint(hint));
+    }
+    if is_solving && !settings.can_submit(cur_time) {
+        let hint = "Problem set is closed.".to_owned();
+        return Err(ServiceError::UnauthorizedWithHint(hint));
+    }
+
+    if settings.restrict_to_groups {
+        match logged_user.0 {
+            Some(user) => check_acl(conn, user.id, region),
+            None => Err(ServiceError::Unauthorized),
+        }
+    } else {
+        Ok(())
+    }
+}
+
 // have right to get colume to see problem list
 pub fn check_view_right(
     pool: web::Data<Pool>,
@@ -55,14 +111,8 @@ pub fn check_view_right(
 ) -> ServiceResult<()> {
     let conn = &db_connection(&pool)?;
 
-    use crate::schema::problem_sets as problem_sets_schema;
-    if problem_sets_schema::table
-        .filter(problem_sets_schema::region.eq(region.clone()))
-        .count()
-        .get_result::<i64>(conn)?
-        == 1
-    {
-        return Ok(());
+    if let Some(settings) = get_problem_set_settings(conn, &region)? {
+        return check_problem_set_right(conn, logged_user, region, &settings, false);
     }
 
     if let Some(user) = logged_user.0.clone() {
@@ -121,14 +171,8 @@ pub fn check_solve_right(
 ) -> ServiceResult<()> {
     let conn = &db_connection(&pool)?;
 
-    use crate::schema::problem_sets as problem_sets_schema;
-    if problem_sets_schema::table
-        .filter(problem_sets_schema::region.eq(region.clone()))
-        .count()
-        .get_result::<i64>(conn)?
-        == 1
-    {
-        return Ok(());
+    if let Some(settings) = get_problem_set_settings(conn, &region)? {
+        return check_problem_set_right(conn, logged_user, region, &settings, true);
     }
 
     if let Some(user) = logged_user.0.clone() {
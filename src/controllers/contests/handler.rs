@@ -2,6 +2,7 @@ use crate::auth::region::*;
 use crate::models::contests::*;
 use crate::models::users::LoggedUser;
 use crate::services::contest;
+use crate::services::rank::export::{self, ExportFormat, UnratedPolicy};
 use actix_web::{delete, get, post, put, web, HttpResponse};
 use chrono::*;
 use server_core::database::Pool;
@@ -153,6 +154,153 @@ pub async fn get_acm_rank(
     Ok(HttpResponse::Ok().json(&res))
 }
 
+#[post("/{region}/rank_acm/rebuild")]
+pub async fn rebuild_acm_rank(
+    web::Path(region): web::Path<String>,
+    pool: web::Data<Pool>,
+    logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || contest::rebuild_acm_rank(region, pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[post("/{region}/unfreeze")]
+pub async fn unfreeze(
+    web::Path(region): web::Path<String>,
+    pool: web::Data<Pool>,
+    logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || contest::unfreeze(region, pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[get("/{region}/resolver")]
+pub async fn get_resolver_replay(
+    web::Path(region): web::Path<String>,
+    pool: web::Data<Pool>,
+    logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+    check_view_right(pool.clone(), logged_user.clone(), region.clone())?;
+    let is_admin = match &logged_user.0 {
+        Some(cur_user) => cur_user.role == "sup" || cur_user.role == "admin",
+        None => false,
+    };
+
+    let res = web::block(move || contest::get_resolver_replay(region, is_admin, pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[derive(Deserialize)]
+pub struct ExportRankParams {
+    format: ExportFormat,
+    #[serde(default)]
+    unrated: UnratedPolicy,
+}
+
+#[get("/{region}/rank/export")]
+pub async fn export_rank(
+    web::Path(region): web::Path<String>,
+    query: web::Query<ExportRankParams>,
+    pool: web::Data<Pool>,
+    logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || export::export(region, query.format, query.unrated, pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(HttpResponse::Ok()
+        .content_type(res.content_type)
+        .header(
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", res.file_name),
+        )
+        .body(res.body))
+}
+
+#[get("/{region}/rank_oi")]
+pub async fn get_oi_rank(
+    web::Path(region): web::Path<String>,
+    pool: web::Data<Pool>,
+    logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+    check_view_right(pool.clone(), logged_user.clone(), region.clone())?;
+
+    let res = web::block(move || contest::get_oi_rank(region, pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[get("/{region}/rank_ioi")]
+pub async fn get_ioi_rank(
+    web::Path(region): web::Path<String>,
+    pool: web::Data<Pool>,
+    logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+    check_view_right(pool.clone(), logged_user.clone(), region.clone())?;
+
+    let res = web::block(move || contest::get_ioi_rank(region, pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
 #[delete("/{region}")]
 pub async fn delete(
     web::Path(region): web::Path<String>,
@@ -9,6 +9,12 @@ pub fn route(cfg: &mut web::ServiceConfig) {
             .service(handler::get_contest_list)
             .service(handler::register)
             .service(handler::get_acm_rank)
+            .service(handler::rebuild_acm_rank)
+            .service(handler::unfreeze)
+            .service(handler::get_resolver_replay)
+            .service(handler::export_rank)
+            .service(handler::get_oi_rank)
+            .service(handler::get_ioi_rank)
             .service(handler::delete)
             .service(handler::update)
             .service(handler::get)
@@ -1,7 +1,12 @@
 use crate::judge_actor::JudgeActorAddr;
+use crate::models::users::LoggedUser;
 use crate::services::judge_server::*;
+use crate::services::submission::progress;
 use actix_identity::Identity;
 use actix_web::{delete, get, post, put, web, HttpRequest, HttpResponse};
+use judge_client::models::JudgeProgressReport;
+use judge_client::JudgeClient;
+use server_core::database::Pool;
 use server_core::errors::ServiceError;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -25,14 +30,20 @@ pub async fn handle_heartbeat(
     body: web::Json<HeartbeatBody>,
     req: HttpRequest,
     judge_actor: web::Data<JudgeActorAddr>,
+    judge_client: web::Data<JudgeClient>,
+    pool: web::Data<Pool>,
 ) -> Result<HttpResponse, ServiceError> {
-    let token = req
+    let token = match req
         .headers()
         .get("x-judge-server-token")
-        .unwrap()
-        .to_str()
-        .unwrap()
-        .to_string();
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(token) => token.to_string(),
+        None => {
+            let hint = "Missing X-Judge-Server-Token header.".to_string();
+            return Err(ServiceError::UnauthorizedWithHint(hint));
+        }
+    };
 
     record_server_info(
         body.judger_version.clone(),
@@ -43,6 +54,8 @@ pub async fn handle_heartbeat(
         body.service_url.clone(),
         token.clone(),
         judge_actor,
+        judge_client,
+        pool,
     )
     .await?;
 
@@ -61,3 +74,182 @@ pub async fn get_server_info(id: Identity) -> Result<HttpResponse, ServiceError>
         .await
         .map(|res| HttpResponse::Ok().json(&res))
 }
+
+#[derive(Deserialize)]
+pub struct CreateJudgeServerBody {
+    name: String,
+    token: Option<String>,
+}
+
+#[post("")]
+pub async fn create(
+    body: web::Json<CreateJudgeServerBody>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || registry::create(body.name.clone(), body.token.clone(), pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[get("")]
+pub async fn get_list(
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || registry::get_list(pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[derive(Deserialize)]
+pub struct UpdateJudgeServerBody {
+    new_name: Option<String>,
+    new_state: Option<String>,
+}
+
+#[put("/{id}")]
+pub async fn update(
+    web::Path(id): web::Path<i32>,
+    body: web::Json<UpdateJudgeServerBody>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || {
+        registry::update(id, body.new_name.clone(), body.new_state.clone(), pool)
+    })
+    .await
+    .map_err(|e| {
+        eprintln!("{}", e);
+        e
+    })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[delete("/{id}")]
+pub async fn delete(
+    web::Path(id): web::Path<i32>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || registry::delete(id, pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[derive(Deserialize)]
+pub struct PruneDeadParams {
+    timeout_in_sec: Option<u64>,
+}
+
+#[delete("/dead")]
+pub async fn prune_dead(
+    query: web::Query<PruneDeadParams>,
+    logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = registry::prune_dead(query.timeout_in_sec.unwrap_or(60))?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+/// Receives test cases finished by judge servers, see `JUDGE_PROGRESS_CALLBACK_URL`.
+#[post("/progress")]
+pub async fn report_progress(
+    body: web::Json<JudgeProgressReport>,
+    req: HttpRequest,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    let token = match req
+        .headers()
+        .get("x-judge-server-token")
+        .and_then(|value| value.to_str().ok())
+    {
+        Some(token) => token.to_string(),
+        None => {
+            let hint = "Missing X-Judge-Server-Token header.".to_string();
+            return Err(ServiceError::UnauthorizedWithHint(hint));
+        }
+    };
+
+    let judge_server = {
+        let pool = pool.clone();
+        web::block(move || registry::get_by_token_header(token, pool)).await?
+    };
+    let judge_server_id = match judge_server {
+        Some(judge_server) if judge_server.state != "Disabled" => judge_server.id,
+        _ => {
+            let hint = "Unknown judge server token.".to_string();
+            return Err(ServiceError::UnauthorizedWithHint(hint));
+        }
+    };
+
+    let res = web::block(move || progress::record(body.into_inner(), judge_server_id, pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
@@ -6,6 +6,12 @@ pub fn route(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/judge_servers")
             .service(handler::handle_heartbeat)
-            .service(handler::get_server_info),
+            .service(handler::report_progress)
+            .service(handler::get_server_info)
+            .service(handler::prune_dead)
+            .service(handler::create)
+            .service(handler::get_list)
+            .service(handler::update)
+            .service(handler::delete),
     );
 }
@@ -0,0 +1,135 @@
+use crate::models::languages::{CheckerConfig, LanguageConfig};
+use crate::models::users::LoggedUser;
+use crate::services::language;
+use actix_web::{delete, get, post, put, web, HttpResponse};
+use server_core::database::Pool;
+use server_core::errors::ServiceError;
+
+#[derive(Deserialize)]
+pub struct CreateLanguageBody {
+    name: String,
+    description: String,
+    config: LanguageConfig,
+    time_multiplier: Option<f64>,
+    memory_multiplier: Option<f64>,
+    checker_config: Option<CheckerConfig>,
+}
+
+#[post("")]
+pub async fn create(
+    body: web::Json<CreateLanguageBody>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || {
+        language::create(
+            body.name.clone(),
+            body.description.clone(),
+            body.config.clone(),
+            body.time_multiplier,
+            body.memory_multiplier,
+            body.checker_config.clone(),
+            pool,
+        )
+    })
+    .await
+    .map_err(|e| {
+        eprintln!("{}", e);
+        e
+    })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[get("")]
+pub async fn get_list(pool: web::Data<Pool>) -> Result<HttpResponse, ServiceError> {
+    let res = web::block(move || language::get_list(pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[derive(Deserialize)]
+pub struct UpdateLanguageBody {
+    new_description: Option<String>,
+    new_config: Option<LanguageConfig>,
+    new_time_multiplier: Option<f64>,
+    new_memory_multiplier: Option<f64>,
+    new_is_enabled: Option<bool>,
+    new_checker_config: Option<CheckerConfig>,
+}
+
+#[put("/{name}")]
+pub async fn update(
+    web::Path(name): web::Path<String>,
+    body: web::Json<UpdateLanguageBody>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || {
+        language::update(
+            name,
+            body.new_description.clone(),
+            body.new_config.clone(),
+            body.new_time_multiplier,
+            body.new_memory_multiplier,
+            body.new_is_enabled,
+            body.new_checker_config.clone(),
+            pool,
+        )
+    })
+    .await
+    .map_err(|e| {
+        eprintln!("{}", e);
+        e
+    })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[delete("/{name}")]
+pub async fn delete(
+    web::Path(name): web::Path<String>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || language::delete(name, pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
@@ -2,6 +2,7 @@ pub mod announcements;
 pub mod contests;
 pub mod groups;
 pub mod judge_servers;
+pub mod languages;
 pub mod problem_sets;
 pub mod problem_tags;
 pub mod problems;
@@ -9,3 +10,4 @@ pub mod regions;
 pub mod samples;
 pub mod submissions;
 pub mod users;
+mod utils;
@@ -1,6 +1,9 @@
+use crate::models::problem_sets::ProblemSetSettings;
 use crate::models::users::LoggedUser;
-use crate::services::problem_set;
+use crate::services::contest;
+use crate::services::problem_set::{self, grades};
 use actix_web::{delete, get, post, put, web, HttpResponse};
+use chrono::NaiveDateTime;
 use server_core::database::Pool;
 use server_core::errors::ServiceError;
 
@@ -10,6 +13,8 @@ pub struct CreateProblemSetBody {
     title: String,
     introduction: Option<String>,
     can_view_testcases: bool,
+    #[serde(default)]
+    settings: ProblemSetSettings,
 }
 
 #[post("")]
@@ -33,6 +38,7 @@ pub async fn create(
             body.title.clone(),
             body.introduction.clone(),
             body.can_view_testcases,
+            body.settings.clone(),
             pool,
         )
     })
@@ -99,6 +105,7 @@ pub struct UpdateProblemSetBody {
     new_title: Option<String>,
     new_introduction: Option<String>,
     new_can_view_testcases: Option<bool>,
+    new_settings: Option<ProblemSetSettings>,
 }
 
 #[put("/{region}")]
@@ -123,6 +130,7 @@ pub async fn update(
             body.new_title.clone(),
             body.new_introduction.clone(),
             body.new_can_view_testcases,
+            body.new_settings.clone(),
             pool,
         )
     })
@@ -134,3 +142,139 @@ pub async fn update(
 
     Ok(HttpResponse::Ok().json(&res))
 }
+
+#[derive(Deserialize)]
+pub struct GetGradesParams {
+    group_id: Option<i32>,
+    /// Submissions after it are marked as late, the close time of the problem set by default
+    deadline: Option<NaiveDateTime>,
+    /// Json by default, or csv
+    format: Option<String>,
+}
+
+#[get("/{region}/grades")]
+pub async fn get_grades(
+    web::Path(region): web::Path<String>,
+    query: web::Query<GetGradesParams>,
+    pool: web::Data<Pool>,
+    logged_user: LoggedUser,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let is_csv = match query.format.as_deref() {
+        None | Some("json") => false,
+        Some("csv") => true,
+        Some(_) => {
+            let hint = "Unsupported format.".to_string();
+            return Err(ServiceError::BadRequest(hint));
+        }
+    };
+
+    let file_name = format!("{}-grades.csv", region);
+    let res = web::block(move || grades::get_grades(region, query.group_id, query.deadline, pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    if is_csv {
+        Ok(HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .header(
+                "Content-Disposition",
+                format!("attachment; filename=\"{}\"", file_name),
+            )
+            .body(grades::to_csv(&res)))
+    } else {
+        Ok(HttpResponse::Ok().json(&res))
+    }
+}
+
+#[derive(Deserialize)]
+pub struct InsertGroupIntoProblemSetBody {
+    group_ids: Vec<i32>,
+}
+
+#[post("/{region}/group")]
+pub async fn insert_groups(
+    web::Path(region): web::Path<String>,
+    body: web::Json<InsertGroupIntoProblemSetBody>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || problem_set::insert_groups(region, body.group_ids.clone(), pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[get("/{region}/group")]
+pub async fn get_linked_groups(
+    web::Path(region): web::Path<String>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || contest::get_linked_groups(region, pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[delete("/{region}/group/{group_id}")]
+pub async fn delete_group(
+    web::Path((region, group_id)): web::Path<(String, i32)>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || problem_set::delete_group(region, group_id, pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
@@ -8,6 +8,10 @@ pub fn route(cfg: &mut web::ServiceConfig) {
             .service(handler::create)
             .service(handler::get_set_list)
             .service(handler::delete)
-            .service(handler::update),
+            .service(handler::update)
+            .service(handler::get_grades)
+            .service(handler::insert_groups)
+            .service(handler::get_linked_groups)
+            .service(handler::delete_group),
     );
 }
@@ -1,10 +1,15 @@
-use crate::models::problems::{ProblemContents, ProblemSettings};
+use crate::controllers::utils::save_upload;
+use crate::judge_actor::JudgeActorAddr;
+use crate::models::problems::{ImportFormat, ProblemContents, ProblemSettings};
+use crate::models::rejudge_records::RejudgeFilter;
+use crate::models::test_data_programs::TestDataProgramKind;
 use crate::models::users::LoggedUser;
 use crate::services::problem;
+use crate::services::submission::rejudge;
 use actix_files::NamedFile;
 use actix_multipart::Multipart;
 use actix_web::{delete, get, post, put, web, HttpResponse};
-use futures::{StreamExt, TryStreamExt};
+use judge_client::JudgeClient;
 use serde_qs::actix::QsQuery;
 use server_core::database::Pool;
 use server_core::errors::ServiceError;
@@ -12,7 +17,7 @@ use server_core::errors::ServiceError;
 #[post("/batch_create")]
 pub async fn batch_create(
     logged_user: LoggedUser,
-    mut payload: Multipart,
+    payload: Multipart,
     pool: web::Data<Pool>,
 ) -> Result<HttpResponse, ServiceError> {
     if logged_user.0.is_none() {
@@ -24,29 +29,9 @@ pub async fn batch_create(
         return Err(ServiceError::BadRequest(hint));
     }
 
-    let mut bytes = web::BytesMut::new();
-    // iterate over multipart stream
-    let mut filename = None;
-    while let Ok(Some(mut field)) = payload.try_next().await {
-        let content_type = field.content_disposition().unwrap();
-        if filename.is_none() {
-            filename = Some(content_type.get_filename().unwrap().to_owned());
-        } else {
-            // only accept one file
-            if filename.clone().unwrap() != content_type.get_filename().unwrap() {
-                continue;
-            }
-        }
-
-        // Field in turn is stream of *Bytes* object
-        while let Some(chunk) = field.next().await {
-            let data = chunk.unwrap();
-            // filesystem operations are blocking, we have to use threadpool
-            bytes.extend_from_slice(&data);
-        }
-    }
-
-    let res = web::block(move || problem::batch_create(&bytes, pool))
+    let upload_path = save_upload(payload).await?;
+
+    let res = web::block(move || problem::batch_create(upload_path, pool))
         .await
         .map_err(|e| {
             eprintln!("{}", e);
@@ -56,6 +41,69 @@ pub async fn batch_create(
     Ok(HttpResponse::Ok().json(res))
 }
 
+#[derive(Deserialize)]
+pub struct ImportProblemsParams {
+    format: ImportFormat,
+}
+
+#[post("/import")]
+pub async fn import(
+    query: web::Query<ImportProblemsParams>,
+    logged_user: LoggedUser,
+    payload: Multipart,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let upload_path = save_upload(payload).await?;
+
+    let res = web::block(move || problem::import(query.format, upload_path, pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(HttpResponse::Ok().json(res))
+}
+
+#[derive(Deserialize)]
+pub struct ExportProblemsParams {
+    ids: Vec<i32>,
+}
+
+#[get("/export")]
+pub async fn export(
+    query: QsQuery<ExportProblemsParams>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<NamedFile, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || problem::export(query.ids.clone(), pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(res)
+}
+
 #[derive(Deserialize)]
 pub struct ChangeReleaseStateBody {
     target_state: bool,
@@ -291,11 +339,257 @@ pub async fn update(
     Ok(HttpResponse::Ok().json(&res))
 }
 
+#[derive(Deserialize)]
+pub struct InsertTestCasesParams {
+    #[serde(default)]
+    force: bool,
+}
+
 #[post("/{id}/test_case")]
 pub async fn insert_test_cases(
+    web::Path(id): web::Path<i32>,
+    query: web::Query<InsertTestCasesParams>,
+    logged_user: LoggedUser,
+    payload: Multipart,
+    pool: web::Data<Pool>,
+    judge_client: web::Data<JudgeClient>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let upload_path = save_upload(payload).await?;
+
+    let res = web::block(move || {
+        problem::insert_test_cases(id, upload_path, query.force, pool, judge_client)
+    })
+    .await
+    .map_err(|e| {
+        eprintln!("{}", e);
+        e
+    })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[get("/{id}/test_case")]
+pub async fn get_test_cases(
+    web::Path(id): web::Path<i32>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<NamedFile, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || problem::get_test_cases(id, pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(res)
+}
+
+#[get("/{id}/test_case/versions")]
+pub async fn get_test_case_versions(
+    web::Path(id): web::Path<i32>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || problem::test_case_version::get_list(id, pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[derive(Deserialize)]
+pub struct DiffTestCaseVersionsParams {
+    from: String,
+    to: String,
+}
+
+#[get("/{id}/test_case/versions/diff")]
+pub async fn diff_test_case_versions(
+    web::Path(id): web::Path<i32>,
+    query: web::Query<DiffTestCaseVersionsParams>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || {
+        problem::test_case_version::diff(id, query.from.clone(), query.to.clone(), pool)
+    })
+    .await
+    .map_err(|e| {
+        eprintln!("{}", e);
+        e
+    })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[post("/{id}/test_case/versions/{version}/rollback")]
+pub async fn rollback_test_cases(
+    web::Path((id, version)): web::Path<(i32, String)>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || problem::test_case_version::rollback(id, version, pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(HttpResponse::Ok().json(res))
+}
+
+#[derive(Deserialize)]
+pub struct UploadCheckerBody {
+    language: String,
+    src: String,
+}
+
+#[put("/{id}/checker")]
+pub async fn upload_checker(
+    web::Path(id): web::Path<i32>,
+    body: web::Json<UploadCheckerBody>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+    judge_client: web::Data<JudgeClient>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || {
+        problem::checker::upload(
+            id,
+            body.language.clone(),
+            body.src.clone(),
+            pool,
+            judge_client,
+        )
+    })
+    .await
+    .map_err(|e| {
+        eprintln!("{}", e);
+        e
+    })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[get("/{id}/checker")]
+pub async fn get_checker(
+    web::Path(id): web::Path<i32>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || problem::checker::get(id, pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[derive(Deserialize)]
+pub struct SaveTestDataProgramBody {
+    language: String,
+    src: String,
+}
+
+#[put("/{id}/test_data_programs/{kind}")]
+pub async fn save_test_data_program(
+    web::Path((id, kind)): web::Path<(i32, TestDataProgramKind)>,
+    body: web::Json<SaveTestDataProgramBody>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || {
+        problem::test_data::save_program(id, kind, body.language.clone(), body.src.clone(), pool)
+    })
+    .await
+    .map_err(|e| {
+        eprintln!("{}", e);
+        e
+    })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[get("/{id}/test_data_programs")]
+pub async fn get_test_data_programs(
     web::Path(id): web::Path<i32>,
     logged_user: LoggedUser,
-    mut payload: Multipart,
     pool: web::Data<Pool>,
 ) -> Result<HttpResponse, ServiceError> {
     if logged_user.0.is_none() {
@@ -307,25 +601,32 @@ pub async fn insert_test_cases(
         return Err(ServiceError::BadRequest(hint));
     }
 
-    let mut bytes = web::BytesMut::new();
-    let mut filename = None;
-    while let Ok(Some(mut field)) = payload.try_next().await {
-        let content_type = field.content_disposition().unwrap();
-        if filename.is_none() {
-            filename = Some(content_type.get_filename().unwrap().to_owned());
-        } else {
-            if filename.clone().unwrap() != content_type.get_filename().unwrap() {
-                continue;
-            }
-        }
+    let res = web::block(move || problem::test_data::get_programs(id, pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
 
-        while let Some(chunk) = field.next().await {
-            let data = chunk.unwrap();
-            bytes.extend_from_slice(&data);
-        }
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[delete("/{id}/test_data_programs/{kind}")]
+pub async fn delete_test_data_program(
+    web::Path((id, kind)): web::Path<(i32, TestDataProgramKind)>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
     }
 
-    let res = web::block(move || problem::insert_test_cases(id, &bytes, pool))
+    let res = web::block(move || problem::test_data::delete_program(id, kind, pool))
         .await
         .map_err(|e| {
             eprintln!("{}", e);
@@ -335,11 +636,14 @@ pub async fn insert_test_cases(
     Ok(HttpResponse::Ok().json(res))
 }
 
-#[get("/{id}/test_case")]
-pub async fn get_test_cases(
+#[post("/{id}/rejudge")]
+pub async fn rejudge_submissions(
     web::Path(id): web::Path<i32>,
+    body: Option<web::Json<RejudgeFilter>>,
     logged_user: LoggedUser,
-) -> Result<NamedFile, ServiceError> {
+    pool: web::Data<Pool>,
+    judge_actor: web::Data<JudgeActorAddr>,
+) -> Result<HttpResponse, ServiceError> {
     if logged_user.0.is_none() {
         return Err(ServiceError::Unauthorized);
     }
@@ -349,12 +653,15 @@ pub async fn get_test_cases(
         return Err(ServiceError::BadRequest(hint));
     }
 
-    let res = web::block(move || problem::get_test_cases(id))
+    let mut filter = body.map(|body| body.into_inner()).unwrap_or_default();
+    filter.problem_id = Some(id);
+
+    let res = web::block(move || rejudge::rejudge(filter, cur_user.id, pool, judge_actor))
         .await
         .map_err(|e| {
             eprintln!("{}", e);
             e
         })?;
 
-    Ok(res)
+    Ok(HttpResponse::Ok().json(&res))
 }
@@ -6,6 +6,8 @@ pub fn route(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/problems")
             .service(handler::batch_create)
+            .service(handler::import)
+            .service(handler::export)
             //.service(handler::change_release_state)
             .service(handler::get_list)
             .service(handler::get_title)
@@ -14,6 +16,15 @@ pub fn route(cfg: &mut web::ServiceConfig) {
             .service(handler::create)
             .service(handler::update)
             .service(handler::insert_test_cases)
-            .service(handler::get_test_cases),
+            .service(handler::get_test_cases)
+            .service(handler::get_test_case_versions)
+            .service(handler::diff_test_case_versions)
+            .service(handler::rollback_test_cases)
+            .service(handler::upload_checker)
+            .service(handler::get_checker)
+            .service(handler::save_test_data_program)
+            .service(handler::get_test_data_programs)
+            .service(handler::delete_test_data_program)
+            .service(handler::rejudge_submissions),
     );
 }
@@ -1,12 +1,16 @@
 use crate::auth::region::*;
 use crate::judge_actor::JudgeActorAddr;
+use crate::models::rejudge_records::RejudgeFilter;
 use crate::models::users::LoggedUser;
+use crate::services::plagiarism;
 use crate::services::region;
+use crate::services::submission::rejudge;
 use actix_files::NamedFile;
 use actix_web::{delete, get, post, put, web, HttpResponse};
 use serde_qs::actix::QsQuery;
 use server_core::database::Pool;
 use server_core::errors::ServiceError;
+use uuid::Uuid;
 
 #[derive(Deserialize)]
 pub struct GetRegionListParams {
@@ -217,3 +221,155 @@ pub async fn get_linked_problem_test_case(
 
     Ok(res)
 }
+
+#[post("/{region}/rejudge")]
+pub async fn rejudge_submissions(
+    web::Path(region): web::Path<String>,
+    body: Option<web::Json<RejudgeFilter>>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+    judge_actor: web::Data<JudgeActorAddr>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let mut filter = body.map(|body| body.into_inner()).unwrap_or_default();
+    filter.region = Some(region);
+
+    let res = web::block(move || rejudge::rejudge(filter, cur_user.id, pool, judge_actor))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+fn default_threshold() -> f64 {
+    0.6
+}
+
+#[derive(Deserialize)]
+pub struct CheckPlagiarismBody {
+    problem_id: i32,
+    /// Compares only accepted submissions, or all finished ones
+    #[serde(default)]
+    only_accepted: bool,
+    #[serde(default = "default_threshold")]
+    threshold: f64,
+}
+
+#[post("/{region}/plagiarism")]
+pub async fn check_plagiarism(
+    web::Path(region): web::Path<String>,
+    body: web::Json<CheckPlagiarismBody>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    check_manage_right(pool.clone(), logged_user.clone(), region.clone())?;
+    let operator_id = logged_user.0.unwrap().id;
+
+    let res = web::block(move || {
+        plagiarism::check(
+            region,
+            body.problem_id,
+            body.only_accepted,
+            body.threshold,
+            operator_id,
+            pool,
+        )
+    })
+    .await
+    .map_err(|e| {
+        eprintln!("{}", e);
+        e
+    })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[derive(Deserialize)]
+pub struct GetPlagiarismReportListParams {
+    problem_id_filter: Option<i32>,
+    limit: i32,
+    offset: i32,
+}
+
+#[get("/{region}/plagiarism")]
+pub async fn get_plagiarism_report_list(
+    web::Path(region): web::Path<String>,
+    query: web::Query<GetPlagiarismReportListParams>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    check_manage_right(pool.clone(), logged_user, region.clone())?;
+
+    let res = web::block(move || {
+        plagiarism::get_report_list(
+            region,
+            query.problem_id_filter,
+            query.limit,
+            query.offset,
+            pool,
+        )
+    })
+    .await
+    .map_err(|e| {
+        eprintln!("{}", e);
+        e
+    })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[get("/{region}/plagiarism/{report_id}")]
+pub async fn get_plagiarism_report(
+    web::Path((region, report_id)): web::Path<(String, i32)>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    check_manage_right(pool.clone(), logged_user, region.clone())?;
+
+    let res = web::block(move || plagiarism::get_report(region, report_id, pool))
+        .await
+        .map_err(|e| {
+            eprintln!("{}", e);
+            e
+        })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[derive(Deserialize)]
+pub struct GetPlagiarismDiffParams {
+    first: Uuid,
+    second: Uuid,
+}
+
+#[get("/{region}/plagiarism/{report_id}/diff")]
+pub async fn get_plagiarism_diff(
+    web::Path((region, report_id)): web::Path<(String, i32)>,
+    query: web::Query<GetPlagiarismDiffParams>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    check_manage_right(pool.clone(), logged_user, region.clone())?;
+
+    let res = web::block(move || {
+        plagiarism::get_diff(region, report_id, query.first, query.second, pool)
+    })
+    .await
+    .map_err(|e| {
+        eprintln!("{}", e);
+        e
+    })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
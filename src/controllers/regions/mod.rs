@@ -7,10 +7,15 @@ pub fn route(cfg: &mut web::ServiceConfig) {
         web::scope("/regions")
             //.service(handler::get_list)
             .service(handler::insert_problems)
+            .service(handler::check_plagiarism)
+            .service(handler::get_plagiarism_report_list)
+            .service(handler::get_plagiarism_report)
+            .service(handler::get_plagiarism_diff)
             .service(handler::get_linked_problem_column_list)
             .service(handler::get_linked_problem)
             .service(handler::create_submission)
             .service(handler::delete_problem)
-            .service(handler::get_linked_problem_test_case),
+            .service(handler::get_linked_problem_test_case)
+            .service(handler::rejudge_submissions),
     );
 }
@@ -1,7 +1,13 @@
+use crate::auth::region::is_manager;
 use crate::judge_actor::JudgeActorAddr;
+use crate::models::rejudge_records::RejudgeFilter;
 use crate::models::users::LoggedUser;
+use crate::services::contest::utils::is_verdict_hidden;
 use crate::services::submission;
+use crate::services::submission::events::{subscribe as subscribe_events, SubscriptionFilter};
+use crate::services::submission::{progress, rejudge};
 use actix_web::{get, post, put, web, HttpResponse};
+use futures::StreamExt;
 use server_core::database::{db_connection, Pool};
 use server_core::errors::ServiceError;
 use uuid::Uuid;
@@ -72,7 +78,8 @@ pub async fn get(
         return Err(ServiceError::BadRequest(hint));
     }
 
-    let res = web::block(move || submission::get(submission_id, pool))
+    let is_admin = cur_user.role == "sup" || cur_user.role == "admin";
+    let res = web::block(move || submission::get(submission_id, is_admin, pool))
         .await
         .map_err(|e| {
             eprintln!("{}", e);
@@ -100,6 +107,8 @@ pub async fn get_list(
     if logged_user.0.is_none() {
         return Err(ServiceError::Unauthorized);
     }
+    let cur_user = logged_user.0.unwrap();
+    let is_admin = cur_user.role == "sup" || cur_user.role == "admin";
 
     let res = web::block(move || {
         submission::get_list(
@@ -108,6 +117,180 @@ pub async fn get_list(
             query.user_id_filter.clone(),
             query.limit,
             query.offset,
+            is_admin,
+            pool,
+        )
+    })
+    .await
+    .map_err(|e| {
+        eprintln!("{}", e);
+        e
+    })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[derive(Deserialize)]
+pub struct SubscribeParams {
+    region: Option<String>,
+}
+
+/// Streams state changes of submissions as Server-Sent Events,
+/// own submissions by default, or all submissions in a managed region.
+#[get("/subscribe")]
+pub async fn subscribe(
+    query: web::Query<SubscribeParams>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+
+    let filter = if let Some(region) = query.region.clone() {
+        if cur_user.role != "sup" && cur_user.role != "admin" {
+            let user_id = cur_user.id;
+            let conn_region = region.clone();
+            let is_region_manager = web::block(move || {
+                let conn = &db_connection(&pool)?;
+                is_manager(conn, user_id, conn_region)
+            })
+            .await
+            .map_err(|e| {
+                eprintln!("{}", e);
+                e
+            })?;
+
+            if !is_region_manager {
+                let hint = "No permission.".to_string();
+                return Err(ServiceError::BadRequest(hint));
+            }
+        }
+        SubscriptionFilter::Region(region)
+    } else {
+        SubscriptionFilter::User(cur_user.id)
+    };
+
+    let receiver = subscribe_events(filter);
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .set_header("Cache-Control", "no-cache")
+        .streaming(receiver.map(Ok::<_, actix_web::Error>)))
+}
+
+#[get("/{id}/progress")]
+pub async fn get_progress(
+    web::Path(submission_id): web::Path<Uuid>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+
+    let res = web::block(move || {
+        let conn = &db_connection(&pool)?;
+
+        use crate::schema::submissions as submissions_schema;
+        use diesel::prelude::*;
+
+        let (user_id, region): (i32, Option<String>) = submissions_schema::table
+            .filter(submissions_schema::id.eq(submission_id))
+            .select((submissions_schema::user_id, submissions_schema::region))
+            .first(conn)?;
+
+        let is_admin = cur_user.role == "sup" || cur_user.role == "admin";
+        if cur_user.id != user_id && !is_admin {
+            let hint = "No permission.".to_string();
+            return Err(ServiceError::BadRequest(hint));
+        }
+
+        let mut res = progress::get(submission_id)?;
+        if let (Some(progress), Some(region)) = (&mut res, region) {
+            if !is_admin && is_verdict_hidden(conn, &region)? {
+                progress.details.clear();
+            }
+        }
+
+        Ok(res)
+    })
+    .await
+    .map_err(|e| {
+        eprintln!("{}", e);
+        e
+    })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[post("/{id}/rejudge")]
+pub async fn rejudge_submission(
+    web::Path(submission_id): web::Path<Uuid>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+    judge_actor: web::Data<JudgeActorAddr>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || {
+        rejudge::rejudge(
+            RejudgeFilter {
+                submission_id: Some(submission_id),
+                ..Default::default()
+            },
+            cur_user.id,
+            pool,
+            judge_actor,
+        )
+    })
+    .await
+    .map_err(|e| {
+        eprintln!("{}", e);
+        e
+    })?;
+
+    Ok(HttpResponse::Ok().json(&res))
+}
+
+#[derive(Deserialize)]
+pub struct GetRejudgeRecordListParams {
+    rejudge_id_filter: Option<Uuid>,
+    submission_id_filter: Option<Uuid>,
+    limit: i32,
+    offset: i32,
+}
+
+#[get("/rejudge_records")]
+pub async fn get_rejudge_record_list(
+    query: web::Query<GetRejudgeRecordListParams>,
+    logged_user: LoggedUser,
+    pool: web::Data<Pool>,
+) -> Result<HttpResponse, ServiceError> {
+    if logged_user.0.is_none() {
+        return Err(ServiceError::Unauthorized);
+    }
+    let cur_user = logged_user.0.unwrap();
+    if cur_user.role != "sup" && cur_user.role != "admin" {
+        let hint = "No permission.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let res = web::block(move || {
+        rejudge::get_record_list(
+            query.rejudge_id_filter,
+            query.submission_id_filter,
+            query.limit,
+            query.offset,
             pool,
         )
     })
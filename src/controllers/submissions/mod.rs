@@ -6,7 +6,11 @@ pub fn route(cfg: &mut web::ServiceConfig) {
     cfg.service(
         web::scope("/submissions")
             //.service(handler::create)
+            .service(handler::subscribe)
+            .service(handler::get_rejudge_record_list)
             .service(handler::get)
+            .service(handler::get_progress)
+            .service(handler::rejudge_submission)
             .service(handler::get_list),
     );
 }
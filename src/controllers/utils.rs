@@ -0,0 +1,83 @@
+use crate::services::problem::archive::too_large;
+use crate::statics::ARCHIVE_LIMITS;
+use actix_multipart::Multipart;
+use actix_web::web;
+use futures::{StreamExt, TryStreamExt};
+use server_core::errors::ServiceError;
+use std::fs::{self, File};
+use std::io::Write;
+use uuid::Uuid;
+
+async fn write_upload(mut payload: Multipart, path: String) -> Result<(), ServiceError> {
+    let limits = *ARCHIVE_LIMITS.read().unwrap();
+
+    let file_path = path.clone();
+    let mut file = web::block(move || {
+        fs::create_dir_all("data/tmp")?;
+        File::create(file_path).map_err(ServiceError::from)
+    })
+    .await?;
+
+    let mut size = 0;
+    let mut filename = None;
+    loop {
+        let mut field = match payload.try_next().await {
+            Ok(Some(field)) => field,
+            Ok(None) => break,
+            Err(e) => {
+                let hint = format!("Failed to receive the file: {}", e);
+                return Err(ServiceError::BadRequest(hint));
+            }
+        };
+        let field_filename = match field
+            .content_disposition()
+            .and_then(|content_disposition| content_disposition.get_filename().map(str::to_owned))
+        {
+            Some(field_filename) => field_filename,
+            None => {
+                let hint = "Every part of the upload should be a file with a name.".to_string();
+                return Err(ServiceError::BadRequest(hint));
+            }
+        };
+        if filename.is_none() {
+            filename = Some(field_filename);
+        } else if filename.as_ref() != Some(&field_filename) {
+            // only accept one file
+            continue;
+        }
+
+        while let Some(chunk) = field.next().await {
+            let data = chunk.map_err(|e| {
+                let hint = format!("Failed to receive the file: {}", e);
+                ServiceError::BadRequest(hint)
+            })?;
+            size += data.len() as u64;
+            if size > limits.max_size {
+                return Err(too_large(&limits));
+            }
+
+            // filesystem operations are blocking, we have to use threadpool
+            file = web::block(move || {
+                file.write_all(&data)?;
+                Ok::<_, ServiceError>(file)
+            })
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Saves the uploaded file to a temporary path chunk by chunk rather than keeping it in memory,
+/// uploads larger than the archive size limit are refused.
+pub async fn save_upload(payload: Multipart) -> Result<String, ServiceError> {
+    let path = format!("data/tmp/{}.upload", Uuid::new_v4().to_hyphenated());
+
+    match write_upload(payload, path.clone()).await {
+        Ok(()) => Ok(path),
+        Err(e) => {
+            fs::remove_file(path).unwrap_or({});
+            Err(e)
+        }
+    }
+}
@@ -1,13 +1,16 @@
+use super::queue::*;
+use super::scheduler::*;
 use super::statistics::*;
-use super::utils::*;
 use super::JudgeActor;
 use crate::models::*;
-//use crate::services::rank::utils::update_acm_rank_cache;
-//use crate::services::region::utils::get_self_type;
+use crate::services::rank::utils::{apply_acm_submission, invalidate_rank_caches};
+use crate::services::submission::utils::get_score;
+use crate::services::submission::{events, progress, rejudge};
 use crate::statics::JUDGE_SERVER_INFOS;
-use crate::statics::WAITING_QUEUE;
 use actix::prelude::*;
 use diesel::prelude::*;
+use judge_client::models::ProgressCallback;
+use judge_client::JudgeClientError;
 use server_core::database::db_connection;
 use server_core::utils::time::get_cur_naive_date_time;
 
@@ -31,187 +34,229 @@ impl Handler<StartJudge> for JudgeActor {
             }
         };
 
-        let mut queue_size = {
-            let lock = WAITING_QUEUE.read().unwrap();
-            lock.len()
-        };
-        // log::info!("queue_size: {}", queue_size);
-        while queue_size != 0 {
-            let server = choose_judge_server();
-            if server.is_none() {
-                return;
+        loop {
+            // try servers in scheduling order until one of them gets a task
+            let mut claimed = None;
+            for server in schedule_judge_servers() {
+                match claim_next(&conn, &server.region_filter, &self.instance_id) {
+                    Ok(Some(task)) => {
+                        claimed = Some((server, task));
+                        break;
+                    }
+                    Ok(None) => (),
+                    Err(_) => {
+                        log::error!("Error claiming submission from queue.");
+                        return;
+                    }
+                }
             }
-            let (server_url, server_token) = server.unwrap();
-
-            let task_uuid = {
-                let mut lock = WAITING_QUEUE.write().unwrap();
-                lock.pop_front().clone().unwrap()
+            let (server, (task_uuid, setting_string, attempts)) = match claimed {
+                Some(claimed) => claimed,
+                None => {
+                    return;
+                }
             };
+            let (server_url, server_token) = (server.url, server.token);
 
-            let cur_state = match submissions_schema::table
-                .filter(submissions_schema::id.eq(task_uuid))
-                .select(submissions_schema::state)
-                .first::<String>(&conn)
+            match events::notify(&conn, task_uuid)
+                .and_then(|_| events::notify_queue_positions(&conn))
             {
-                Ok(cur_state) => cur_state,
-                Err(_) => {
-                    log::error!("Error loading setting_data from submissions.");
-                    return;
+                Ok(_) => (),
+                Err(_) => log::error!("Error publishing submission event."),
+            }
+
+            let raw_result = if attempts > self.max_attempts {
+                // the submission keeps failing or stalling judge servers
+                log::error!(
+                    "Giving up judging {} after {} attempts",
+                    task_uuid,
+                    attempts - 1
+                );
+                submissions::RawJudgeResult {
+                    err: Some("SystemError".to_owned()),
+                    data: serde_json::json!(format!("Judging failed {} times", attempts - 1)),
                 }
-            };
+            } else {
+                match serde_json::from_str::<submissions::JudgeSettings>(&setting_string) {
+                    Ok(mut settings) => {
+                        // progress is only known if judge servers report it
+                        if let Some(url) = self.progress_callback_url.clone() {
+                            settings.progress_callback = Some(ProgressCallback {
+                                url: url,
+                                submission_id: task_uuid.to_string(),
+                            });
+                            match progress::start(&conn, task_uuid, server.server_id) {
+                                Ok(_) => (),
+                                Err(_) => log::error!("Error tracking judge progress."),
+                            }
+                        }
 
-            if cur_state == *"Waiting" {
-                // run judge
-                let setting_string = match submissions_schema::table
-                    .filter(submissions_schema::id.eq(task_uuid))
-                    .select(submissions_schema::settings)
-                    .first::<String>(&conn)
-                {
-                    Ok(setting_string) => setting_string,
-                    Err(_) => {
-                        log::error!("Error loading setting_data from submissions.");
-                        return;
-                    }
-                };
+                        info!("sending request to {}", server_url);
+                        {
+                            let mut lock = JUDGE_SERVER_INFOS.write().unwrap();
+                            if let Some(server_info) = lock.get_mut(&server_url) {
+                                server_info.task_number += 1;
+                                server_info.assigned_count += 1;
+                                server_info.last_assigned_time = Some(std::time::SystemTime::now());
+                            }
+                        }
+                        let judge_result = self.client.judge(&server_url, &server_token, &settings);
+                        {
+                            let mut lock = JUDGE_SERVER_INFOS.write().unwrap();
+                            if let Some(server_info) = lock.get_mut(&server_url) {
+                                server_info.task_number -= 1;
+                            }
+                        }
+                        progress::finish(task_uuid);
 
-                let target = submissions_schema::table.filter(submissions_schema::id.eq(task_uuid));
-                match diesel::update(target)
-                    .set((submissions_schema::state.eq("Pending".to_owned()),))
-                    .execute(&conn)
-                {
-                    Ok(_) => (),
-                    Err(_) => {
-                        log::error!("Error changing submissions's state to Pending.");
-                        return;
-                    }
-                };
+                        match judge_result {
+                            Ok(raw_result) => raw_result,
+                            Err(JudgeClientError::MalformedResponse(reason)) => {
+                                log::error!("Malformed judge result for {}: {}", task_uuid, reason);
+                                submissions::RawJudgeResult {
+                                    err: Some("JudgeClientError".to_owned()),
+                                    data: serde_json::json!(reason),
+                                }
+                            }
+                            Err(e) => {
+                                log::error!("Error judging {} on {}: {}", task_uuid, server_url, e);
+                                match e {
+                                    JudgeClientError::ConnectionRefused
+                                    | JudgeClientError::AuthRejected => {
+                                        // keep the server out of scheduling until its next heartbeat
+                                        let mut lock = JUDGE_SERVER_INFOS.write().unwrap();
+                                        if let Some(server_info) = lock.get_mut(&server_url) {
+                                            server_info.is_deprecated = true;
+                                        }
+                                    }
+                                    _ => (),
+                                }
 
-                info!("sending request to {}", server_url);
-                {
-                    let mut lock = JUDGE_SERVER_INFOS.write().unwrap();
-                    let mut server_info = lock.get_mut(&server_url).unwrap();
-                    server_info.task_number += 1;
-                }
-                let result_string =
-                    run_judge_client(server_token, server_url.clone(), setting_string);
-                info!("{}", result_string);
+                                match release(&conn, task_uuid, &self.instance_id, attempts) {
+                                    Ok(_) => (),
+                                    Err(_) => {
+                                        log::error!(
+                                            "Error changing submissions's state to Waiting."
+                                        );
+                                        return;
+                                    }
+                                };
 
-                {
-                    let mut lock = JUDGE_SERVER_INFOS.write().unwrap();
-                    let mut server_info = lock.get_mut(&server_url).unwrap();
-                    server_info.task_number -= 1;
-                }
+                                match events::notify(&conn, task_uuid) {
+                                    Ok(_) => (),
+                                    Err(_) => log::error!("Error publishing submission event."),
+                                }
 
-                if result_string == *"" {
-                    let target =
-                        submissions_schema::table.filter(submissions_schema::id.eq(task_uuid));
-                    match diesel::update(target)
-                        .set((submissions_schema::state.eq("Waiting".to_owned()),))
-                        .execute(&conn)
-                    {
-                        Ok(_) => (),
-                        Err(_) => {
-                            log::error!("Error changing submissions's state to Waiting.");
-                            return;
+                                // the queue is picked up again by the next heartbeat
+                                info!("pushed {} back to queue", task_uuid);
+                                return;
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        log::error!("Error parsing judge settings of {}: {}", task_uuid, e);
+                        submissions::RawJudgeResult {
+                            err: Some("JudgeClientError".to_owned()),
+                            data: serde_json::json!(e.to_string()),
                         }
-                    };
-
-                    {
-                        let mut lock = WAITING_QUEUE.write().unwrap();
-                        lock.push_front(task_uuid);
                     }
+                }
+            };
+            let result = submissions::JudgeResult::from(raw_result);
+            let score = match get_score(&conn, task_uuid, &result) {
+                Ok(score) => score,
+                Err(_) => {
+                    log::error!("Error scoring submission {}.", task_uuid);
+                    None
+                }
+            };
 
-                    info!("pushed {} back to queue", task_uuid);
+            // update submissions, unless it has been recovered and claimed again meanwhile
+            let target = submissions_schema::table
+                .filter(submissions_schema::id.eq(task_uuid))
+                .filter(submissions_schema::state.eq("Pending".to_owned()))
+                .filter(submissions_schema::judge_instance.eq(&self.instance_id))
+                .filter(submissions_schema::judge_attempts.eq(attempts));
+            let mut result_set = std::collections::HashSet::new();
+            match diesel::update(target)
+                .set((
+                    submissions_schema::state.eq("Finished".to_owned()),
+                    submissions_schema::result.eq(serde_json::to_string(&result).unwrap()),
+                    submissions_schema::is_accepted.eq(result.is_accepted),
+                    submissions_schema::finish_time.eq(get_cur_naive_date_time()),
+                    submissions_schema::max_time.eq(result.max_time),
+                    submissions_schema::max_memory.eq(result.max_memory),
+                    submissions_schema::err.eq(result.err),
+                    submissions_schema::score.eq(score),
+                    submissions_schema::out_results.eq({
+                        if let Some(details) = result.details {
+                            let mut res = Vec::new();
+                            for detail in details {
+                                result_set.insert(detail.result);
+                            }
+                            for e in result_set.clone() {
+                                res.push(e);
+                            }
+                            Some(res)
+                        } else {
+                            None
+                        }
+                    }),
+                ))
+                .execute(&conn)
+            {
+                Ok(0) => {
+                    info!("dropped the stale result of {}", task_uuid);
                     continue;
                 }
+                Ok(_) => (),
+                Err(_) => {
+                    log::error!("Error changing submissions's data.");
+                    return;
+                }
+            };
 
-                let raw_result =
-                    serde_json::from_str::<submissions::RawJudgeResult>(&result_string).unwrap();
-                let result = submissions::JudgeResult::from(raw_result);
-
-                // update submissions
-                let target = submissions_schema::table.filter(submissions_schema::id.eq(task_uuid));
-                let mut result_set = std::collections::HashSet::new();
-                match diesel::update(target)
-                    .set((
-                        submissions_schema::state.eq("Finished".to_owned()),
-                        submissions_schema::result.eq(serde_json::to_string(&result).unwrap()),
-                        submissions_schema::is_accepted.eq(result.is_accepted),
-                        submissions_schema::finish_time.eq(get_cur_naive_date_time()),
-                        submissions_schema::max_time.eq(result.max_time),
-                        submissions_schema::max_memory.eq(result.max_memory),
-                        submissions_schema::err.eq(result.err),
-                        submissions_schema::out_results.eq({
-                            if let Some(details) = result.details {
-                                let mut res = Vec::new();
-                                for detail in details {
-                                    result_set.insert(detail.result);
-                                }
-                                for e in result_set.clone() {
-                                    res.push(e);
-                                }
-                                Some(res)
-                            } else {
-                                None
-                            }
-                        }),
-                    ))
-                    .execute(&conn)
+            let submission = submissions::Submission::from(
+                match submissions_schema::table
+                    .filter(submissions_schema::id.eq(task_uuid))
+                    .first::<submissions::RawSubmission>(&conn)
                 {
-                    Ok(_) => (),
+                    Ok(raw_submission) => raw_submission,
                     Err(_) => {
-                        log::error!("Error changing submissions's data.");
+                        log::error!("Error querying submission.");
                         return;
                     }
-                };
-
-                let submission = submissions::Submission::from(
-                    match submissions_schema::table
-                        .filter(submissions_schema::id.eq(task_uuid))
-                        .first::<submissions::RawSubmission>(&conn)
-                    {
-                        Ok(raw_submission) => raw_submission,
-                        Err(_) => {
-                            log::error!("Error querying submission.");
-                            return;
-                        }
-                    },
-                );
+                },
+            );
 
-                match common_region::update_results(&conn, submission.clone()) {
-                    Ok(_) => {}
-                    Err(_) => {
-                        log::error!("Error updating results type.");
-                        return;
-                    }
-                };
-                /*
-                if let Some(region) = submission.region.clone() {
-                    if match get_self_type(region, &conn) {
-                        Ok(region_type) => region_type,
-                        Err(_) => {
-                            log::error!("Error getting region type.");
-                            return;
-                        }
-                    } == "contest"
-                    {
-                        match update_acm_rank_cache(submission.region.unwrap(), &conn, false) {
-                            Ok(_) => (),
-                            Err(_) => {
-                                log::error!("Error updating acm rank cache.");
-                                return;
-                            }
-                        };
+            match rejudge::finish_record(&conn, &submission) {
+                Ok(true) => {
+                    // the rank may be final already and never be refreshed by itself
+                    if let Some(region) = &submission.region {
+                        invalidate_rank_caches(region);
                     }
-                }*/
+                }
+                Ok(false) => (),
+                Err(_) => log::error!("Error updating rejudge record."),
             }
 
-            queue_size = {
-                let lock = WAITING_QUEUE.read().unwrap();
-                lock.len()
+            match events::notify(&conn, task_uuid) {
+                Ok(_) => (),
+                Err(_) => log::error!("Error publishing submission event."),
+            }
+
+            match common_region::update_results(&conn, submission.clone()) {
+                Ok(_) => {}
+                Err(_) => {
+                    log::error!("Error updating results type.");
+                    return;
+                }
             };
-        }
 
-        ()
+            match apply_acm_submission(&conn, &submission) {
+                Ok(_) => (),
+                Err(_) => log::error!("Error updating acm rank cache."),
+            }
+        }
     }
 }
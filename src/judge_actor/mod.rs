@@ -1,12 +1,19 @@
 pub mod handler;
+mod queue;
+pub mod scheduler;
 mod statistics;
-mod utils;
 
 use actix::prelude::*;
-use server_core::database::Pool;
+use actix_web::web;
+use judge_client::JudgeClient;
+use server_core::database::{db_connection, Pool};
 
 pub struct JudgeActor {
     pub pool: Pool,
+    pub client: JudgeClient,
+    pub progress_callback_url: Option<String>,
+    pub instance_id: String,
+    pub max_attempts: i32,
 }
 
 impl Actor for JudgeActor {
@@ -17,13 +24,60 @@ pub struct JudgeActorAddr {
     pub addr: Addr<JudgeActor>,
 }
 
-pub(crate) fn start_judge_actor(opt: server_core::cli_args::Opt, pool: Pool) -> Addr<JudgeActor> {
+pub(crate) fn start_judge_actor(
+    opt: server_core::cli_args::Opt,
+    pool: Pool,
+    client: JudgeClient,
+) -> Addr<JudgeActor> {
+    // submissions left Pending by a previous run of this instance will never be finished
+    match db_connection(&pool) {
+        Ok(conn) => match queue::recover_owned(&conn, &opt.judge_instance_id) {
+            Ok(count) => info!("Put {} unfinished submissions back to queue", count),
+            Err(_) => log::error!("Error recovering unfinished submissions."),
+        },
+        Err(_) => log::error!("Error connecting to database."),
+    }
+
     info!(
         "Spawning {} JudgeActor in SyncArbiter",
         opt.judge_actor_count
     );
 
-    SyncArbiter::start(opt.judge_actor_count, move || JudgeActor {
+    let recover_pool = pool.clone();
+    let timeout_in_sec = opt.judge_pending_timeout_in_sec;
+    let addr = SyncArbiter::start(opt.judge_actor_count, move || JudgeActor {
         pool: pool.clone(),
-    })
+        client: client.clone(),
+        progress_callback_url: opt.judge_progress_callback_url.clone(),
+        instance_id: opt.judge_instance_id.clone(),
+        max_attempts: opt.judge_max_attempts,
+    });
+
+    // drain whatever is already waiting in the queue
+    addr.do_send(handler::StartJudge());
+
+    // instances sharing the database may go away with submissions still Pending
+    let recover_addr = addr.clone();
+    actix_web::rt::spawn(async move {
+        let mut interval = actix_web::rt::time::interval(std::time::Duration::from_secs(60));
+        loop {
+            interval.tick().await;
+            let pool = recover_pool.clone();
+            match web::block(move || {
+                let conn = db_connection(&pool)?;
+                queue::recover_stale(&conn, timeout_in_sec)
+            })
+            .await
+            {
+                Ok(0) => (),
+                Ok(count) => {
+                    info!("Put {} stale submissions back to queue", count);
+                    recover_addr.do_send(handler::StartJudge());
+                }
+                Err(_) => log::error!("Error recovering stale submissions."),
+            }
+        }
+    });
+
+    addr
 }
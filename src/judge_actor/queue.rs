@@ -0,0 +1,135 @@
+use super::scheduler::RegionFilter;
+use diesel::prelude::*;
+use server_core::errors::{ServiceError, ServiceResult};
+use server_core::utils::time::get_cur_naive_date_time;
+use uuid::Uuid;
+
+// The judge queue lives in the submissions table:
+// every submission in "Waiting" state is queued, ordered by submit_time.
+
+/// Claims the oldest waiting submission allowed by `region_filter` and marks it as Pending
+/// by `instance`, returns its settings and how many times it has been claimed.
+///
+/// The row is locked with `SKIP LOCKED`, so concurrent actors (or other server
+/// instances sharing the database) never claim the same submission twice.
+pub fn claim_next(
+    conn: &PgConnection,
+    region_filter: &RegionFilter,
+    instance: &str,
+) -> ServiceResult<Option<(Uuid, String, i32)>> {
+    use crate::schema::submissions as submissions_schema;
+
+    conn.transaction::<_, ServiceError, _>(|| {
+        let task: Option<(Uuid, String)> = match region_filter {
+            RegionFilter::Only(regions) => submissions_schema::table
+                .filter(submissions_schema::state.eq("Waiting".to_owned()))
+                .filter(submissions_schema::region.eq_any(regions.clone()))
+                .order(submissions_schema::submit_time.asc())
+                .select((submissions_schema::id, submissions_schema::settings))
+                .for_update()
+                .skip_locked()
+                .first(conn)
+                .optional()?,
+            RegionFilter::Exclude(regions) => submissions_schema::table
+                .filter(submissions_schema::state.eq("Waiting".to_owned()))
+                .filter(
+                    submissions_schema::region
+                        .is_null()
+                        .or(submissions_schema::region.ne_all(regions.clone())),
+                )
+                .order(submissions_schema::submit_time.asc())
+                .select((submissions_schema::id, submissions_schema::settings))
+                .for_update()
+                .skip_locked()
+                .first(conn)
+                .optional()?,
+        };
+
+        match task {
+            Some((id, settings)) => {
+                let attempts =
+                    diesel::update(submissions_schema::table.filter(submissions_schema::id.eq(id)))
+                        .set((
+                            submissions_schema::state.eq("Pending".to_owned()),
+                            submissions_schema::pending_time.eq(Some(get_cur_naive_date_time())),
+                            submissions_schema::judge_instance.eq(Some(instance.to_owned())),
+                            submissions_schema::judge_attempts
+                                .eq(submissions_schema::judge_attempts + 1),
+                        ))
+                        .returning(submissions_schema::judge_attempts)
+                        .get_result(conn)?;
+
+                Ok(Some((id, settings, attempts)))
+            }
+            None => Ok(None),
+        }
+    })
+}
+
+/// Puts a claimed submission back to the queue, unless it has been recovered
+/// and claimed again since `instance` claimed it for the `attempts`th time.
+pub fn release(conn: &PgConnection, id: Uuid, instance: &str, attempts: i32) -> ServiceResult<()> {
+    use crate::schema::submissions as submissions_schema;
+
+    diesel::update(
+        submissions_schema::table
+            .filter(submissions_schema::id.eq(id))
+            .filter(submissions_schema::state.eq("Pending".to_owned()))
+            .filter(submissions_schema::judge_instance.eq(instance))
+            .filter(submissions_schema::judge_attempts.eq(attempts)),
+    )
+    .set((
+        submissions_schema::state.eq("Waiting".to_owned()),
+        submissions_schema::pending_time.eq(None::<chrono::NaiveDateTime>),
+        submissions_schema::judge_instance.eq(None::<String>),
+    ))
+    .execute(conn)?;
+
+    Ok(())
+}
+
+/// Resets submissions which have been Pending for longer than `timeout_in_sec`,
+/// returns how many of them were put back to the queue.
+pub fn recover_stale(conn: &PgConnection, timeout_in_sec: i64) -> ServiceResult<usize> {
+    use crate::schema::submissions as submissions_schema;
+
+    let deadline = get_cur_naive_date_time() - chrono::Duration::seconds(timeout_in_sec);
+
+    let count = diesel::update(
+        submissions_schema::table
+            .filter(submissions_schema::state.eq("Pending".to_owned()))
+            .filter(
+                submissions_schema::pending_time
+                    .is_null()
+                    .or(submissions_schema::pending_time.lt(deadline)),
+            ),
+    )
+    .set((
+        submissions_schema::state.eq("Waiting".to_owned()),
+        submissions_schema::pending_time.eq(None::<chrono::NaiveDateTime>),
+        submissions_schema::judge_instance.eq(None::<String>),
+    ))
+    .execute(conn)?;
+
+    Ok(count)
+}
+
+/// Resets every submission left Pending by `instance`, its judge actors are gone
+/// once the instance restarts. Returns how many of them were put back to the queue.
+pub fn recover_owned(conn: &PgConnection, instance: &str) -> ServiceResult<usize> {
+    use crate::schema::submissions as submissions_schema;
+
+    let count = diesel::update(
+        submissions_schema::table
+            .filter(submissions_schema::state.eq("Pending".to_owned()))
+            .filter(submissions_schema::judge_instance.eq(instance)),
+    )
+    .set((
+        submissions_schema::state.eq("Waiting".to_owned()),
+        submissions_schema::pending_time.eq(None::<chrono::NaiveDateTime>),
+        submissions_schema::judge_instance.eq(None::<String>),
+    ))
+    .execute(conn)?;
+
+    Ok(count)
+}
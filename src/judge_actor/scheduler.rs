@@ -0,0 +1,107 @@
+use crate::models::judge_servers::{JudgeServerInfo, ScheduleStrategy};
+use crate::statics::{JUDGE_SERVER_INFOS, SCHEDULE_CONFIG};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+/// Which submissions a judge server may take from the queue.
+#[derive(Debug, Clone)]
+pub enum RegionFilter {
+    /// Only submissions of these regions, used by pinned servers
+    Only(Vec<String>),
+    /// Anything but submissions of these regions
+    Exclude(Vec<String>),
+}
+
+#[derive(Debug, Clone)]
+pub struct ScheduledServer {
+    pub server_id: i32,
+    pub url: String,
+    pub token: String,
+    pub region_filter: RegionFilter,
+}
+
+pub fn is_available(info: &JudgeServerInfo) -> bool {
+    let last_heartbeat = match info.heartbeat_time.elapsed() {
+        Ok(elapsed) => elapsed.as_secs(),
+        Err(_) => 0,
+    };
+
+    !info.is_deprecated
+        && !info.is_draining
+        && info.task_number < info.cpu_core * 2
+        && last_heartbeat <= 5
+}
+
+pub fn server_load(info: &JudgeServerInfo, strategy: ScheduleStrategy) -> f32 {
+    let task_load = info.task_number as f32 / info.cpu_core.max(1) as f32;
+    match strategy {
+        // heartbeats lag behind, so tasks sent since the last one still count
+        ScheduleStrategy::CpuWeighted => info.cpu / 100.0 + task_load,
+        ScheduleStrategy::LeastLoaded | ScheduleStrategy::RoundRobin => task_load,
+    }
+}
+
+/// Regions pinned to the judge server at `url`.
+pub fn pinned_regions(url: &str) -> Vec<String> {
+    let mut regions: Vec<String> = SCHEDULE_CONFIG
+        .pinned_regions
+        .iter()
+        .filter(|(_, urls)| urls.iter().any(|pinned_url| pinned_url == url))
+        .map(|(region, _)| region.clone())
+        .collect();
+    regions.sort();
+    regions
+}
+
+pub fn region_filter(url: &str) -> RegionFilter {
+    let regions = pinned_regions(url);
+    if regions.is_empty() {
+        RegionFilter::Exclude(SCHEDULE_CONFIG.pinned_regions.keys().cloned().collect())
+    } else {
+        RegionFilter::Only(regions)
+    }
+}
+
+/// Orders available judge servers by preference of `strategy`.
+pub fn rank_judge_servers(
+    infos: &HashMap<String, JudgeServerInfo>,
+    strategy: ScheduleStrategy,
+) -> Vec<String> {
+    let mut candidates: Vec<&JudgeServerInfo> =
+        infos.values().filter(|info| is_available(info)).collect();
+
+    candidates.sort_by(|a, b| {
+        let order = match strategy {
+            ScheduleStrategy::RoundRobin => a
+                .last_assigned_time
+                .unwrap_or(SystemTime::UNIX_EPOCH)
+                .cmp(&b.last_assigned_time.unwrap_or(SystemTime::UNIX_EPOCH)),
+            _ => server_load(a, strategy)
+                .partial_cmp(&server_load(b, strategy))
+                .unwrap_or(Ordering::Equal),
+        };
+        // keep decisions stable between calls
+        order.then_with(|| a.service_url.cmp(&b.service_url))
+    });
+
+    candidates
+        .into_iter()
+        .map(|info| info.service_url.clone())
+        .collect()
+}
+
+/// Available judge servers in the order they should be tried.
+pub fn schedule_judge_servers() -> Vec<ScheduledServer> {
+    let lock = JUDGE_SERVER_INFOS.read().unwrap();
+
+    rank_judge_servers(&lock, SCHEDULE_CONFIG.strategy)
+        .into_iter()
+        .map(|url| ScheduledServer {
+            server_id: lock[&url].server_id,
+            token: lock[&url].token.clone(),
+            region_filter: region_filter(&url),
+            url,
+        })
+        .collect()
+}
@@ -46,11 +46,39 @@ async fn main() -> std::io::Result<()> {
     let _secure_cookie = opt.secure_cookie;
     let auth_duration = time::Duration::hours(i64::from(opt.auth_duration_in_hour));
 
-    let judge_actor_addr = judge_actor::start_judge_actor(opt.clone(), pool.clone());
+    *statics::ARCHIVE_LIMITS.write().unwrap() = services::problem::archive::ArchiveLimits {
+        max_size: opt.archive_max_size_in_mb * 1024 * 1024,
+        max_file_count: opt.archive_max_file_count,
+    };
+
+    let judge_client = judge_client::JudgeClient::new(judge_client::JudgeClientConfig {
+        connect_timeout: std::time::Duration::from_secs(opt.judge_connect_timeout_in_sec),
+        judge_timeout: std::time::Duration::from_secs(opt.judge_timeout_in_sec),
+        ping_timeout: std::time::Duration::from_secs(opt.judge_connect_timeout_in_sec),
+        max_retries: opt.judge_retry_times,
+        ..Default::default()
+    })
+    .expect("Failed to build judge client");
+
+    // read schedule.toml now so that a broken one is reported at startup
+    lazy_static::initialize(&statics::SCHEDULE_CONFIG);
+
+    let judge_actor_addr =
+        judge_actor::start_judge_actor(opt.clone(), pool.clone(), judge_client.clone());
+
+    // keep submission event streams alive and drop closed ones
+    actix_web::rt::spawn(async {
+        let mut interval = actix_web::rt::time::interval(std::time::Duration::from_secs(15));
+        loop {
+            interval.tick().await;
+            services::submission::events::ping();
+        }
+    });
 
     HttpServer::new(move || {
         App::new()
             .data(pool.clone())
+            .data(judge_client.clone())
             .data(judge_actor::JudgeActorAddr {
                 addr: judge_actor_addr.clone(),
             })
@@ -71,6 +99,7 @@ async fn main() -> std::io::Result<()> {
             .configure(controllers::users::route)
             .configure(controllers::problems::route)
             .configure(controllers::judge_servers::route)
+            .configure(controllers::languages::route)
             .configure(controllers::submissions::route)
             .configure(controllers::samples::route)
             .configure(controllers::regions::route)
@@ -0,0 +1,13 @@
+use crate::schema::*;
+use chrono::NaiveDateTime;
+
+/// Checker of a special judge problem, `version` is the hash of `src`.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable, AsChangeset)]
+#[table_name = "checkers"]
+pub struct Checker {
+    pub problem_id: i32,
+    pub language: String,
+    pub src: String,
+    pub version: String,
+    pub update_time: NaiveDateTime,
+}
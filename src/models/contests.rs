@@ -15,6 +15,7 @@ pub struct RawContest {
     pub self_type: String,
     pub salt: Option<String>,
     pub hash: Option<Vec<u8>>,
+    pub unfreeze_time: Option<NaiveDateTime>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,6 +31,8 @@ pub struct Contest {
     pub state: String,
     pub salt: Option<String>,
     pub hash: Option<Vec<u8>>,
+    /// Sealed results stay hidden after the end until an admin unfreezes the rank
+    pub unfreeze_time: Option<NaiveDateTime>,
 }
 
 impl From<RawContest> for Contest {
@@ -46,6 +49,7 @@ impl From<RawContest> for Contest {
             state: format!("{}", ContestState::Ended),
             salt: raw.salt,
             hash: raw.hash,
+            unfreeze_time: raw.unfreeze_time,
         };
         res.state = format!(
             "{}",
@@ -90,6 +94,64 @@ impl From<RawContest> for SlimContest {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContestRule {
+    /// Accepted problems, then penalty time
+    Acm,
+    /// Score of the last submission of each problem, verdicts are hidden until the end
+    Oi,
+    /// Best score of each problem, with live feedback
+    Ioi,
+}
+
+impl Default for ContestRule {
+    fn default() -> Self {
+        ContestRule::Acm
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PenaltyUnit {
+    Second,
+    /// Accepted time is rounded down to whole minutes
+    Minute,
+}
+
+impl Default for PenaltyUnit {
+    fn default() -> Self {
+        PenaltyUnit::Second
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ACMPenaltyRules {
+    /// Penalty of each wrong try before the first accepted one
+    pub penalty_minutes: i64,
+    /// Compile errors do not count as tries unless a contest opts in
+    pub count_compile_error: bool,
+    /// Judged submissions with test cases failed by the judge system rather than
+    /// the contestant count as tries unless a contest opts out
+    pub count_system_error: bool,
+    pub penalty_unit: PenaltyUnit,
+    /// Breaks ties by whoever got the last accepted problem earlier
+    pub tie_break_by_last_accepted: bool,
+}
+
+impl Default for ACMPenaltyRules {
+    fn default() -> Self {
+        Self {
+            penalty_minutes: 20,
+            count_compile_error: false,
+            count_system_error: true,
+            penalty_unit: PenaltyUnit::default(),
+            tie_break_by_last_accepted: false,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ContestSettings {
     pub register_after_start: bool,
@@ -98,6 +160,10 @@ pub struct ContestSettings {
     pub view_after_end: bool,
     pub public_after_end: bool,
     pub submit_after_end: bool,
+    #[serde(default)]
+    pub rule: ContestRule,
+    #[serde(default)]
+    pub acm_penalty: ACMPenaltyRules,
 }
 
 impl Default for ContestSettings {
@@ -109,6 +175,8 @@ impl Default for ContestSettings {
             view_after_end: true,
             public_after_end: false,
             submit_after_end: true,
+            rule: ContestRule::default(),
+            acm_penalty: ACMPenaltyRules::default(),
         }
     }
 }
@@ -192,6 +260,7 @@ pub struct OutContest {
     pub is_registered: bool,
     pub need_pass: bool,
     pub settings: ContestSettings,
+    pub unfreeze_time: Option<NaiveDateTime>,
     pub problem_count: i32,
     pub registered_user_count: i32,
 }
@@ -212,6 +281,7 @@ impl From<RawContest> for OutContest {
             is_registered: false,
             need_pass: if contest.hash.is_some() { true } else { false },
             settings: contest.settings,
+            unfreeze_time: contest.unfreeze_time,
             problem_count: 0,
             registered_user_count: 0,
         }
@@ -1,7 +1,77 @@
+use crate::schema::*;
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
 use std::time::SystemTime;
 
+/// A judge server allowed to send heartbeats,
+/// `state` is one of "Enabled", "Draining" and "Disabled".
+/// Only a digest of the token header is stored, the token itself is shown once on creation.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+pub struct JudgeServer {
+    pub id: i32,
+    pub name: String,
+    pub state: String,
+    pub created_time: NaiveDateTime,
+    #[serde(skip)]
+    pub token_digest: String,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "judge_servers"]
+pub struct InsertableJudgeServer {
+    pub name: String,
+    pub state: String,
+    pub created_time: NaiveDateTime,
+    pub token_digest: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CreatedJudgeServer {
+    pub id: i32,
+    pub name: String,
+    pub token: String,
+    pub state: String,
+    pub created_time: NaiveDateTime,
+}
+
+#[derive(AsChangeset)]
+#[table_name = "judge_servers"]
+pub struct JudgeServerForm {
+    pub name: Option<String>,
+    pub state: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduleStrategy {
+    /// Fewest running tasks per cpu core first
+    LeastLoaded,
+    /// Lowest cpu usage reported by heartbeats first
+    CpuWeighted,
+    /// Least recently assigned server first
+    RoundRobin,
+}
+
+impl Default for ScheduleStrategy {
+    fn default() -> Self {
+        ScheduleStrategy::LeastLoaded
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ScheduleConfig {
+    #[serde(default)]
+    pub strategy: ScheduleStrategy,
+    /// region name -> service urls of the judge servers dedicated to it
+    #[serde(default)]
+    pub pinned_regions: HashMap<String, Vec<String>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct JudgeServerInfo {
+    pub server_id: i32,
+    pub server_name: String,
+    pub is_draining: bool,
     pub judger_version: String,
     pub hostname: String,
     pub cpu_core: i32,
@@ -12,10 +82,15 @@ pub struct JudgeServerInfo {
     pub token: String,
     pub heartbeat_time: SystemTime,
     pub is_deprecated: bool,
+    pub assigned_count: i64,
+    pub last_assigned_time: Option<SystemTime>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OutJudgeServerInfo {
+    pub server_id: i32,
+    pub server_name: String,
+    pub is_draining: bool,
     pub judger_version: String,
     pub hostname: String,
     pub cpu_core: i32,
@@ -23,15 +98,24 @@ pub struct OutJudgeServerInfo {
     pub cpu: f32,
     pub task_number: i32,
     pub service_url: String,
-    pub token: String,
     pub heartbeat_time: SystemTime,
     pub last_heartbeat: i32,
     pub is_deprecated: bool,
+    pub assigned_count: i64,
+    pub last_assigned_time: Option<SystemTime>,
+    pub strategy: ScheduleStrategy,
+    pub load: f32,
+    pub pinned_regions: Vec<String>,
+    /// Position in the next scheduling decision, none if the server is unavailable
+    pub schedule_rank: Option<usize>,
 }
 
 impl From<JudgeServerInfo> for OutJudgeServerInfo {
     fn from(raw: JudgeServerInfo) -> Self {
         Self {
+            server_id: raw.server_id,
+            server_name: raw.server_name,
+            is_draining: raw.is_draining,
             judger_version: raw.judger_version,
             hostname: raw.hostname,
             cpu_core: raw.cpu_core,
@@ -39,10 +123,15 @@ impl From<JudgeServerInfo> for OutJudgeServerInfo {
             cpu: raw.cpu,
             task_number: raw.task_number,
             service_url: raw.service_url,
-            token: raw.token,
             heartbeat_time: raw.heartbeat_time,
             last_heartbeat: raw.heartbeat_time.elapsed().unwrap().as_secs() as i32,
             is_deprecated: raw.is_deprecated,
+            assigned_count: raw.assigned_count,
+            last_assigned_time: raw.last_assigned_time,
+            strategy: ScheduleStrategy::default(),
+            load: 0.0,
+            pinned_regions: Vec::new(),
+            schedule_rank: None,
         }
     }
 }
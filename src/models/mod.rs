@@ -1,18 +1,23 @@
 pub mod access_control_list;
 pub mod announcements;
+pub mod checkers;
 pub mod contests;
 pub mod group_links;
 pub mod groups;
 pub mod judge_servers;
 pub mod languages;
+pub mod plagiarism_reports;
 pub mod problem_sets;
 pub mod problem_tags;
 pub mod problems;
 pub mod ranks;
 pub mod region_links;
 pub mod regions;
+pub mod rejudge_records;
 pub mod samples;
 pub mod statistics;
 pub mod submissions;
+pub mod test_case_versions;
+pub mod test_data_programs;
 pub mod users;
 pub mod utils;
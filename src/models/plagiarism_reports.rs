@@ -0,0 +1,97 @@
+use crate::schema::*;
+use chrono::NaiveDateTime;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Queryable)]
+pub struct RawPlagiarismReport {
+    pub id: i32,
+    pub region: String,
+    pub problem_id: i32,
+    pub only_accepted: bool,
+    pub threshold: f64,
+    pub submission_count: i32,
+    pub pairs: String,
+    pub operator_id: i32,
+    pub create_time: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "plagiarism_reports"]
+pub struct InsertablePlagiarismReport {
+    pub region: String,
+    pub problem_id: i32,
+    pub only_accepted: bool,
+    pub threshold: f64,
+    pub submission_count: i32,
+    pub pairs: String,
+    pub operator_id: i32,
+    pub create_time: NaiveDateTime,
+}
+
+/// Two submissions of different users whose fingerprints look alike.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SuspiciousPair {
+    pub first_submission_id: Uuid,
+    pub first_user_id: i32,
+    pub second_submission_id: Uuid,
+    pub second_user_id: i32,
+    /// Shared fingerprints over those of the smaller submission, from 0 to 1
+    pub similarity: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlagiarismReport {
+    pub id: i32,
+    pub region: String,
+    pub problem_id: i32,
+    pub only_accepted: bool,
+    pub threshold: f64,
+    pub submission_count: i32,
+    /// Sorted by similarity, most similar first
+    pub pairs: Vec<SuspiciousPair>,
+    pub operator_id: i32,
+    pub create_time: NaiveDateTime,
+}
+
+impl From<RawPlagiarismReport> for PlagiarismReport {
+    fn from(raw: RawPlagiarismReport) -> Self {
+        Self {
+            id: raw.id,
+            region: raw.region,
+            problem_id: raw.problem_id,
+            only_accepted: raw.only_accepted,
+            threshold: raw.threshold,
+            submission_count: raw.submission_count,
+            pairs: serde_json::from_str(&raw.pairs).unwrap(),
+            operator_id: raw.operator_id,
+            create_time: raw.create_time,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffTag {
+    Equal,
+    /// Only in the first submission
+    Delete,
+    /// Only in the second submission
+    Insert,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiffLine {
+    pub tag: DiffTag,
+    pub first_line_number: Option<i32>,
+    pub first: Option<String>,
+    pub second_line_number: Option<i32>,
+    pub second: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SideBySideDiff {
+    pub first_submission_id: Uuid,
+    pub second_submission_id: Uuid,
+    pub similarity: f64,
+    pub lines: Vec<DiffLine>,
+}
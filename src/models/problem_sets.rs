@@ -1,11 +1,80 @@
 use crate::schema::*;
+use chrono::NaiveDateTime;
 
 #[derive(Debug, Clone, Serialize, Deserialize, Insertable, Queryable)]
 #[table_name = "problem_sets"]
+pub struct RawProblemSet {
+    pub region: String,
+    pub title: String,
+    pub introduction: Option<String>,
+    pub settings: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProblemSetInfo {
     pub region: String,
     pub title: String,
     pub introduction: Option<String>,
+    pub settings: ProblemSetSettings,
+}
+
+impl From<RawProblemSet> for ProblemSetInfo {
+    fn from(raw: RawProblemSet) -> Self {
+        Self {
+            region: raw.region,
+            title: raw.title,
+            introduction: raw.introduction,
+            settings: serde_json::from_str(&raw.settings).unwrap(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProblemSetSettings {
+    pub open_time: Option<NaiveDateTime>,
+    pub close_time: Option<NaiveDateTime>,
+    /// Submissions after `close_time` are taken as late until then
+    pub late_close_time: Option<NaiveDateTime>,
+    /// Multiplies scores of late submissions
+    pub late_penalty_factor: f64,
+    /// Only members of linked groups may view and solve
+    pub restrict_to_groups: bool,
+}
+
+impl Default for ProblemSetSettings {
+    fn default() -> Self {
+        Self {
+            open_time: None,
+            close_time: None,
+            late_close_time: None,
+            late_penalty_factor: 1.0,
+            restrict_to_groups: false,
+        }
+    }
+}
+
+impl ProblemSetSettings {
+    pub fn is_open(&self, time: NaiveDateTime) -> bool {
+        self.open_time.map_or(true, |open_time| time >= open_time)
+    }
+
+    pub fn is_late(&self, time: NaiveDateTime) -> bool {
+        self.close_time
+            .map_or(false, |close_time| time > close_time)
+    }
+
+    /// Whether submitting at `time` is allowed, late or not.
+    pub fn can_submit(&self, time: NaiveDateTime) -> bool {
+        if !self.is_open(time) {
+            return false;
+        }
+        if !self.is_late(time) {
+            return true;
+        }
+        self.late_close_time
+            .map_or(false, |late_close_time| time <= late_close_time)
+    }
 }
 
 #[derive(AsChangeset)]
@@ -13,4 +82,47 @@ pub struct ProblemSetInfo {
 pub struct ProblemSetForm {
     pub title: Option<String>,
     pub introduction: Option<String>,
+    pub settings: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GradeProblem {
+    pub inner_id: i32,
+    pub problem_id: i32,
+    pub title: String,
+}
+
+/// Results of a student on a problem, scores are scaled to the problem set.
+#[derive(Debug, Clone, Serialize)]
+pub struct GradeCell {
+    pub inner_id: i32,
+    /// Accepted if ever accepted, or the verdict of the last submission
+    pub verdict: Option<String>,
+    pub score: Option<f64>,
+    pub first_accepted_time: Option<NaiveDateTime>,
+    pub try_times: i32,
+    /// Tries after the deadline
+    pub late_try_times: i32,
+    /// Whether the result above was only reached after the deadline
+    pub is_late: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GradeRow {
+    pub user_id: i32,
+    pub username: String,
+    pub real_name: Option<String>,
+    pub student_number: Option<String>,
+    pub total_accepted: i32,
+    pub total_score: f64,
+    pub cells: Vec<GradeCell>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct GradeMatrix {
+    pub region: String,
+    pub group_id: Option<i32>,
+    pub deadline: Option<NaiveDateTime>,
+    pub problems: Vec<GradeProblem>,
+    pub rows: Vec<GradeRow>,
 }
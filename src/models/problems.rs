@@ -1,5 +1,8 @@
+use crate::models::languages::Language;
+use crate::models::submissions::JudgeResultData;
 use crate::schema::*;
 use crate::statics::PROBLEM_TAG_NAME_CACHE;
+use std::collections::{BTreeMap, HashSet};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
 pub struct RawProblem {
@@ -10,6 +13,8 @@ pub struct RawProblem {
     pub contents: String,
     pub settings: String,
     pub is_released: bool,
+    /// Test data in use, `None` if it is still in the legacy unversioned folder
+    pub test_case_version: Option<String>,
 }
 
 #[derive(Debug, Clone, Insertable)]
@@ -43,15 +48,206 @@ pub struct ProblemContents {
     pub examples: Vec<Example>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProblemType {
+    /// Outputs are compared with answers, or checked by the checker
+    Batch,
+    /// Submissions talk to `interactor_src.c` or `interactor_src.cpp` in the test cases
+    Interactive,
+}
+
+impl Default for ProblemType {
+    fn default() -> Self {
+        ProblemType::Batch
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "LegacyProblemSettings")]
 pub struct ProblemSettings {
+    #[serde(default)]
+    pub problem_type: ProblemType,
     pub is_spj: bool,
     pub high_performance_max_cpu_time: i32,
     pub high_performance_max_memory: i32,
-    pub other_max_cpu_time: i32,
-    pub other_max_memory: i32,
+    /// Limits of specific languages, languages not listed here get
+    /// the high performance limits scaled by their multipliers
+    #[serde(default)]
+    pub language_limits: BTreeMap<String, LanguageLimit>,
     pub opaque_output: bool,
     pub test_case_count: Option<i32>,
+    /// Skip remaining test cases after the first failed one
+    #[serde(default)]
+    pub stop_on_failure: bool,
+    /// Scores of groups of test cases, every test case is worth
+    /// the same part of 100 points if there is none
+    #[serde(default)]
+    pub subtasks: Vec<Subtask>,
+}
+
+/// Settings which may still have the limits shared by every language but c and cpp,
+/// from before `language_limits`. Such settings are read the way the migration
+/// converted the stored ones.
+#[derive(Deserialize)]
+struct LegacyProblemSettings {
+    #[serde(default)]
+    problem_type: ProblemType,
+    is_spj: bool,
+    high_performance_max_cpu_time: i32,
+    high_performance_max_memory: i32,
+    #[serde(default)]
+    language_limits: BTreeMap<String, LanguageLimit>,
+    other_max_cpu_time: Option<i32>,
+    other_max_memory: Option<i32>,
+    opaque_output: bool,
+    test_case_count: Option<i32>,
+    #[serde(default)]
+    stop_on_failure: bool,
+    #[serde(default)]
+    subtasks: Vec<Subtask>,
+}
+
+impl From<LegacyProblemSettings> for ProblemSettings {
+    fn from(legacy: LegacyProblemSettings) -> Self {
+        let mut language_limits = legacy.language_limits;
+        if legacy.other_max_cpu_time.is_some() || legacy.other_max_memory.is_some() {
+            let other_limit = LanguageLimit {
+                max_cpu_time: legacy
+                    .other_max_cpu_time
+                    .unwrap_or(legacy.high_performance_max_cpu_time),
+                max_memory: legacy
+                    .other_max_memory
+                    .unwrap_or(legacy.high_performance_max_memory),
+            };
+            for language in &["java", "py2", "py3"] {
+                language_limits
+                    .entry(language.to_string())
+                    .or_insert(other_limit);
+            }
+        }
+
+        Self {
+            problem_type: legacy.problem_type,
+            is_spj: legacy.is_spj,
+            high_performance_max_cpu_time: legacy.high_performance_max_cpu_time,
+            high_performance_max_memory: legacy.high_performance_max_memory,
+            language_limits: language_limits,
+            opaque_output: legacy.opaque_output,
+            test_case_count: legacy.test_case_count,
+            stop_on_failure: legacy.stop_on_failure,
+            subtasks: legacy.subtasks,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SubtaskAggregation {
+    /// Full score only if all test cases pass
+    Min,
+    /// Score split evenly over test cases
+    Sum,
+}
+
+impl Default for SubtaskAggregation {
+    fn default() -> Self {
+        SubtaskAggregation::Min
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Subtask {
+    pub score: f64,
+    /// Ids of test cases, the same as the names of their `.in` files
+    pub test_cases: Vec<i32>,
+    #[serde(default)]
+    pub aggregation: SubtaskAggregation,
+}
+
+impl ProblemSettings {
+    pub fn effective_limit(&self, language: &Language) -> LanguageLimit {
+        match self.language_limits.get(&language.name) {
+            Some(limit) => *limit,
+            None => LanguageLimit {
+                max_cpu_time: (self.high_performance_max_cpu_time as f64 * language.time_multiplier)
+                    as i32,
+                max_memory: (self.high_performance_max_memory as f64 * language.memory_multiplier)
+                    as i32,
+            },
+        }
+    }
+
+    pub fn effective_limits(&self, languages: &[Language]) -> BTreeMap<String, LanguageLimit> {
+        languages
+            .iter()
+            .map(|language| (language.name.clone(), self.effective_limit(language)))
+            .collect()
+    }
+
+    pub fn full_score(&self) -> f64 {
+        if self.subtasks.is_empty() {
+            100.0
+        } else {
+            self.subtasks.iter().map(|subtask| subtask.score).sum()
+        }
+    }
+
+    /// Id of the test case a judge result is of, the same as the name of its `.in` file.
+    /// Judge servers report the keys of `info`, which start from 0 for special judge
+    /// test cases and from 1 for the others.
+    fn test_case_id(&self, detail: &JudgeResultData) -> Option<i32> {
+        let key: i32 = detail.test_case.parse().ok()?;
+        if self.is_spj && self.problem_type == ProblemType::Batch {
+            Some(key + 1)
+        } else {
+            Some(key)
+        }
+    }
+
+    /// Points earned by judged test cases, test cases skipped by
+    /// `stop_on_failure` are counted as failed.
+    pub fn score(&self, details: &[JudgeResultData]) -> f64 {
+        let passed: HashSet<i32> = details
+            .iter()
+            .filter(|detail| detail.result == "SUCCESS")
+            .filter_map(|detail| self.test_case_id(detail))
+            .collect();
+
+        if self.subtasks.is_empty() {
+            let total = self.test_case_count.unwrap_or(details.len() as i32).max(1);
+            return 100.0 * passed.len() as f64 / total as f64;
+        }
+
+        self.subtasks
+            .iter()
+            .map(|subtask| {
+                let passed_count = subtask
+                    .test_cases
+                    .iter()
+                    .filter(|id| passed.contains(id))
+                    .count();
+                match subtask.aggregation {
+                    SubtaskAggregation::Min => {
+                        if passed_count == subtask.test_cases.len() {
+                            subtask.score
+                        } else {
+                            0.0
+                        }
+                    }
+                    SubtaskAggregation::Sum => {
+                        subtask.score * passed_count as f64 / subtask.test_cases.len().max(1) as f64
+                    }
+                }
+            })
+            .sum()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct LanguageLimit {
+    pub max_cpu_time: i32,
+    pub max_memory: i32,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
@@ -61,6 +257,9 @@ pub struct Problem {
     pub contents: ProblemContents,
     pub settings: ProblemSettings,
     pub is_released: bool,
+    /// Limits each enabled language is judged with
+    #[serde(default)]
+    pub effective_limits: BTreeMap<String, LanguageLimit>,
 }
 
 impl From<RawProblem> for Problem {
@@ -85,6 +284,7 @@ impl From<RawProblem> for Problem {
             contents: serde_json::from_str::<ProblemContents>(&raw.contents).unwrap(),
             settings: serde_json::from_str::<ProblemSettings>(&raw.settings).unwrap(),
             is_released: raw.is_released,
+            effective_limits: BTreeMap::new(),
         }
     }
 }
@@ -158,6 +358,42 @@ pub struct CreateProblemsResult {
     pub title: String,
     pub is_success: bool,
     pub id: Option<i32>,
+    pub effective_limits: Option<BTreeMap<String, LanguageLimit>>,
+    /// Problems in the package found broken, nothing is created if there are any
+    #[serde(default)]
+    pub errors: Vec<PackageError>,
+}
+
+/// Formats of problems made by other judges.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportFormat {
+    /// Zipped full package of Codeforces Polygon, or a zip of such packages
+    Polygon,
+    /// Free Problem Set xml exported by QDUOJ or HUSTOJ
+    Fps,
+}
+
+/// `Manifest.toml` of a problem in a package, problems without it are read as version 0.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageManifest {
+    pub format_version: i32,
+    #[serde(default)]
+    pub checker: Option<PackageChecker>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageChecker {
+    pub language: String,
+    /// Path of the source relative to the problem folder
+    pub file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackageError {
+    /// Path relative to the problem folder
+    pub file: String,
+    pub message: String,
 }
 
 #[derive(AsChangeset)]
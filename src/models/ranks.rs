@@ -1,9 +1,14 @@
+use crate::models::contests::ACMPenaltyRules;
 use chrono::*;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ACMRank {
     pub region: String,
+    /// Time the rank was last built from the database
     pub last_updated_time: NaiveDateTime,
+    /// Sealed submissions are revealed in a final rank
+    pub is_final: bool,
+    pub rules: ACMPenaltyRules,
     pub columns: Vec<ACMRankColumn>,
 }
 
@@ -15,6 +20,7 @@ pub struct ACMRankColumn {
     pub real_name: Option<String>,
     pub total_accepted: i32,
     pub time_cost: i64,
+    pub last_accepted_time: Option<NaiveDateTime>,
     pub is_unrated: Option<bool>,
     pub problem_block: Vec<ACMProblemBlock>,
 }
@@ -28,3 +34,81 @@ pub struct ACMProblemBlock {
     pub try_times: i32,
     pub last_submit_time: Option<NaiveDateTime>,
 }
+
+/// A sealed problem revealed by the resolver, positions start from 0.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolverEvent {
+    pub user_id: i32,
+    pub inner_id: i32,
+    pub is_accepted: Option<bool>,
+    pub try_times: i32,
+    pub total_accepted: i32,
+    pub time_cost: i64,
+    pub position_before: i32,
+    pub position_after: i32,
+    pub rank: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResolverReplay {
+    /// Rank before the first event
+    pub frozen: ACMRank,
+    pub events: Vec<ResolverEvent>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OIRank {
+    pub region: String,
+    pub last_updated_time: NaiveDateTime,
+    /// Scores are hidden until the contest ends
+    pub is_final: bool,
+    pub columns: Vec<OIRankColumn>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OIRankColumn {
+    pub rank: Option<i32>,
+    pub user_id: i32,
+    pub username: String,
+    pub real_name: Option<String>,
+    pub total_score: f64,
+    pub is_unrated: Option<bool>,
+    pub problem_block: Vec<OIProblemBlock>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OIProblemBlock {
+    pub inner_id: i32,
+    /// Score of the last submission
+    pub score: Option<f64>,
+    pub submit_times: i32,
+    pub last_submit_time: Option<NaiveDateTime>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IOIRank {
+    pub region: String,
+    pub last_updated_time: NaiveDateTime,
+    pub columns: Vec<IOIRankColumn>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IOIRankColumn {
+    pub rank: Option<i32>,
+    pub user_id: i32,
+    pub username: String,
+    pub real_name: Option<String>,
+    pub total_score: f64,
+    pub is_unrated: Option<bool>,
+    pub problem_block: Vec<IOIProblemBlock>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IOIProblemBlock {
+    pub inner_id: i32,
+    /// Best score so far
+    pub score: Option<f64>,
+    pub is_sealed: bool,
+    pub try_times: i32,
+    pub best_submit_time: Option<NaiveDateTime>,
+}
@@ -28,6 +28,8 @@ pub struct RawLinkedProblemColumn {
     pub problem_tags: Vec<i32>,
     pub problem_difficulty: f64,
     pub is_released: bool,
+    pub score: Option<i32>,
+    pub problem_settings: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +37,8 @@ pub struct LinkedProblemColumn {
     pub region: String,
     pub inner_id: i32,
     pub is_accepted: bool,
+    /// Best score of the user, scaled to the score of the problem in the region
+    pub score: Option<f64>,
     pub out_problem: problems::OutProblem,
     pub submit_times: i32,
     pub accept_times: i32,
@@ -42,7 +46,43 @@ pub struct LinkedProblemColumn {
 }
 
 use crate::models::statistics::get_results;
+use diesel::prelude::*;
 use server_core::database::*;
+
+/// Scales points of a submission to the score of the problem in a region.
+pub fn scale_score(
+    link_score: Option<i32>,
+    score: f64,
+    settings: &problems::ProblemSettings,
+) -> f64 {
+    let full_score = settings.full_score();
+    if full_score > 0.0 {
+        link_score.unwrap_or(100) as f64 * score / full_score
+    } else {
+        0.0
+    }
+}
+
+/// Best score of `user_id` on a linked problem, scaled to `link_score`.
+pub fn get_best_score(
+    conn: &PgConnection,
+    region: &str,
+    problem_id: i32,
+    user_id: i32,
+    link_score: Option<i32>,
+    settings: &problems::ProblemSettings,
+) -> ServiceResult<Option<f64>> {
+    use crate::schema::submissions as submissions_schema;
+    let best: Option<f64> = submissions_schema::table
+        .filter(submissions_schema::region.eq(region.to_owned()))
+        .filter(submissions_schema::problem_id.eq(problem_id))
+        .filter(submissions_schema::user_id.eq(user_id))
+        .select(diesel::dsl::max(submissions_schema::score))
+        .first(conn)?;
+
+    Ok(best.map(|best| scale_score(link_score, best, settings)))
+}
+
 pub fn get_column_from_raw(
     conn: &PooledConnection,
     raw: RawLinkedProblemColumn,
@@ -60,7 +100,20 @@ pub fn get_column_from_raw(
         }
     }
 
+    let score = match user_id {
+        Some(user_id) => get_best_score(
+            conn,
+            &raw.region,
+            raw.problem_id,
+            user_id,
+            raw.score,
+            &serde_json::from_str::<problems::ProblemSettings>(&raw.problem_settings).unwrap(),
+        )?,
+        None => None,
+    };
+
     Ok(LinkedProblemColumn {
+        score: score,
         region: raw.region,
         inner_id: raw.inner_id,
         is_accepted: if let Some(inner_id) = user_id {
@@ -0,0 +1,60 @@
+use crate::schema::*;
+use chrono::NaiveDateTime;
+use uuid::Uuid;
+
+/// Verdicts of a submission before and after a rejudge,
+/// `new_*` fields are filled when the rejudged submission is finished.
+/// Submissions which can not be rejudged are recorded with `skip_reason` and left as they were.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable)]
+pub struct RejudgeRecord {
+    pub id: i32,
+    pub rejudge_id: Uuid,
+    pub submission_id: Uuid,
+    pub operator_id: i32,
+    pub rejudge_time: NaiveDateTime,
+    pub old_is_accepted: Option<bool>,
+    pub old_err: Option<String>,
+    pub old_out_results: Option<Vec<String>>,
+    pub new_is_accepted: Option<bool>,
+    pub new_err: Option<String>,
+    pub new_out_results: Option<Vec<String>>,
+    pub finish_time: Option<NaiveDateTime>,
+    pub skip_reason: Option<String>,
+}
+
+#[derive(Debug, Insertable)]
+#[table_name = "rejudge_records"]
+pub struct InsertableRejudgeRecord {
+    pub rejudge_id: Uuid,
+    pub submission_id: Uuid,
+    pub operator_id: i32,
+    pub rejudge_time: NaiveDateTime,
+    pub old_is_accepted: Option<bool>,
+    pub old_err: Option<String>,
+    pub old_out_results: Option<Vec<String>>,
+    pub finish_time: Option<NaiveDateTime>,
+    pub skip_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RejudgeSummary {
+    pub rejudge_id: Uuid,
+    pub count: i32,
+    /// Submissions matched but left as they were, like those in disabled languages
+    pub skipped_count: i32,
+}
+
+/// Selects finished submissions to rejudge, unset fields match anything.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RejudgeFilter {
+    pub submission_id: Option<Uuid>,
+    pub problem_id: Option<i32>,
+    pub region: Option<String>,
+    pub is_accepted: Option<bool>,
+    /// Matches submissions having this result in any test case, like "WRONG_ANSWER"
+    pub out_result: Option<String>,
+    /// Matches submissions failed with this error, like "CompileError"
+    pub err: Option<String>,
+    pub start_time: Option<NaiveDateTime>,
+    pub end_time: Option<NaiveDateTime>,
+}
@@ -1,49 +1,9 @@
-use super::languages::*;
 use crate::schema::submissions;
 use chrono::NaiveDateTime;
 use std::collections::HashSet;
 use uuid::Uuid;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct TestCase {
-    pub input: String,
-    pub output: String,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct JudgeSettings {
-    pub language_config: LanguageConfig,
-    pub src: String,
-    pub max_cpu_time: i32,
-    pub max_memory: i32,
-    pub test_case_id: Option<String>,
-    pub test_case: Option<Vec<TestCase>>,
-    pub spj_version: Option<String>,
-    pub spj_config: Option<SpjConfig>,
-    pub spj_compile_config: Option<SpjCompileConfig>,
-    pub spj_src: Option<String>,
-    pub output: bool,
-}
-
-#[derive(Debug, Clone, Deserialize)]
-pub struct RawJudgeResult {
-    pub err: Option<String>,
-    pub data: serde_json::Value,
-}
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub struct RawJudgeResultData {
-    pub cpu_time: i32,
-    pub real_time: i32,
-    pub memory: i32,
-    pub signal: i32,
-    pub exit_code: i32,
-    pub error: i32,
-    pub result: i32,
-    pub test_case: String,
-    pub output_md5: Option<String>,
-    pub output: Option<String>,
-}
+pub use judge_client::models::{JudgeSettings, RawJudgeResult, RawJudgeResultData, TestCase};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JudgeResultData {
@@ -81,18 +41,22 @@ impl From<RawJudgeResultData> for JudgeResultData {
                     -9 => "SETUID_FAILED".to_owned(),
                     -10 => "EXECVE_FAILED".to_owned(),
                     -11 => "SPJ_ERROR".to_owned(),
+                    -12 => "INTERACTOR_ERROR".to_owned(),
                     _ => "UNKNOWN_ERROR".to_owned(),
                 }
             },
             result: {
-                match raw.result {
-                    -1 => "WRONG_ANSWER".to_owned(),
-                    0 => "SUCCESS".to_owned(),
-                    1 => "CPU_TIME_LIMIT_EXCEEDED".to_owned(),
-                    2 => "REAL_TIME_LIMIT_EXCEEDED".to_owned(),
-                    3 => "MEMORY_LIMIT_EXCEEDED".to_owned(),
-                    4 => "RUNTIME_ERROR".to_owned(),
-                    5 => "SYSTEM_ERROR".to_owned(),
+                match (raw.result, raw.interactor_exit_code) {
+                    // testlib exit codes of the interactor decide once the submission ran fine
+                    (0, Some(1)) | (0, Some(2)) => "WRONG_ANSWER".to_owned(),
+                    (0, Some(code)) if code != 0 => "INTERACTOR_ERROR".to_owned(),
+                    (-1, _) => "WRONG_ANSWER".to_owned(),
+                    (0, _) => "SUCCESS".to_owned(),
+                    (1, _) => "CPU_TIME_LIMIT_EXCEEDED".to_owned(),
+                    (2, _) => "REAL_TIME_LIMIT_EXCEEDED".to_owned(),
+                    (3, _) => "MEMORY_LIMIT_EXCEEDED".to_owned(),
+                    (4, _) => "RUNTIME_ERROR".to_owned(),
+                    (5, _) => "SYSTEM_ERROR".to_owned(),
                     _ => "UNKNOWN_ERROR".to_owned(),
                 }
             },
@@ -125,10 +89,11 @@ impl From<RawJudgeResult> for JudgeResult {
         let details: Option<Vec<JudgeResultData>> = if raw_details.is_some() {
             let mut tmp = Vec::new();
             for raw_detail in raw_details.unwrap() {
-                if raw_detail.result != 0 {
+                let detail = JudgeResultData::from(raw_detail);
+                if detail.result != "SUCCESS" {
                     is_accepted = false;
                 }
-                tmp.push(JudgeResultData::from(raw_detail))
+                tmp.push(detail)
             }
             Some(tmp)
         } else {
@@ -197,6 +162,15 @@ pub struct RawSubmission {
     pub language: Option<String>,
     pub err: Option<String>,
     pub out_results: Option<Vec<String>>,
+    pub pending_time: Option<NaiveDateTime>,
+    /// Points earned out of the full score of the problem
+    pub score: Option<f64>,
+    pub is_late: bool,
+    pub test_case_version: Option<String>,
+    /// Server instance whose judge actors claimed the submission
+    pub judge_instance: Option<String>,
+    /// Times the submission has been sent to judge servers since it was last queued by a user
+    pub judge_attempts: i32,
 }
 
 #[derive(Debug, Clone, Deserialize, Insertable, Queryable)]
@@ -216,6 +190,8 @@ pub struct InsertableSubmission {
     pub max_memory: Option<i32>,
     pub language: Option<String>,
     pub err: Option<String>,
+    pub is_late: bool,
+    pub test_case_version: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -235,6 +211,11 @@ pub struct Submission {
     pub language: Option<String>,
     pub err: Option<String>,
     pub out_results: Option<HashSet<String>>,
+    pub score: Option<f64>,
+    /// Submitted after the close time of a problem set
+    pub is_late: bool,
+    /// Version of the test data judged against
+    pub test_case_version: Option<String>,
 }
 
 impl From<RawSubmission> for Submission {
@@ -258,6 +239,9 @@ impl From<RawSubmission> for Submission {
             max_memory: raw.max_memory,
             language: raw.language,
             err: raw.err,
+            score: raw.score,
+            is_late: raw.is_late,
+            test_case_version: raw.test_case_version,
             out_results: {
                 if let Some(result) = raw.result {
                     let result = serde_json::from_str::<JudgeResult>(&result).unwrap();
@@ -278,6 +262,19 @@ impl From<RawSubmission> for Submission {
     }
 }
 
+impl Submission {
+    /// Keeps only what a contestant may see before an OI contest ends.
+    pub fn hide_verdict(&mut self) {
+        self.result = None;
+        self.is_accepted = None;
+        self.max_time = None;
+        self.max_memory = None;
+        self.err = None;
+        self.out_results = None;
+        self.score = None;
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SlimSubmission {
     pub id: Uuid,
@@ -292,6 +289,9 @@ pub struct SlimSubmission {
     pub max_memory: Option<i32>,
     pub language: Option<String>,
     pub err: Option<String>,
+    pub score: Option<f64>,
+    /// Submitted after the close time of a problem set
+    pub is_late: bool,
 }
 
 impl From<RawSubmission> for SlimSubmission {
@@ -324,6 +324,101 @@ impl From<RawSubmission> for SlimSubmission {
             max_memory: raw.max_memory,
             language: raw.language,
             err: raw.err,
+            score: raw.score,
+            is_late: raw.is_late,
         }
     }
 }
+
+impl SlimSubmission {
+    /// Keeps only what a contestant may see before an OI contest ends.
+    pub fn hide_verdict(&mut self) {
+        self.is_accepted = None;
+        self.out_results = None;
+        self.max_time = None;
+        self.max_memory = None;
+        self.err = None;
+        self.score = None;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JudgeResultSummary {
+    pub err: Option<String>,
+    pub err_reason: Option<String>,
+    pub is_accepted: Option<bool>,
+    pub max_time: Option<i32>,
+    pub max_memory: Option<i32>,
+    pub out_results: Option<HashSet<String>>,
+}
+
+impl From<JudgeResult> for JudgeResultSummary {
+    fn from(result: JudgeResult) -> Self {
+        Self {
+            err: result.err,
+            err_reason: result.err_reason,
+            is_accepted: result.is_accepted,
+            max_time: result.max_time,
+            max_memory: result.max_memory,
+            out_results: result
+                .details
+                .map(|details| details.into_iter().map(|detail| detail.result).collect()),
+        }
+    }
+}
+
+/// A state transition pushed to subscribers of a submission.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubmissionEvent {
+    pub submission_id: Uuid,
+    pub problem_id: i32,
+    pub user_id: i32,
+    pub region: Option<String>,
+    pub state: String,
+    /// Starts from 1, only set while the submission is Waiting
+    pub queue_position: Option<i64>,
+    pub result: Option<JudgeResultSummary>,
+    pub score: Option<f64>,
+    /// Test cases finished so far, only set while the submission is Pending
+    pub progress: Option<JudgeProgress>,
+}
+
+impl From<RawSubmission> for SubmissionEvent {
+    fn from(raw: RawSubmission) -> Self {
+        Self {
+            submission_id: raw.id,
+            problem_id: raw.problem_id,
+            user_id: raw.user_id,
+            region: raw.region,
+            state: raw.state,
+            queue_position: None,
+            progress: None,
+            score: raw.score,
+            result: raw.result.map(|result| {
+                JudgeResultSummary::from(serde_json::from_str::<JudgeResult>(&result).unwrap())
+            }),
+        }
+    }
+}
+
+impl SubmissionEvent {
+    /// Keeps only what a contestant may see before an OI contest ends.
+    pub fn hide_verdict(&mut self) {
+        self.result = None;
+        self.score = None;
+        self.progress = None;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JudgeProgress {
+    pub submission_id: Uuid,
+    /// The judge server the submission is sent to, only its reports are taken
+    #[serde(skip)]
+    pub judge_server_id: i32,
+    pub finished_cases: i32,
+    pub total_cases: Option<i32>,
+    /// One for each finished test case in order of arrival, judge servers may run
+    /// test cases in parallel
+    pub details: Vec<JudgeResultData>,
+}
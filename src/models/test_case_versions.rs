@@ -0,0 +1,62 @@
+use crate::models::test_data_programs::TestDataReport;
+use crate::schema::*;
+use chrono::NaiveDateTime;
+use std::collections::BTreeMap;
+
+/// Test data of a problem as it was uploaded, `version` is the hash of `files`.
+#[derive(Debug, Clone, Queryable, Insertable)]
+#[table_name = "test_case_versions"]
+pub struct RawTestCaseVersion {
+    pub problem_id: i32,
+    pub version: String,
+    pub files: String,
+    pub test_case_count: i32,
+    pub create_time: NaiveDateTime,
+    pub report: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestCaseVersion {
+    pub problem_id: i32,
+    pub version: String,
+    /// Sha256 of every file by its name
+    pub files: BTreeMap<String, String>,
+    pub test_case_count: i32,
+    pub create_time: NaiveDateTime,
+    /// Found by the validator and the reference solution when it was uploaded
+    pub report: Option<TestDataReport>,
+    pub is_current: bool,
+}
+
+impl From<RawTestCaseVersion> for TestCaseVersion {
+    fn from(raw: RawTestCaseVersion) -> Self {
+        Self {
+            problem_id: raw.problem_id,
+            version: raw.version,
+            files: serde_json::from_str(&raw.files).unwrap(),
+            test_case_count: raw.test_case_count,
+            create_time: raw.create_time,
+            report: raw
+                .report
+                .map(|report| serde_json::from_str(&report).unwrap()),
+            is_current: false,
+        }
+    }
+}
+
+/// Files of two versions compared by their hashes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestCaseVersionDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub changed: Vec<String>,
+    pub unchanged: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InsertTestCasesResult {
+    /// Test cases failing the checks are not saved unless forced to
+    pub is_saved: bool,
+    pub version: Option<String>,
+    pub report: Option<TestDataReport>,
+}
@@ -0,0 +1,50 @@
+use crate::schema::*;
+use chrono::NaiveDateTime;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TestDataProgramKind {
+    /// Reads every `.in` from stdin and exits with a non-zero code if it is invalid
+    Validator,
+    /// Generates every missing `.out` and is judged against the others
+    ReferenceSolution,
+}
+
+impl TestDataProgramKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TestDataProgramKind::Validator => "validator",
+            TestDataProgramKind::ReferenceSolution => "reference_solution",
+        }
+    }
+}
+
+/// Program run on every upload of test cases, `kind` is a `TestDataProgramKind`.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable, AsChangeset)]
+#[table_name = "test_data_programs"]
+pub struct TestDataProgram {
+    pub problem_id: i32,
+    pub kind: String,
+    pub language: String,
+    pub src: String,
+    pub update_time: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestCaseReport {
+    pub test_case: i32,
+    /// Result of the validator, `None` if the problem has none
+    pub validator_result: Option<String>,
+    /// Result of the reference solution, `None` if it is not run on the test case
+    pub solution_result: Option<String>,
+    /// The `.out` was missing and is the output of the reference solution
+    pub is_generated: bool,
+    pub is_passed: bool,
+}
+
+/// What the programs of a problem found in uploaded test cases.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestDataReport {
+    pub is_passed: bool,
+    pub test_cases: Vec<TestCaseReport>,
+}
@@ -19,6 +19,16 @@ table! {
     }
 }
 
+table! {
+    checkers (problem_id) {
+        problem_id -> Int4,
+        language -> Text,
+        src -> Text,
+        version -> Text,
+        update_time -> Timestamp,
+    }
+}
+
 table! {
     contests (region) {
         region -> Text,
@@ -31,6 +41,7 @@ table! {
         self_type -> Text,
         salt -> Nullable<Varchar>,
         hash -> Nullable<Bytea>,
+        unfreeze_time -> Nullable<Timestamp>,
     }
 }
 
@@ -49,11 +60,48 @@ table! {
     }
 }
 
+table! {
+    judge_servers (id) {
+        id -> Int4,
+        name -> Text,
+        state -> Text,
+        created_time -> Timestamp,
+        token_digest -> Text,
+    }
+}
+
+table! {
+    languages (name) {
+        name -> Text,
+        description -> Text,
+        config -> Text,
+        time_multiplier -> Float8,
+        memory_multiplier -> Float8,
+        is_enabled -> Bool,
+        checker_config -> Nullable<Text>,
+    }
+}
+
+table! {
+    plagiarism_reports (id) {
+        id -> Int4,
+        region -> Text,
+        problem_id -> Int4,
+        only_accepted -> Bool,
+        threshold -> Float8,
+        submission_count -> Int4,
+        pairs -> Text,
+        operator_id -> Int4,
+        create_time -> Timestamp,
+    }
+}
+
 table! {
     problem_sets (region) {
         region -> Text,
         title -> Text,
         introduction -> Nullable<Text>,
+        settings -> Text,
     }
 }
 
@@ -73,6 +121,7 @@ table! {
         contents -> Text,
         settings -> Text,
         is_released -> Bool,
+        test_case_version -> Nullable<Text>,
     }
 }
 
@@ -92,6 +141,24 @@ table! {
     }
 }
 
+table! {
+    rejudge_records (id) {
+        id -> Int4,
+        rejudge_id -> Uuid,
+        submission_id -> Uuid,
+        operator_id -> Int4,
+        rejudge_time -> Timestamp,
+        old_is_accepted -> Nullable<Bool>,
+        old_err -> Nullable<Text>,
+        old_out_results -> Nullable<Array<Text>>,
+        new_is_accepted -> Nullable<Bool>,
+        new_err -> Nullable<Text>,
+        new_out_results -> Nullable<Array<Text>>,
+        finish_time -> Nullable<Timestamp>,
+        skip_reason -> Nullable<Text>,
+    }
+}
+
 table! {
     samples (submission_id) {
         submission_id -> Uuid,
@@ -116,6 +183,33 @@ table! {
         language -> Nullable<Text>,
         err -> Nullable<Text>,
         out_results -> Nullable<Array<Text>>,
+        pending_time -> Nullable<Timestamp>,
+        score -> Nullable<Float8>,
+        is_late -> Bool,
+        test_case_version -> Nullable<Text>,
+        judge_instance -> Nullable<Text>,
+        judge_attempts -> Int4,
+    }
+}
+
+table! {
+    test_case_versions (problem_id, version) {
+        problem_id -> Int4,
+        version -> Text,
+        files -> Text,
+        test_case_count -> Int4,
+        create_time -> Timestamp,
+        report -> Nullable<Text>,
+    }
+}
+
+table! {
+    test_data_programs (problem_id, kind) {
+        problem_id -> Int4,
+        kind -> Text,
+        language -> Text,
+        src -> Text,
+        update_time -> Timestamp,
     }
 }
 
@@ -139,15 +233,22 @@ table! {
 allow_tables_to_appear_in_same_query!(
     access_control_list,
     announcements,
+    checkers,
     contests,
     group_links,
     groups,
+    judge_servers,
+    languages,
+    plagiarism_reports,
     problem_sets,
     problem_tags,
     problems,
     region_links,
     regions,
+    rejudge_records,
     samples,
     submissions,
+    test_case_versions,
+    test_data_programs,
     users,
 );
@@ -7,8 +7,11 @@ use crate::models::groups::{Group, OutGroup};
 use crate::models::ranks::*;
 use crate::models::regions::*;
 use crate::models::utils::SizedList;
-use crate::services::rank::utils::update_acm_rank_cache;
-use crate::statics::ACM_RANK_CACHE;
+use crate::services::rank::utils::{
+    build_resolver_replay, invalidate_rank_caches, update_acm_rank_cache, update_ioi_rank_cache,
+    update_oi_rank_cache,
+};
+use crate::statics::{ACM_RANK_CACHE, IOI_RANK_CACHE, OI_RANK_CACHE};
 use actix_web::web;
 use chrono::*;
 use diesel::pg::expression::dsl::any;
@@ -67,6 +70,7 @@ pub fn create(
             self_type: self_type,
             salt: salt,
             hash: hash,
+            unfreeze_time: None,
         })
         .execute(conn)?;
 
@@ -190,57 +194,203 @@ pub fn register(
     diesel::insert_into(access_control_list_schema::table)
         .values(&AccessControlListColumn {
             self_type: "user".to_string(),
-            region,
+            region: region.clone(),
             id: user_id,
             is_unrated,
             is_manager: false,
         })
         .execute(conn)?;
 
+    // new participants show up once ranks are built again
+    invalidate_rank_caches(&region);
+
     Ok(())
 }
 
-pub fn get_acm_rank(region: String, pool: web::Data<Pool>) -> ServiceResult<ACMRank> {
-    let conn = &db_connection(&pool)?;
-
+fn load_ranked_contest(
+    region: &str,
+    rule: ContestRule,
+    conn: &PgConnection,
+) -> ServiceResult<Contest> {
     use crate::schema::contests as contests_schema;
     let contest = Contest::from(
         contests_schema::table
-            .filter(contests_schema::region.eq(region.clone()))
+            .filter(contests_schema::region.eq(region.to_owned()))
             .first::<RawContest>(conn)?,
     );
 
-    let contest_state = get_contest_state(contest.clone(), get_cur_naive_date_time());
-    let is_final = if contest_state == ContestState::Ended {
-        true
+    if contest.settings.rule != rule {
+        let hint = "Contest is ranked by other rules.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    Ok(contest)
+}
+
+/// Ranks are cached for a minute, and kept once refreshed after the end.
+fn rank_need_update(contest: &Contest, last_updated_time: Option<NaiveDateTime>) -> bool {
+    if let Some(last_updated_time) = last_updated_time {
+        if contest.end_time.is_some() && last_updated_time > contest.end_time.unwrap() {
+            false
+        } else {
+            get_cur_naive_date_time().timestamp() - last_updated_time.timestamp() > 60
+        }
     } else {
-        false
+        true
+    }
+}
+
+/// Sealed results are revealed once the contest ends,
+/// or once an admin unfreezes the rank if the contest is sealed.
+fn is_acm_rank_final(contest: &Contest) -> bool {
+    get_contest_state(contest.clone(), get_cur_naive_date_time()) == ContestState::Ended
+        && (contest.seal_time.is_none() || contest.unfreeze_time.is_some())
+}
+
+pub fn get_acm_rank(region: String, pool: web::Data<Pool>) -> ServiceResult<ACMRank> {
+    let conn = &db_connection(&pool)?;
+
+    let contest = load_ranked_contest(&region, ContestRule::Acm, conn)?;
+    let is_final = is_acm_rank_final(&contest);
+
+    // finished submissions are applied as they come, the rank is only built
+    // again to reveal sealed results once it is final
+    let need_update = |rank: Option<&ACMRank>| match rank {
+        Some(rank) => is_final && !rank.is_final,
+        None => true,
     };
 
+    if need_update(ACM_RANK_CACHE.read().unwrap().get(&region)) {
+        update_acm_rank_cache(region.clone(), conn, is_final, need_update)?;
+    }
+
+    Ok(ACM_RANK_CACHE
+        .read()
+        .unwrap()
+        .get(&region)
+        .unwrap()
+        .to_owned())
+}
+
+/// Rebuilds the acm rank from the database, in case the cached one went wrong.
+pub fn rebuild_acm_rank(region: String, pool: web::Data<Pool>) -> ServiceResult<ACMRank> {
+    let conn = &db_connection(&pool)?;
+
+    let contest = load_ranked_contest(&region, ContestRule::Acm, conn)?;
+    let is_final = is_acm_rank_final(&contest);
+
+    update_acm_rank_cache(region.clone(), conn, is_final, |_| true)?;
+
+    Ok(ACM_RANK_CACHE
+        .read()
+        .unwrap()
+        .get(&region)
+        .unwrap()
+        .to_owned())
+}
+
+fn load_sealed_contest(region: &str, conn: &PgConnection) -> ServiceResult<Contest> {
+    let contest = load_ranked_contest(region, ContestRule::Acm, conn)?;
+
+    if contest.seal_time.is_none() {
+        let hint = "Contest is not sealed.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+    if get_contest_state(contest.clone(), get_cur_naive_date_time()) != ContestState::Ended {
+        let hint = "Contest has not ended.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    Ok(contest)
+}
+
+/// Reveals sealed results of an ended contest,
+/// returns the resolver replay of the unfreezing.
+pub fn unfreeze(region: String, pool: web::Data<Pool>) -> ServiceResult<ResolverReplay> {
+    let conn = &db_connection(&pool)?;
+
+    let contest = load_sealed_contest(&region, conn)?;
+    if contest.unfreeze_time.is_some() {
+        let hint = "Contest has been unfrozen.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let replay = build_resolver_replay(region.clone(), conn)?;
+
+    use crate::schema::contests as contests_schema;
+    diesel::update(contests_schema::table.filter(contests_schema::region.eq(region.clone())))
+        .set(contests_schema::unfreeze_time.eq(Some(get_cur_naive_date_time())))
+        .execute(conn)?;
+
+    update_acm_rank_cache(region, conn, true, |_| true)?;
+
+    Ok(replay)
+}
+
+/// Returns the resolver replay, contestants may only see it after the unfreezing.
+pub fn get_resolver_replay(
+    region: String,
+    is_admin: bool,
+    pool: web::Data<Pool>,
+) -> ServiceResult<ResolverReplay> {
+    let conn = &db_connection(&pool)?;
+
+    let contest = load_sealed_contest(&region, conn)?;
+    if !is_admin && contest.unfreeze_time.is_none() {
+        let hint = "Contest has not been unfrozen.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    build_resolver_replay(region, conn)
+}
+
+pub fn get_oi_rank(region: String, pool: web::Data<Pool>) -> ServiceResult<OIRank> {
+    let conn = &db_connection(&pool)?;
+
+    let contest = load_ranked_contest(&region, ContestRule::Oi, conn)?;
+    let is_final =
+        get_contest_state(contest.clone(), get_cur_naive_date_time()) == ContestState::Ended;
+
     let need_update = {
-        let rank_cache = ACM_RANK_CACHE.read().unwrap();
-
-        // not been refreshed after ended and in a minute
-        if let Some(rank) = rank_cache.get(&region) {
-            if contest.end_time.is_some() && rank.last_updated_time > contest.end_time.unwrap() {
-                false
-            } else if get_cur_naive_date_time().timestamp() - rank.last_updated_time.timestamp()
-                > 60
-            {
-                true
-            } else {
-                false
-            }
-        } else {
-            true
-        }
+        let rank_cache = OI_RANK_CACHE.read().unwrap();
+        rank_need_update(
+            &contest,
+            rank_cache.get(&region).map(|rank| rank.last_updated_time),
+        )
     };
 
     if need_update {
-        update_acm_rank_cache(region.clone(), conn, is_final)?;
+        update_oi_rank_cache(region.clone(), conn, is_final)?;
     }
 
-    Ok(ACM_RANK_CACHE
+    Ok(OI_RANK_CACHE
+        .read()
+        .unwrap()
+        .get(&region)
+        .unwrap()
+        .to_owned())
+}
+
+pub fn get_ioi_rank(region: String, pool: web::Data<Pool>) -> ServiceResult<IOIRank> {
+    let conn = &db_connection(&pool)?;
+
+    let contest = load_ranked_contest(&region, ContestRule::Ioi, conn)?;
+    let is_final =
+        get_contest_state(contest.clone(), get_cur_naive_date_time()) == ContestState::Ended;
+
+    let need_update = {
+        let rank_cache = IOI_RANK_CACHE.read().unwrap();
+        rank_need_update(
+            &contest,
+            rank_cache.get(&region).map(|rank| rank.last_updated_time),
+        )
+    };
+
+    if need_update {
+        update_ioi_rank_cache(region.clone(), conn, is_final)?;
+    }
+
+    Ok(IOI_RANK_CACHE
         .read()
         .unwrap()
         .get(&region)
@@ -272,7 +422,7 @@ pub fn delete(region: String, pool: web::Data<Pool>) -> ServiceResult<()> {
     )
     .execute(conn)?;
 
-    ACM_RANK_CACHE.write().unwrap().remove(&region);
+    invalidate_rank_caches(&region);
 
     Ok(())
 }
@@ -332,6 +482,8 @@ pub fn update(
         })
         .execute(conn)?;
 
+    invalidate_rank_caches(&region);
+
     Ok(())
 }
 
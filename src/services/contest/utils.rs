@@ -1,10 +1,34 @@
 use crate::models::contests::*;
+use diesel::prelude::*;
 use server_core::errors::{ServiceError, ServiceResult};
+use server_core::utils::time::get_cur_naive_date_time;
 
 pub fn check_settings_legal(settings: ContestSettings) -> ServiceResult<()> {
     if !settings.view_after_end && settings.public_after_end {
         let hint = "Can not allow public_after_end if view_after end is true".to_owned();
         return Err(ServiceError::BadRequest(hint));
     }
+    if settings.acm_penalty.penalty_minutes < 0 {
+        let hint = "Penalty minutes can not be negative.".to_owned();
+        return Err(ServiceError::BadRequest(hint));
+    }
     Ok(())
 }
+
+/// Verdicts of an OI contest stay hidden from contestants until it ends.
+pub fn is_verdict_hidden(conn: &PgConnection, region: &str) -> ServiceResult<bool> {
+    use crate::schema::contests as contests_schema;
+    let raw_contest: Option<RawContest> = contests_schema::table
+        .filter(contests_schema::region.eq(region.to_owned()))
+        .first(conn)
+        .optional()?;
+
+    Ok(match raw_contest {
+        Some(raw_contest) => {
+            let contest = Contest::from(raw_contest);
+            contest.settings.rule == ContestRule::Oi
+                && get_contest_state(contest, get_cur_naive_date_time()) != ContestState::Ended
+        }
+        None => false,
+    })
+}
@@ -1,9 +1,11 @@
+use super::registry::get_by_token_header;
 use crate::judge_actor::{handler::StartJudge, JudgeActorAddr};
 use crate::models::judge_servers::JudgeServerInfo;
 use crate::statics::JUDGE_SERVER_INFOS;
-use actix_web::client::Client;
 use actix_web::web;
-use server_core::errors::ServiceResult;
+use judge_client::JudgeClient;
+use server_core::database::Pool;
+use server_core::errors::{ServiceError, ServiceResult};
 use std::time::SystemTime;
 
 pub async fn record_server_info(
@@ -15,37 +17,65 @@ pub async fn record_server_info(
     service_url: Option<String>,
     token: String,
     judge_actor: web::Data<JudgeActorAddr>,
+    judge_client: web::Data<JudgeClient>,
+    pool: web::Data<Pool>,
 ) -> ServiceResult<()> {
+    let judge_server = {
+        let token = token.clone();
+        web::block(move || get_by_token_header(token, pool)).await?
+    };
+    let judge_server = match judge_server {
+        Some(judge_server) => judge_server,
+        None => {
+            let hint = "Unknown judge server token.".to_string();
+            return Err(ServiceError::UnauthorizedWithHint(hint));
+        }
+    };
+    if judge_server.state == "Disabled" {
+        if let Some(url) = &service_url {
+            let mut lock = JUDGE_SERVER_INFOS.write().unwrap();
+            lock.remove(url);
+        }
+        let hint = "Judge server is disabled.".to_string();
+        return Err(ServiceError::UnauthorizedWithHint(hint));
+    }
+    let is_draining = judge_server.state == "Draining";
+
     if !service_url.is_none() {
         let url = service_url.clone().unwrap();
-        let task_number = {
+        let (task_number, assigned_count, last_assigned_time) = {
             let lock = JUDGE_SERVER_INFOS.read().unwrap();
             if lock.get(&url).is_none() {
-                0
+                (0, 0, None)
             } else {
                 let target = lock.get(&url).unwrap();
-                target.task_number
+                (
+                    target.task_number,
+                    target.assigned_count,
+                    target.last_assigned_time,
+                )
             }
         };
 
-        let response = Client::new()
-            .post(format!("{}/ping", url))
-            .set_header("X-Judge-Server-Token", token.clone())
-            .set_header("Content-Type", "application/json")
-            .send()
-            .await;
+        let response = {
+            let url = url.clone();
+            let token = token.clone();
+            web::block(move || judge_client.ping(&url, &token)).await
+        };
 
-        let is_deprecated = {
-            if !response.is_ok() {
-                info!("setting is_deprecated to true");
+        let is_deprecated = match response {
+            Ok(_) => false,
+            Err(e) => {
+                info!("setting is_deprecated to true: {:?}", e);
                 true
-            } else {
-                false
             }
         };
 
         let now = SystemTime::now();
         let judge_server_info = JudgeServerInfo {
+            server_id: judge_server.id,
+            server_name: judge_server.name,
+            is_draining: is_draining,
             judger_version: judger_version.clone(),
             hostname: hostname.clone(),
             cpu_core: cpu_core,
@@ -56,11 +86,13 @@ pub async fn record_server_info(
             token: token.clone(),
             heartbeat_time: now,
             is_deprecated: is_deprecated,
+            assigned_count: assigned_count,
+            last_assigned_time: last_assigned_time,
         };
         let mut lock = JUDGE_SERVER_INFOS.write().unwrap();
         lock.insert(service_url.clone().unwrap(), judge_server_info);
 
-        if !is_deprecated {
+        if !is_deprecated && !is_draining {
             judge_actor.addr.do_send(StartJudge());
         }
     }
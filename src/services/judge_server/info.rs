@@ -1,13 +1,22 @@
+use crate::judge_actor::scheduler::{pinned_regions, rank_judge_servers, server_load};
 use crate::models::judge_servers::OutJudgeServerInfo;
-use crate::statics::JUDGE_SERVER_INFOS;
+use crate::statics::{JUDGE_SERVER_INFOS, SCHEDULE_CONFIG};
 use actix_identity::Identity;
 use server_core::errors::ServiceResult;
 
 pub async fn server_info(_id: Identity) -> ServiceResult<Vec<OutJudgeServerInfo>> {
     let lock = JUDGE_SERVER_INFOS.read().unwrap();
+    let strategy = SCHEDULE_CONFIG.strategy;
+    let ranked_urls = rank_judge_servers(&lock, strategy);
+
     let mut info_vec: Vec<OutJudgeServerInfo> = Vec::new();
-    for (_url, info) in lock.iter() {
-        info_vec.push(OutJudgeServerInfo::from(info.clone()));
+    for (url, info) in lock.iter() {
+        let mut out_info = OutJudgeServerInfo::from(info.clone());
+        out_info.strategy = strategy;
+        out_info.load = server_load(info, strategy);
+        out_info.pinned_regions = pinned_regions(url);
+        out_info.schedule_rank = ranked_urls.iter().position(|ranked_url| ranked_url == url);
+        info_vec.push(out_info);
     }
     Ok(info_vec)
 }
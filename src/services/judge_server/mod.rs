@@ -3,3 +3,5 @@ pub(crate) use heartbeat::record_server_info;
 
 mod info;
 pub(crate) use info::server_info;
+
+pub mod registry;
@@ -0,0 +1,185 @@
+use crate::models::judge_servers::*;
+use crate::models::utils::SizedList;
+use crate::statics::JUDGE_SERVER_INFOS;
+use actix_web::web;
+use diesel::prelude::*;
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use server_core::database::{db_connection, Pool};
+use server_core::errors::{ServiceError, ServiceResult};
+use server_core::utils::time::get_cur_naive_date_time;
+use sha2::{Digest, Sha256};
+
+const STATES: [&str; 3] = ["Enabled", "Draining", "Disabled"];
+
+fn sha256_hex(value: &str) -> String {
+    hex::encode(Sha256::digest(value.as_bytes()))
+}
+
+/// Judge servers send `sha256(token)` in `X-Judge-Server-Token`, the header is hashed
+/// once more to be stored, so that stored digests can not be sent as headers.
+fn header_digest(token_header: &str) -> String {
+    sha256_hex(token_header)
+}
+
+fn check_state(state: &str) -> ServiceResult<()> {
+    if !STATES.contains(&state) {
+        let hint = format!("State must be one of {:?}.", STATES);
+        return Err(ServiceError::BadRequest(hint));
+    }
+    Ok(())
+}
+
+pub fn create(
+    name: String,
+    token: Option<String>,
+    pool: web::Data<Pool>,
+) -> ServiceResult<CreatedJudgeServer> {
+    let conn = &db_connection(&pool)?;
+
+    let token = match token {
+        Some(token) => token,
+        None => rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(32)
+            .map(char::from)
+            .collect(),
+    };
+
+    let digest = header_digest(&sha256_hex(&token));
+
+    use crate::schema::judge_servers as judge_servers_schema;
+    if judge_servers_schema::table
+        .filter(judge_servers_schema::token_digest.eq(&digest))
+        .count()
+        .get_result::<i64>(conn)?
+        > 0
+    {
+        let hint = "Token is already used by another judge server.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let judge_server: JudgeServer = diesel::insert_into(judge_servers_schema::table)
+        .values(&InsertableJudgeServer {
+            name: name,
+            state: "Enabled".to_owned(),
+            created_time: get_cur_naive_date_time(),
+            token_digest: digest,
+        })
+        .get_result(conn)?;
+
+    Ok(CreatedJudgeServer {
+        id: judge_server.id,
+        name: judge_server.name,
+        token: token,
+        state: judge_server.state,
+        created_time: judge_server.created_time,
+    })
+}
+
+pub fn get_list(pool: web::Data<Pool>) -> ServiceResult<SizedList<JudgeServer>> {
+    let conn = &db_connection(&pool)?;
+
+    use crate::schema::judge_servers as judge_servers_schema;
+    let judge_servers = judge_servers_schema::table
+        .order(judge_servers_schema::id.asc())
+        .load::<JudgeServer>(conn)?;
+
+    Ok(SizedList {
+        total: judge_servers.len() as i64,
+        list: judge_servers,
+    })
+}
+
+pub fn update(
+    id: i32,
+    new_name: Option<String>,
+    new_state: Option<String>,
+    pool: web::Data<Pool>,
+) -> ServiceResult<()> {
+    let conn = &db_connection(&pool)?;
+
+    if let Some(state) = &new_state {
+        check_state(state)?;
+    }
+
+    use crate::schema::judge_servers as judge_servers_schema;
+    let judge_server: Option<JudgeServer> =
+        diesel::update(judge_servers_schema::table.filter(judge_servers_schema::id.eq(id)))
+            .set(JudgeServerForm {
+                name: new_name,
+                state: new_state,
+            })
+            .get_result(conn)
+            .optional()?;
+    let judge_server = match judge_server {
+        Some(judge_server) => judge_server,
+        None => {
+            let hint = "Judge server not found.".to_string();
+            return Err(ServiceError::BadRequest(hint));
+        }
+    };
+
+    // apply to running servers at once instead of waiting for their heartbeats
+    let mut lock = JUDGE_SERVER_INFOS.write().unwrap();
+    if judge_server.state == "Disabled" {
+        lock.retain(|_, info| info.server_id != id);
+    } else {
+        for info in lock.values_mut().filter(|info| info.server_id == id) {
+            info.server_name = judge_server.name.clone();
+            info.is_draining = judge_server.state == "Draining";
+        }
+    }
+
+    Ok(())
+}
+
+pub fn delete(id: i32, pool: web::Data<Pool>) -> ServiceResult<()> {
+    let conn = &db_connection(&pool)?;
+
+    use crate::schema::judge_servers as judge_servers_schema;
+    diesel::delete(judge_servers_schema::table.filter(judge_servers_schema::id.eq(id)))
+        .execute(conn)?;
+
+    let mut lock = JUDGE_SERVER_INFOS.write().unwrap();
+    lock.retain(|_, info| info.server_id != id);
+
+    Ok(())
+}
+
+/// Removes runtime entries whose last heartbeat is older than `timeout_in_sec`,
+/// returns the service urls removed.
+pub fn prune_dead(timeout_in_sec: u64) -> ServiceResult<Vec<String>> {
+    let mut lock = JUDGE_SERVER_INFOS.write().unwrap();
+
+    let dead_urls: Vec<String> = lock
+        .iter()
+        .filter(|(_, info)| match info.heartbeat_time.elapsed() {
+            Ok(elapsed) => elapsed.as_secs() > timeout_in_sec,
+            Err(_) => false,
+        })
+        .map(|(url, _)| url.clone())
+        .collect();
+
+    for url in &dead_urls {
+        lock.remove(url);
+    }
+
+    Ok(dead_urls)
+}
+
+/// Finds the registered judge server sending `token_header` in its requests.
+pub fn get_by_token_header(
+    token_header: String,
+    pool: web::Data<Pool>,
+) -> ServiceResult<Option<JudgeServer>> {
+    let conn = &db_connection(&pool)?;
+
+    use crate::schema::judge_servers as judge_servers_schema;
+    let judge_server = judge_servers_schema::table
+        .filter(judge_servers_schema::token_digest.eq(header_digest(&token_header)))
+        .first::<JudgeServer>(conn)
+        .optional()?;
+
+    Ok(judge_server)
+}
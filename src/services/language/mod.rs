@@ -0,0 +1,158 @@
+use crate::models::languages::*;
+use crate::models::utils::SizedList;
+use actix_web::web;
+use diesel::prelude::*;
+use server_core::database::{db_connection, Pool};
+use server_core::errors::{ServiceError, ServiceResult};
+use std::convert::TryFrom;
+
+fn check_multiplier(multiplier: f64) -> ServiceResult<()> {
+    if multiplier.is_nan() || multiplier <= 0.0 {
+        let hint = "Multiplier must be positive.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+    Ok(())
+}
+
+pub fn create(
+    name: String,
+    description: String,
+    config: LanguageConfig,
+    time_multiplier: Option<f64>,
+    memory_multiplier: Option<f64>,
+    checker_config: Option<CheckerConfig>,
+    pool: web::Data<Pool>,
+) -> ServiceResult<()> {
+    let conn = &db_connection(&pool)?;
+
+    let time_multiplier = time_multiplier.unwrap_or(1.0);
+    let memory_multiplier = memory_multiplier.unwrap_or(1.0);
+    check_multiplier(time_multiplier)?;
+    check_multiplier(memory_multiplier)?;
+
+    use crate::schema::languages as languages_schema;
+    if languages_schema::table
+        .filter(languages_schema::name.eq(name.clone()))
+        .count()
+        .get_result::<i64>(conn)?
+        > 0
+    {
+        let hint = format!("Language {} already exists.", name);
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    diesel::insert_into(languages_schema::table)
+        .values(&InsertableLanguage {
+            name: name,
+            description: description,
+            config: serde_json::to_string(&config).unwrap(),
+            time_multiplier: time_multiplier,
+            memory_multiplier: memory_multiplier,
+            is_enabled: true,
+            checker_config: checker_config.map(|config| serde_json::to_string(&config).unwrap()),
+        })
+        .execute(conn)?;
+
+    Ok(())
+}
+
+pub fn update(
+    name: String,
+    new_description: Option<String>,
+    new_config: Option<LanguageConfig>,
+    new_time_multiplier: Option<f64>,
+    new_memory_multiplier: Option<f64>,
+    new_is_enabled: Option<bool>,
+    new_checker_config: Option<CheckerConfig>,
+    pool: web::Data<Pool>,
+) -> ServiceResult<()> {
+    let conn = &db_connection(&pool)?;
+
+    if let Some(multiplier) = new_time_multiplier {
+        check_multiplier(multiplier)?;
+    }
+    if let Some(multiplier) = new_memory_multiplier {
+        check_multiplier(multiplier)?;
+    }
+
+    use crate::schema::languages as languages_schema;
+    diesel::update(languages_schema::table.filter(languages_schema::name.eq(name)))
+        .set(LanguageForm {
+            description: new_description,
+            config: new_config.map(|config| serde_json::to_string(&config).unwrap()),
+            time_multiplier: new_time_multiplier,
+            memory_multiplier: new_memory_multiplier,
+            is_enabled: new_is_enabled,
+            checker_config: new_checker_config
+                .map(|config| serde_json::to_string(&config).unwrap()),
+        })
+        .execute(conn)?;
+
+    Ok(())
+}
+
+pub fn delete(name: String, pool: web::Data<Pool>) -> ServiceResult<()> {
+    let conn = &db_connection(&pool)?;
+
+    use crate::schema::submissions as submissions_schema;
+    if submissions_schema::table
+        .filter(submissions_schema::language.eq(name.clone()))
+        .count()
+        .get_result::<i64>(conn)?
+        > 0
+    {
+        let hint = "Language is used by submissions, disable it instead.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    use crate::schema::languages as languages_schema;
+    diesel::delete(languages_schema::table.filter(languages_schema::name.eq(name)))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+pub fn get_list(pool: web::Data<Pool>) -> ServiceResult<SizedList<Language>> {
+    let conn = &db_connection(&pool)?;
+
+    use crate::schema::languages as languages_schema;
+    let raw_languages: Vec<RawLanguage> = languages_schema::table
+        .order(languages_schema::name.asc())
+        .load(conn)?;
+
+    Ok(SizedList {
+        total: raw_languages.len() as i64,
+        list: raw_languages
+            .into_iter()
+            .map(Language::try_from)
+            .collect::<ServiceResult<_>>()?,
+    })
+}
+
+/// Looks up an enabled language for judging.
+pub fn get_enabled(conn: &PgConnection, name: &str) -> ServiceResult<Language> {
+    use crate::schema::languages as languages_schema;
+    let raw_language: Option<RawLanguage> = languages_schema::table
+        .filter(languages_schema::name.eq(name.to_owned()))
+        .filter(languages_schema::is_enabled.eq(true))
+        .first(conn)
+        .optional()?;
+
+    match raw_language {
+        Some(raw_language) => Language::try_from(raw_language),
+        None => {
+            let hint = format!("Unsupported language: {}.", name);
+            Err(ServiceError::BadRequest(hint))
+        }
+    }
+}
+
+pub fn get_enabled_list(conn: &PgConnection) -> ServiceResult<Vec<Language>> {
+    use crate::schema::languages as languages_schema;
+    let raw_languages: Vec<RawLanguage> = languages_schema::table
+        .filter(languages_schema::is_enabled.eq(true))
+        .order(languages_schema::name.asc())
+        .load(conn)?;
+
+    raw_languages.into_iter().map(Language::try_from).collect()
+}
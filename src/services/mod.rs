@@ -2,6 +2,8 @@ pub mod announcement;
 pub mod contest;
 pub mod group;
 pub mod judge_server;
+pub mod language;
+pub mod plagiarism;
 pub mod problem;
 pub mod problem_set;
 pub mod problem_tag;
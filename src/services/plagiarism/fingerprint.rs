@@ -0,0 +1,394 @@
+use crate::models::plagiarism_reports::{DiffLine, DiffTag};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+/// Tokens in a k-gram, shorter matches are taken as coincidence.
+const K_GRAM_SIZE: usize = 5;
+/// Hashes in a winnowing window, any match of `K_GRAM_SIZE + WINDOW_SIZE - 1` tokens is found.
+const WINDOW_SIZE: usize = 4;
+
+/// Keywords kept as they are, other identifiers are renamed to one token.
+const KEYWORDS: &[&str] = &[
+    "auto",
+    "bool",
+    "break",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "continue",
+    "def",
+    "default",
+    "del",
+    "delete",
+    "do",
+    "double",
+    "elif",
+    "else",
+    "enum",
+    "except",
+    "extends",
+    "false",
+    "False",
+    "final",
+    "finally",
+    "float",
+    "for",
+    "from",
+    "global",
+    "if",
+    "implements",
+    "import",
+    "in",
+    "int",
+    "interface",
+    "is",
+    "lambda",
+    "long",
+    "new",
+    "None",
+    "not",
+    "or",
+    "and",
+    "pass",
+    "private",
+    "protected",
+    "public",
+    "raise",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "template",
+    "this",
+    "throw",
+    "true",
+    "True",
+    "try",
+    "typedef",
+    "union",
+    "unsigned",
+    "using",
+    "void",
+    "volatile",
+    "while",
+    "with",
+    "yield",
+    "namespace",
+    "operator",
+    "virtual",
+];
+
+fn is_python(language: &str) -> bool {
+    language.starts_with("py")
+}
+
+/// Strips comments and whitespace of `src` and renames identifiers and literals,
+/// so that renaming variables or reformatting code does not hide a copy.
+pub fn tokenize(src: &str, language: &str) -> Vec<String> {
+    let chars: Vec<char> = src.chars().collect();
+    let is_python = is_python(language);
+    let mut tokens = Vec::new();
+
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let next = chars.get(i + 1).copied();
+
+        if c.is_whitespace() {
+            i += 1;
+        } else if (is_python && c == '#')
+            || (!is_python && c == '/' && next == Some('/'))
+            // preprocessor lines are mostly the same includes
+            || (!is_python && c == '#')
+        {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if !is_python && c == '/' && next == Some('*') {
+            i += 2;
+            while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                i += 1;
+            }
+            i += 2;
+        } else if c == '"' || c == '\'' {
+            let is_triple = is_python && next == Some(c) && chars.get(i + 2) == Some(&c);
+            i += if is_triple { 3 } else { 1 };
+            while i < chars.len() {
+                if chars[i] == '\\' {
+                    i += 2;
+                } else if is_triple {
+                    if chars[i] == c && chars.get(i + 1) == Some(&c) && chars.get(i + 2) == Some(&c)
+                    {
+                        i += 3;
+                        break;
+                    }
+                    i += 1;
+                } else if chars[i] == c || chars[i] == '\n' {
+                    i += 1;
+                    break;
+                } else {
+                    i += 1;
+                }
+            }
+            tokens.push("S".to_owned());
+        } else if c.is_ascii_digit() {
+            while i < chars.len() && (chars[i].is_ascii_alphanumeric() || chars[i] == '.') {
+                i += 1;
+            }
+            tokens.push("N".to_owned());
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            if KEYWORDS.contains(&word.as_str()) {
+                tokens.push(word);
+            } else {
+                tokens.push("V".to_owned());
+            }
+        } else {
+            tokens.push(c.to_string());
+            i += 1;
+        }
+    }
+
+    tokens
+}
+
+fn hash_k_gram(k_gram: &[String]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    k_gram.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Selects fingerprints of a token stream by winnowing,
+/// the minimum hash of each window is kept.
+pub fn fingerprints(tokens: &[String]) -> HashSet<u64> {
+    let hashes: Vec<u64> = tokens.windows(K_GRAM_SIZE).map(hash_k_gram).collect();
+
+    if hashes.len() < WINDOW_SIZE {
+        return hashes.into_iter().collect();
+    }
+
+    hashes
+        .windows(WINDOW_SIZE)
+        .map(|window| *window.iter().min().unwrap())
+        .collect()
+}
+
+/// Shared fingerprints over those of the smaller side, so that
+/// a copy padded with extra code is still found.
+pub fn similarity(first: &HashSet<u64>, second: &HashSet<u64>) -> f64 {
+    let smaller = first.len().min(second.len());
+    if smaller == 0 {
+        return 0.0;
+    }
+
+    first.intersection(second).count() as f64 / smaller as f64
+}
+
+/// Lengths of the longest common subsequences of `first` with every prefix of `second`.
+fn lcs_lengths<'a>(first: impl Iterator<Item = &'a usize>, second: &[usize]) -> Vec<u32> {
+    let mut row = vec![0u32; second.len() + 1];
+    for a in first {
+        let mut diagonal = 0;
+        for (j, b) in second.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if a == b {
+                diagonal + 1
+            } else {
+                above.max(row[j])
+            };
+            diagonal = above;
+        }
+    }
+
+    row
+}
+
+/// Collects pairs of positions of equal lines in a longest common subsequence of
+/// `first` and `second` by Hirschberg's algorithm, which takes linear memory.
+/// The slices start at `first_offset` and `second_offset` of the whole sources.
+fn lcs_matches(
+    first: &[usize],
+    second: &[usize],
+    first_offset: usize,
+    second_offset: usize,
+    matches: &mut Vec<(usize, usize)>,
+) {
+    if first.is_empty() || second.is_empty() {
+        return;
+    }
+    if first.len() == 1 {
+        if let Some(j) = second.iter().position(|b| *b == first[0]) {
+            matches.push((first_offset, second_offset + j));
+        }
+        return;
+    }
+
+    // split `second` where the halves of `first` share the most lines with its two parts
+    let middle = first.len() / 2;
+    let (top, bottom) = first.split_at(middle);
+    let second_reversed: Vec<usize> = second.iter().rev().copied().collect();
+    let forward = lcs_lengths(top.iter(), second);
+    let backward = lcs_lengths(bottom.iter().rev(), &second_reversed);
+    let m = second.len();
+    let split = (0..=m)
+        .max_by_key(|k| forward[*k] + backward[m - *k])
+        .unwrap_or(0);
+
+    lcs_matches(top, &second[..split], first_offset, second_offset, matches);
+    lcs_matches(
+        bottom,
+        &second[split..],
+        first_offset + middle,
+        second_offset + split,
+        matches,
+    );
+}
+
+/// Ids of lines, the same for lines equal without surrounding whitespace.
+fn line_ids<'a>(lines: &[&'a str], ids: &mut HashMap<&'a str, usize>) -> Vec<usize> {
+    lines
+        .iter()
+        .map(|line| {
+            let next_id = ids.len();
+            *ids.entry(line.trim()).or_insert(next_id)
+        })
+        .collect()
+}
+
+/// Aligns lines of two sources by their longest common subsequence,
+/// lines are compared without surrounding whitespace.
+pub fn diff_lines(first: &str, second: &str) -> Vec<DiffLine> {
+    let first: Vec<&str> = first.lines().collect();
+    let second: Vec<&str> = second.lines().collect();
+    let (n, m) = (first.len(), second.len());
+
+    // lines are compared by ids of their trimmed contents
+    let mut ids = HashMap::new();
+    let first_ids = line_ids(&first, &mut ids);
+    let second_ids = line_ids(&second, &mut ids);
+
+    let mut matches = Vec::new();
+    lcs_matches(&first_ids, &second_ids, 0, 0, &mut matches);
+
+    let line = |tag, i: Option<usize>, j: Option<usize>| DiffLine {
+        tag,
+        first_line_number: i.map(|i| i as i32 + 1),
+        first: i.map(|i| first[i].to_owned()),
+        second_line_number: j.map(|j| j as i32 + 1),
+        second: j.map(|j| second[j].to_owned()),
+    };
+
+    let mut lines = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    for (match_i, match_j) in matches.into_iter().chain(std::iter::once((n, m))) {
+        while i < match_i {
+            lines.push(line(DiffTag::Delete, Some(i), None));
+            i += 1;
+        }
+        while j < match_j {
+            lines.push(line(DiffTag::Insert, None, Some(j)));
+            j += 1;
+        }
+        if i < n && j < m {
+            lines.push(line(DiffTag::Equal, Some(i), Some(j)));
+            i += 1;
+            j += 1;
+        }
+    }
+
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tags(lines: &[DiffLine]) -> Vec<DiffTag> {
+        lines.iter().map(|line| line.tag).collect()
+    }
+
+    #[test]
+    fn tokenize_renamed_identifiers() {
+        assert_eq!(
+            tokenize("int total = count + 1;", "cpp"),
+            tokenize("int s = n + 42;", "cpp")
+        );
+        assert_ne!(
+            tokenize("int total = count + 1;", "cpp"),
+            tokenize("int total = count - 1;", "cpp")
+        );
+    }
+
+    #[test]
+    fn tokenize_strips_comments_and_whitespace() {
+        let src = "#include <cstdio>\nint main() { // entry\n  /* nothing\n here */ return 0;\n}\n";
+        assert_eq!(
+            tokenize(src, "cpp"),
+            tokenize("int main(){return 0;}", "cpp")
+        );
+    }
+
+    #[test]
+    fn tokenize_python_comments() {
+        assert_eq!(
+            tokenize("x = y // 2  # halve it\n", "py3"),
+            tokenize("a = b // 7", "py3")
+        );
+        // `//` starts a comment in c but divides in python
+        assert_eq!(tokenize("x = y // 2", "c").len(), 3);
+        assert_eq!(tokenize("x = y // 2", "py3").len(), 6);
+        assert_eq!(
+            tokenize("s = \"\"\"a\n# b\"\"\"", "py3"),
+            vec!["V", "=", "S"]
+        );
+    }
+
+    #[test]
+    fn fingerprints_of_short_inputs() {
+        let tokens = |count: usize| vec!["V".to_owned(); count];
+
+        assert!(fingerprints(&tokens(0)).is_empty());
+        assert!(fingerprints(&tokens(K_GRAM_SIZE - 1)).is_empty());
+        assert_eq!(fingerprints(&tokens(K_GRAM_SIZE)).len(), 1);
+        // fewer hashes than a window are all kept
+        let short = tokenize("int a = b + c;", "cpp");
+        assert!(short.len() < K_GRAM_SIZE + WINDOW_SIZE - 1);
+        assert!(!fingerprints(&short).is_empty());
+        assert_eq!(
+            similarity(&fingerprints(&short), &fingerprints(&short)),
+            1.0
+        );
+        assert_eq!(similarity(&fingerprints(&short), &HashSet::new()), 0.0);
+    }
+
+    #[test]
+    fn diff_lines_aligns_common_lines() {
+        let lines = diff_lines("a\nb\nc\nd\n", "a\n  c\nx\nd\n");
+        assert_eq!(
+            tags(&lines),
+            vec![
+                DiffTag::Equal,
+                DiffTag::Delete,
+                DiffTag::Equal,
+                DiffTag::Insert,
+                DiffTag::Equal,
+            ]
+        );
+        assert_eq!(lines[2].first_line_number, Some(3));
+        assert_eq!(lines[2].second_line_number, Some(2));
+
+        assert!(diff_lines("", "").is_empty());
+        assert_eq!(tags(&diff_lines("a\n", "")), vec![DiffTag::Delete]);
+        assert_eq!(tags(&diff_lines("", "a\n")), vec![DiffTag::Insert]);
+    }
+}
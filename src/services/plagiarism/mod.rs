@@ -0,0 +1,215 @@
+pub mod fingerprint;
+
+use crate::models::plagiarism_reports::*;
+use crate::models::submissions::{JudgeSettings, RawSubmission};
+use crate::models::utils::SizedList;
+use actix_web::web;
+use diesel::prelude::*;
+use server_core::database::{db_connection, Pool};
+use server_core::errors::{ServiceError, ServiceResult};
+use server_core::utils::time::get_cur_naive_date_time;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+struct Fingerprinted {
+    submission_id: Uuid,
+    user_id: i32,
+    fingerprints: HashSet<u64>,
+}
+
+fn get_src(raw: &RawSubmission) -> ServiceResult<String> {
+    match serde_json::from_str::<JudgeSettings>(&raw.settings) {
+        Ok(settings) => Ok(settings.src),
+        Err(e) => {
+            let hint = format!(
+                "Judge settings of submission {} are malformed: {}",
+                raw.id, e
+            );
+            Err(ServiceError::InternalServerErrorWithHint(hint))
+        }
+    }
+}
+
+/// Compares the last submission of each user on a problem of a region with each other,
+/// pairs at least `threshold` similar are kept in a new report.
+pub fn check(
+    region: String,
+    problem_id: i32,
+    only_accepted: bool,
+    threshold: f64,
+    operator_id: i32,
+    pool: web::Data<Pool>,
+) -> ServiceResult<PlagiarismReport> {
+    if threshold.is_nan() || threshold < 0.0 || threshold > 1.0 {
+        let hint = "Threshold should be between 0 and 1.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let conn = &db_connection(&pool)?;
+
+    use crate::schema::submissions as submissions_schema;
+    let raw_submissions: Vec<RawSubmission> = submissions_schema::table
+        .filter(submissions_schema::region.eq(region.clone()))
+        .filter(submissions_schema::problem_id.eq(problem_id))
+        .filter(submissions_schema::state.eq("Finished".to_owned()))
+        .filter(
+            submissions_schema::is_accepted
+                .eq(Some(true))
+                .or(!only_accepted),
+        )
+        .order(submissions_schema::submit_time.desc())
+        .load(conn)?;
+
+    let mut user_ids = HashSet::new();
+    let submissions: Vec<Fingerprinted> = raw_submissions
+        .into_iter()
+        // a submission without a readable source is left out, the user's previous one is used
+        .filter_map(|raw| match get_src(&raw) {
+            Ok(src) => Some((raw, src)),
+            Err(e) => {
+                log::error!("{}", e);
+                None
+            }
+        })
+        .filter(|(raw, _)| user_ids.insert(raw.user_id))
+        .map(|(raw, src)| {
+            let tokens = fingerprint::tokenize(&src, raw.language.as_deref().unwrap_or(""));
+            Fingerprinted {
+                submission_id: raw.id,
+                user_id: raw.user_id,
+                fingerprints: fingerprint::fingerprints(&tokens),
+            }
+        })
+        .collect();
+
+    let mut pairs = Vec::new();
+    for (i, first) in submissions.iter().enumerate() {
+        for second in &submissions[i + 1..] {
+            let similarity = fingerprint::similarity(&first.fingerprints, &second.fingerprints);
+            if similarity >= threshold {
+                pairs.push(SuspiciousPair {
+                    first_submission_id: first.submission_id,
+                    first_user_id: first.user_id,
+                    second_submission_id: second.submission_id,
+                    second_user_id: second.user_id,
+                    similarity: similarity,
+                });
+            }
+        }
+    }
+    pairs.sort_by(|a, b| b.similarity.partial_cmp(&a.similarity).unwrap());
+
+    use crate::schema::plagiarism_reports as plagiarism_reports_schema;
+    let raw_report: RawPlagiarismReport = diesel::insert_into(plagiarism_reports_schema::table)
+        .values(&InsertablePlagiarismReport {
+            region: region,
+            problem_id: problem_id,
+            only_accepted: only_accepted,
+            threshold: threshold,
+            submission_count: submissions.len() as i32,
+            pairs: serde_json::to_string(&pairs).unwrap(),
+            operator_id: operator_id,
+            create_time: get_cur_naive_date_time(),
+        })
+        .get_result(conn)?;
+
+    Ok(PlagiarismReport::from(raw_report))
+}
+
+pub fn get_report_list(
+    region: String,
+    problem_id_filter: Option<i32>,
+    limit: i32,
+    offset: i32,
+    pool: web::Data<Pool>,
+) -> ServiceResult<SizedList<PlagiarismReport>> {
+    let conn = &db_connection(&pool)?;
+
+    use crate::schema::plagiarism_reports as plagiarism_reports_schema;
+    let target = plagiarism_reports_schema::table
+        .filter(plagiarism_reports_schema::region.eq(region))
+        .filter(
+            plagiarism_reports_schema::problem_id
+                .nullable()
+                .eq(problem_id_filter)
+                .or(problem_id_filter.is_none()),
+        );
+
+    let total: i64 = target.clone().count().get_result(conn)?;
+
+    let raw_reports: Vec<RawPlagiarismReport> = target
+        .offset(offset.into())
+        .limit(limit.into())
+        .order(plagiarism_reports_schema::id.desc())
+        .load(conn)?;
+
+    Ok(SizedList {
+        total: total,
+        list: raw_reports
+            .into_iter()
+            .map(PlagiarismReport::from)
+            .collect(),
+    })
+}
+
+fn load_report(
+    conn: &PgConnection,
+    region: String,
+    report_id: i32,
+) -> ServiceResult<PlagiarismReport> {
+    use crate::schema::plagiarism_reports as plagiarism_reports_schema;
+    let raw_report: RawPlagiarismReport = plagiarism_reports_schema::table
+        .filter(plagiarism_reports_schema::id.eq(report_id))
+        .filter(plagiarism_reports_schema::region.eq(region))
+        .first(conn)?;
+
+    Ok(PlagiarismReport::from(raw_report))
+}
+
+pub fn get_report(
+    region: String,
+    report_id: i32,
+    pool: web::Data<Pool>,
+) -> ServiceResult<PlagiarismReport> {
+    let conn = &db_connection(&pool)?;
+
+    load_report(conn, region, report_id)
+}
+
+/// Puts sources of a suspicious pair in a report side by side.
+pub fn get_diff(
+    region: String,
+    report_id: i32,
+    first_submission_id: Uuid,
+    second_submission_id: Uuid,
+    pool: web::Data<Pool>,
+) -> ServiceResult<SideBySideDiff> {
+    let conn = &db_connection(&pool)?;
+
+    let report = load_report(conn, region, report_id)?;
+    let pair = match report.pairs.iter().find(|pair| {
+        pair.first_submission_id == first_submission_id
+            && pair.second_submission_id == second_submission_id
+    }) {
+        Some(pair) => pair,
+        None => {
+            let hint = "Pair is not in the report.".to_string();
+            return Err(ServiceError::BadRequest(hint));
+        }
+    };
+
+    use crate::schema::submissions as submissions_schema;
+    let first: RawSubmission = submissions_schema::table
+        .filter(submissions_schema::id.eq(first_submission_id))
+        .first(conn)?;
+    let second: RawSubmission = submissions_schema::table
+        .filter(submissions_schema::id.eq(second_submission_id))
+        .first(conn)?;
+
+    Ok(SideBySideDiff {
+        first_submission_id: first_submission_id,
+        second_submission_id: second_submission_id,
+        similarity: pair.similarity,
+        lines: fingerprint::diff_lines(&get_src(&first)?, &get_src(&second)?),
+    })
+}
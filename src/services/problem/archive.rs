@@ -0,0 +1,268 @@
+use crate::statics::ARCHIVE_LIMITS;
+use actix_files::NamedFile;
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use server_core::errors::{ServiceError, ServiceResult};
+use std::fs::{self, File};
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Bounds of uploaded archives, sizes are counted by the bytes really
+/// extracted rather than the sizes an archive claims.
+#[derive(Debug, Clone, Copy)]
+pub struct ArchiveLimits {
+    /// Bytes of an upload, and of all files extracted from it
+    pub max_size: u64,
+    pub max_file_count: usize,
+}
+
+impl Default for ArchiveLimits {
+    fn default() -> Self {
+        Self {
+            max_size: 1024 * 1024 * 1024,
+            max_file_count: 10000,
+        }
+    }
+}
+
+fn malformed(e: impl std::fmt::Display) -> ServiceError {
+    ServiceError::BadRequest(format!("Malformed archive: {}", e))
+}
+
+pub fn too_large(limits: &ArchiveLimits) -> ServiceError {
+    let hint = format!(
+        "Archive is larger than {} MB.",
+        limits.max_size / 1024 / 1024
+    );
+    ServiceError::BadRequest(hint)
+}
+
+fn extract_entries(zip_path: &str, folder: &str, limits: &ArchiveLimits) -> ServiceResult<()> {
+    let mut archive = ZipArchive::new(File::open(zip_path)?).map_err(malformed)?;
+    if archive.len() > limits.max_file_count {
+        let hint = format!("Archive has more than {} files.", limits.max_file_count);
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let mut total_size = 0;
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i).map_err(malformed)?;
+        let target = match entry.enclosed_name() {
+            Some(relative_path) => Path::new(folder).join(relative_path),
+            None => {
+                let hint = format!("{} points outside of the archive.", entry.name());
+                return Err(ServiceError::BadRequest(hint));
+            }
+        };
+
+        if entry.is_dir() {
+            fs::create_dir_all(&target)?;
+            continue;
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // reading a byte more than allowed tells archives lying about their sizes
+        let mut file = File::create(&target)?;
+        total_size += io::copy(
+            &mut (&mut entry).take(limits.max_size - total_size + 1),
+            &mut file,
+        )
+        .map_err(malformed)?;
+        if total_size > limits.max_size {
+            return Err(too_large(limits));
+        }
+    }
+
+    Ok(())
+}
+
+/// Extracts a zip into `folder`, which is removed if the archive is refused.
+pub fn extract(zip_path: &str, folder: &str) -> ServiceResult<()> {
+    let limits = *ARCHIVE_LIMITS.read().unwrap();
+
+    fs::create_dir_all(folder)?;
+    let res = extract_entries(zip_path, folder, &limits);
+    if res.is_err() {
+        fs::remove_dir_all(folder).unwrap_or({});
+    }
+
+    res
+}
+
+/// Packs files in `folder` kept by `filter` into a zip, named by their paths relative to `folder`.
+pub fn compress(folder: &str, zip_path: &str, filter: &dyn Fn(&Path) -> bool) -> ServiceResult<()> {
+    let internal_error = |e: zip::result::ZipError| {
+        ServiceError::InternalServerErrorWithHint(format!("Failed to write the archive: {}", e))
+    };
+
+    if let Some(parent) = Path::new(zip_path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let mut writer = ZipWriter::new(File::create(zip_path)?);
+    let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let mut folders = vec![PathBuf::from(folder)];
+    while let Some(current) = folders.pop() {
+        let mut entries = fs::read_dir(&current)?.collect::<Result<Vec<_>, _>>()?;
+        entries.sort_by_key(|entry| entry.file_name());
+
+        for entry in entries {
+            let path = entry.path();
+            if entry.file_type()?.is_dir() {
+                folders.push(path);
+                continue;
+            }
+            if !filter(&path) {
+                continue;
+            }
+
+            let name: Vec<String> = path
+                .strip_prefix(folder)
+                .unwrap()
+                .components()
+                .map(|component| component.as_os_str().to_string_lossy().into_owned())
+                .collect();
+            writer
+                .start_file(name.join("/"), options)
+                .map_err(internal_error)?;
+            io::copy(&mut File::open(&path)?, &mut writer)?;
+        }
+    }
+    writer.finish().map_err(internal_error)?;
+
+    Ok(())
+}
+
+/// Opens a packed zip for downloading as `file_name`, the zip itself is removed
+/// right away since it is read through the opened file.
+pub fn open_download(zip_path: &str, file_name: &str) -> ServiceResult<NamedFile> {
+    let file = NamedFile::open(zip_path)?.set_content_disposition(ContentDisposition {
+        disposition: DispositionType::Attachment,
+        parameters: vec![DispositionParam::Filename(file_name.to_owned())],
+    });
+    fs::remove_file(zip_path)?;
+
+    Ok(file)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use uuid::Uuid;
+
+    const LIMITS: ArchiveLimits = ArchiveLimits {
+        max_size: 64,
+        max_file_count: 4,
+    };
+
+    fn temp_folder() -> PathBuf {
+        let folder = std::env::temp_dir().join(format!("archive-test-{}", Uuid::new_v4()));
+        fs::create_dir_all(&folder).unwrap();
+        folder
+    }
+
+    /// Writes `entries` of (name, content) into `folder/test.zip`, returns its path.
+    fn build_zip(folder: &Path, entries: &[(&str, &[u8])]) -> String {
+        let zip_path = folder.join("test.zip");
+        let mut writer = ZipWriter::new(File::create(&zip_path).unwrap());
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        for (name, content) in entries {
+            writer.start_file(*name, options).unwrap();
+            writer.write_all(content).unwrap();
+        }
+        writer.finish().unwrap();
+        zip_path.to_str().unwrap().to_owned()
+    }
+
+    fn assert_bad_request(res: ServiceResult<()>) {
+        match res {
+            Err(ServiceError::BadRequest(_)) => {}
+            other => panic!("expected a bad request, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn extract_within_limits() {
+        let root = temp_folder();
+        let zip_path = build_zip(&root, &[("1.in", b"1 2\n"), ("data/1.out", b"3\n")]);
+        let target = root.join("out");
+
+        extract_entries(&zip_path, target.to_str().unwrap(), &LIMITS).unwrap();
+        assert_eq!(fs::read(target.join("1.in")).unwrap(), b"1 2\n");
+        assert_eq!(fs::read(target.join("data/1.out")).unwrap(), b"3\n");
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn refuse_entry_outside_of_archive() {
+        let root = temp_folder();
+        let zip_path = build_zip(&root, &[("../escaped.txt", b"gotcha")]);
+        let target = root.join("out");
+
+        assert_bad_request(extract_entries(
+            &zip_path,
+            target.to_str().unwrap(),
+            &LIMITS,
+        ));
+        assert!(!root.join("escaped.txt").exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn refuse_too_many_entries() {
+        let root = temp_folder();
+        let names: Vec<String> = (0..=LIMITS.max_file_count)
+            .map(|i| format!("{}.in", i))
+            .collect();
+        let entries: Vec<(&str, &[u8])> =
+            names.iter().map(|name| (name.as_str(), &b""[..])).collect();
+        let zip_path = build_zip(&root, &entries);
+        let target = root.join("out");
+
+        assert_bad_request(extract_entries(
+            &zip_path,
+            target.to_str().unwrap(),
+            &LIMITS,
+        ));
+        assert!(!target.join("0.in").exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn refuse_over_limit_entry() {
+        let root = temp_folder();
+        // compresses to far less than the limit, but extracts to more
+        let content = vec![b'0'; LIMITS.max_size as usize * 16];
+        let zip_path = build_zip(&root, &[("1.in", &content)]);
+        assert!(fs::metadata(&zip_path).unwrap().len() < content.len() as u64);
+        let target = root.join("out");
+
+        assert_bad_request(extract_entries(
+            &zip_path,
+            target.to_str().unwrap(),
+            &LIMITS,
+        ));
+        assert!(fs::metadata(target.join("1.in")).unwrap().len() <= LIMITS.max_size + 1);
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn extract_removes_refused_folder() {
+        let root = temp_folder();
+        let zip_path = build_zip(&root, &[("../escaped.txt", b"gotcha")]);
+        let target = root.join("out");
+
+        assert_bad_request(extract(&zip_path, target.to_str().unwrap()));
+        assert!(!target.exists());
+
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,165 @@
+use super::test_case_version;
+use crate::judge_actor::scheduler::schedule_judge_servers;
+use crate::models::checkers::Checker;
+use crate::models::languages::CheckerConfig;
+use crate::services::language::get_enabled;
+use actix_web::web;
+use diesel::prelude::*;
+use judge_client::models::CompileSpjRequest;
+use judge_client::{JudgeClient, JudgeClientError};
+use server_core::database::{db_connection, Pool};
+use server_core::errors::{ServiceError, ServiceResult};
+use server_core::utils::time::get_cur_naive_date_time;
+use sha2::{Digest, Sha256};
+use std::fs;
+
+/// Judge servers keep compiled checkers by version, so it changes whenever the source does.
+pub fn source_version(src: &str) -> String {
+    hex::encode(Sha256::digest(src.as_bytes()))
+}
+
+pub(super) fn save(conn: &PgConnection, checker: &Checker) -> ServiceResult<()> {
+    use crate::schema::checkers as checkers_schema;
+    diesel::insert_into(checkers_schema::table)
+        .values(checker)
+        .on_conflict(checkers_schema::problem_id)
+        .do_update()
+        .set(checker)
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Compiles the checker on a judge server before saving it,
+/// compiler errors are returned to the uploader.
+pub fn upload(
+    problem_id: i32,
+    language: String,
+    src: String,
+    pool: web::Data<Pool>,
+    judge_client: web::Data<JudgeClient>,
+) -> ServiceResult<Checker> {
+    let conn = &db_connection(&pool)?;
+
+    use crate::schema::problems as problems_schema;
+    problems_schema::table
+        .filter(problems_schema::id.eq(problem_id))
+        .select(problems_schema::id)
+        .first::<i32>(conn)?;
+
+    let checker_config = match get_enabled(conn, &language)?.checker_config {
+        Some(checker_config) => checker_config,
+        None => {
+            let hint = format!("Checkers can not be written in {}.", language);
+            return Err(ServiceError::BadRequest(hint));
+        }
+    };
+
+    let server = match schedule_judge_servers().into_iter().next() {
+        Some(server) => server,
+        None => {
+            let hint = "No judge server available to compile the checker.".to_string();
+            return Err(ServiceError::BadRequest(hint));
+        }
+    };
+
+    let version = source_version(&src);
+    match judge_client.compile_spj(
+        &server.url,
+        &server.token,
+        &CompileSpjRequest {
+            src: src.clone(),
+            spj_version: version.clone(),
+            spj_compile_config: checker_config.compile,
+        },
+    ) {
+        Ok(_) => (),
+        Err(JudgeClientError::CompileError(message)) => {
+            let hint = format!("Checker compile error: {}", message);
+            return Err(ServiceError::BadRequest(hint));
+        }
+        Err(e) => {
+            let hint = format!("Failed to compile the checker: {}", e);
+            return Err(ServiceError::InternalServerErrorWithHint(hint));
+        }
+    }
+
+    let checker = Checker {
+        problem_id: problem_id,
+        language: language,
+        src: src,
+        version: version,
+        update_time: get_cur_naive_date_time(),
+    };
+    save(conn, &checker)?;
+
+    Ok(checker)
+}
+
+pub fn get(problem_id: i32, pool: web::Data<Pool>) -> ServiceResult<Checker> {
+    let conn = &db_connection(&pool)?;
+
+    get_for_judge(conn, problem_id)
+}
+
+/// Checkers used to be `spj_src.c` or `spj_src.cpp` in the test case folder.
+fn read_legacy(path: &str, problem_id: i32) -> Option<Checker> {
+    for language in &["cpp", "c"] {
+        if let Ok(src) = fs::read_to_string(format!("{}/spj_src.{}", path, language)) {
+            return Some(Checker {
+                problem_id: problem_id,
+                language: language.to_string(),
+                version: source_version(&src),
+                src: src,
+                update_time: get_cur_naive_date_time(),
+            });
+        }
+    }
+
+    None
+}
+
+pub fn get_for_judge(conn: &PgConnection, problem_id: i32) -> ServiceResult<Checker> {
+    use crate::schema::checkers as checkers_schema;
+    let checker: Option<Checker> = checkers_schema::table
+        .filter(checkers_schema::problem_id.eq(problem_id))
+        .first(conn)
+        .optional()?;
+
+    let legacy = || {
+        test_case_version::current_folder(conn, problem_id)
+            .ok()
+            .and_then(|path| read_legacy(&path, problem_id))
+    };
+    match checker.or_else(legacy) {
+        Some(checker) => Ok(checker),
+        None => {
+            let hint = "Checker of the problem is not uploaded.".to_string();
+            Err(ServiceError::BadRequest(hint))
+        }
+    }
+}
+
+/// The checker along with how judge servers compile and run it.
+pub fn get_for_judge_with_config(
+    conn: &PgConnection,
+    problem_id: i32,
+) -> ServiceResult<(Checker, CheckerConfig)> {
+    let checker = get_for_judge(conn, problem_id)?;
+    match get_enabled(conn, &checker.language)?.checker_config {
+        Some(checker_config) => Ok((checker, checker_config)),
+        None => {
+            let hint = format!("Checkers can not be written in {}.", checker.language);
+            Err(ServiceError::BadRequest(hint))
+        }
+    }
+}
+
+/// Saves `spj_src.c` or `spj_src.cpp` shipped with imported test cases.
+pub fn import(conn: &PgConnection, problem_id: i32, path: &str) -> ServiceResult<()> {
+    if let Some(checker) = read_legacy(path, problem_id) {
+        save(conn, &checker)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,200 @@
+use super::*;
+use crate::services::problem::package;
+use roxmltree::{Document, Node};
+use server_core::errors::{ServiceError, ServiceResult};
+use std::fs;
+
+fn texts<'a>(item: Node<'a, '_>, name: &str) -> Vec<&'a str> {
+    item.children()
+        .filter(|child| child.has_tag_name(name))
+        .map(|child| child.text().unwrap_or_default())
+        .collect()
+}
+
+fn text(item: Node, name: &str) -> Option<String> {
+    texts(item, name)
+        .into_iter()
+        .next()
+        .filter(|text| !text.trim().is_empty())
+        .map(|text| text.to_owned())
+}
+
+/// Time limits are in seconds unless `unit` says otherwise, memory limits are in megabytes.
+fn read_limit(item: Node, name: &str, scales: &[(&str, f64)]) -> Result<i32, PackageError> {
+    let limit = item
+        .children()
+        .find(|child| child.has_tag_name(name))
+        .ok_or_else(|| package_error(name, "Limit is missing.".to_owned()))?;
+    let value: f64 = limit
+        .text()
+        .unwrap_or_default()
+        .trim()
+        .parse()
+        .map_err(|_| package_error(name, "Limit is not a number.".to_owned()))?;
+
+    let unit = limit
+        .attribute("unit")
+        .unwrap_or(scales[0].0)
+        .to_lowercase();
+    match scales.iter().find(|(unit_name, _)| *unit_name == unit) {
+        Some((_, scale)) => Ok((value * scale) as i32),
+        None => Err(package_error(name, format!("Unknown unit {}.", unit))),
+    }
+}
+
+/// Images are shipped in base64 beside the statement, they are inlined as data urls.
+fn inline_images(item: Node, description: String) -> String {
+    let mut description = description;
+    for image in item.children().filter(|child| child.has_tag_name("img")) {
+        let src = texts(image, "src").into_iter().next().unwrap_or_default();
+        let data = texts(image, "base64")
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        if src.is_empty() || data.is_empty() {
+            continue;
+        }
+
+        let extension = src.rsplit('.').next().unwrap_or_default().to_lowercase();
+        let mime = match extension.as_str() {
+            "jpg" | "jpeg" => "image/jpeg",
+            "gif" => "image/gif",
+            "svg" => "image/svg+xml",
+            _ => "image/png",
+        };
+        description = description.replace(
+            src,
+            &format!(
+                "data:{};base64,{}",
+                mime,
+                data.split_whitespace().collect::<String>()
+            ),
+        );
+    }
+
+    description
+}
+
+/// Converts an item of the xml to a problem folder in `target`.
+fn convert_item(item: Node, target: &str) -> Result<(), PackageError> {
+    let title = text(item, "title").unwrap_or_default();
+
+    // special judges of HUSTOJ take `{in} {out} {user_out}`,
+    // the ones here take `{in} {user_out} {ans}` like testlib
+    if item.children().any(|child| child.has_tag_name("spj")) {
+        let message = "Special judges of HUSTOJ are not supported.";
+        return Err(package_error("spj", message.to_owned()));
+    }
+
+    let settings = super::new_settings(
+        read_limit(item, "time_limit", &[("s", 1000.0), ("ms", 1.0)])?,
+        read_limit(
+            item,
+            "memory_limit",
+            &[("mb", 1024.0 * 1024.0), ("kb", 1024.0)],
+        )?,
+    );
+
+    let sample_inputs = texts(item, "sample_input");
+    let sample_outputs = texts(item, "sample_output");
+    if sample_inputs.len() != sample_outputs.len() {
+        let message = "Every sample input needs a sample output.";
+        return Err(package_error("sample_output", message.to_owned()));
+    }
+    let examples: Vec<Example> = sample_inputs
+        .into_iter()
+        .zip(sample_outputs.into_iter())
+        .map(|(input, output)| Example {
+            input: input.to_owned(),
+            output: output.to_owned(),
+        })
+        .collect();
+
+    let test_inputs = texts(item, "test_input");
+    let test_outputs = texts(item, "test_output");
+    if test_inputs.len() != test_outputs.len() {
+        let message = "Every test input needs a test output.";
+        return Err(package_error("test_output", message.to_owned()));
+    }
+    fs::create_dir_all(format!("{}/TestCases", target))
+        .map_err(|e| package_error("test_input", e.to_string()))?;
+    for (i, (input, output)) in test_inputs.into_iter().zip(test_outputs).enumerate() {
+        fs::write(format!("{}/TestCases/{}.in", target, i + 1), input)
+            .map_err(|e| package_error("test_input", e.to_string()))?;
+        fs::write(format!("{}/TestCases/{}.out", target, i + 1), output)
+            .map_err(|e| package_error("test_output", e.to_string()))?;
+    }
+
+    let description = super::join_sections(
+        text(item, "description"),
+        vec![
+            ("Input", text(item, "input")),
+            ("Output", text(item, "output")),
+            ("Hint", text(item, "hint")),
+            ("Source", text(item, "source")),
+        ],
+    )
+    .map(|description| inline_images(item, description));
+
+    package::write_folder(
+        target,
+        &ProblemInfo {
+            title: title,
+            tags: Vec::new(),
+            difficulty: super::DEFAULT_DIFFICULTY,
+        },
+        &ProblemContents {
+            description: description,
+            example_count: examples.len() as i32,
+            examples: examples,
+        },
+        &settings,
+        None,
+    )
+    .map_err(|e| package_error("item", e.to_string()))
+}
+
+/// Converts every item of a Free Problem Set xml to a folder in `target`.
+/// Items failed to convert are returned.
+pub fn convert(buf: &[u8], target: &str) -> ServiceResult<Vec<CreateProblemsResult>> {
+    let xml = match std::str::from_utf8(buf) {
+        Ok(xml) => xml,
+        Err(_) => {
+            let hint = "The xml should be encoded in utf-8.".to_string();
+            return Err(ServiceError::BadRequest(hint));
+        }
+    };
+    let document = match Document::parse(xml) {
+        Ok(document) => document,
+        Err(e) => {
+            let hint = format!("Malformed xml: {}", e);
+            return Err(ServiceError::BadRequest(hint));
+        }
+    };
+
+    let items: Vec<Node> = document
+        .root_element()
+        .children()
+        .filter(|child| child.has_tag_name("item"))
+        .collect();
+    if items.is_empty() {
+        let hint = "No problem found in the xml.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let mut broken = Vec::new();
+    for (i, item) in items.into_iter().enumerate() {
+        let name = format!(
+            "{} {}",
+            i + 1,
+            sanitize_filename::sanitize(text(item, "title").unwrap_or_default())
+        );
+        let problem_target = format!("{}/{}", target, name);
+        if let Err(error) = convert_item(item, &problem_target) {
+            fs::remove_dir_all(&problem_target).unwrap_or({});
+            broken.push(super::broken(name, error));
+        }
+    }
+
+    Ok(broken)
+}
@@ -0,0 +1,76 @@
+pub mod fps;
+pub mod polygon;
+
+use crate::models::problems::*;
+use crate::statics::PROBLEM_TAG_NAME_CACHE;
+use std::collections::BTreeMap;
+
+/// Other formats do not rate problems, setters fill it in after the import.
+const DEFAULT_DIFFICULTY: f64 = 0.0;
+
+fn new_settings(max_cpu_time: i32, max_memory: i32) -> ProblemSettings {
+    ProblemSettings {
+        problem_type: ProblemType::Batch,
+        is_spj: false,
+        high_performance_max_cpu_time: max_cpu_time,
+        high_performance_max_memory: max_memory,
+        language_limits: BTreeMap::new(),
+        opaque_output: false,
+        test_case_count: None,
+        stop_on_failure: false,
+        subtasks: Vec::new(),
+    }
+}
+
+/// Tags unknown here are left out rather than failing the import.
+fn known_tags(tags: Vec<String>) -> Vec<String> {
+    let lock = PROBLEM_TAG_NAME_CACHE.read().unwrap();
+    tags.into_iter()
+        .filter(|tag| lock.values().any(|name| name == tag))
+        .collect()
+}
+
+/// Joins parts of a statement into markdown, every part but the first gets a heading.
+fn join_sections(legend: Option<String>, sections: Vec<(&str, Option<String>)>) -> Option<String> {
+    let mut parts: Vec<String> = legend.into_iter().collect();
+    for (heading, section) in sections {
+        if let Some(section) = section {
+            parts.push(format!("## {}\n\n{}", heading, section.trim()));
+        }
+    }
+
+    if parts.is_empty() {
+        None
+    } else {
+        Some(parts.join("\n\n"))
+    }
+}
+
+/// Language names of checkers, the same as the names of the enabled languages.
+fn checker_language(name: &str) -> Option<&'static str> {
+    let name = name.to_lowercase();
+    if name.starts_with("cpp") || name.starts_with("c++") || name.starts_with("g++") {
+        Some("cpp")
+    } else if name == "c" || name.starts_with("c.") || name.starts_with("gcc") {
+        Some("c")
+    } else {
+        None
+    }
+}
+
+fn package_error(file: &str, message: String) -> PackageError {
+    PackageError {
+        file: file.to_owned(),
+        message: message,
+    }
+}
+
+fn broken(title: String, error: PackageError) -> CreateProblemsResult {
+    CreateProblemsResult {
+        title: title,
+        is_success: false,
+        id: None,
+        effective_limits: None,
+        errors: vec![error],
+    }
+}
@@ -0,0 +1,318 @@
+use super::*;
+use crate::services::problem::package;
+use roxmltree::{Document, Node};
+use server_core::errors::{ServiceError, ServiceResult};
+use std::fs;
+use std::path::Path;
+
+fn child<'a, 'input>(node: Node<'a, 'input>, name: &str) -> Option<Node<'a, 'input>> {
+    node.children().find(|child| child.has_tag_name(name))
+}
+
+fn child_text<'a>(node: Node<'a, '_>, name: &str) -> Option<&'a str> {
+    child(node, name).and_then(|child| child.text())
+}
+
+/// Fills a path pattern of tests like `tests/%02d`.
+fn format_pattern(pattern: &str, id: i32) -> String {
+    let start = match pattern.find('%') {
+        Some(start) => start,
+        None => return pattern.to_owned(),
+    };
+    let end = match pattern[start..].find('d') {
+        Some(end) => start + end,
+        None => return pattern.to_owned(),
+    };
+
+    let spec = &pattern[start + 1..end];
+    let width: usize = spec.parse().unwrap_or(0);
+    let number = if spec.starts_with('0') {
+        format!("{:0width$}", id, width = width)
+    } else {
+        format!("{:width$}", id, width = width)
+    };
+
+    format!("{}{}{}", &pattern[..start], number, &pattern[end + 1..])
+}
+
+fn read_section(path: &str, name: &str) -> Option<String> {
+    fs::read_to_string(format!("{}/{}", path, name))
+        .ok()
+        .filter(|section| !section.trim().is_empty())
+}
+
+fn read_description(path: &str, language: &str) -> Option<String> {
+    let sections_path = format!("{}/statement-sections/{}", path, language);
+    if !Path::new(&sections_path).exists() {
+        return None;
+    }
+
+    super::join_sections(
+        read_section(&sections_path, "legend.tex"),
+        vec![
+            ("Input", read_section(&sections_path, "input.tex")),
+            ("Output", read_section(&sections_path, "output.tex")),
+            (
+                "Interaction",
+                read_section(&sections_path, "interaction.tex"),
+            ),
+            ("Scoring", read_section(&sections_path, "scoring.tex")),
+            ("Notes", read_section(&sections_path, "notes.tex")),
+        ],
+    )
+}
+
+struct PolygonTest {
+    is_sample: bool,
+    points: Option<f64>,
+    group: Option<String>,
+}
+
+/// Groups become subtasks, or every test with points does if there are no groups.
+fn read_subtasks(testset: Node, tests: &[PolygonTest]) -> Vec<Subtask> {
+    let groups: Vec<Node> = child(testset, "groups")
+        .map(|groups| {
+            groups
+                .children()
+                .filter(|group| group.has_tag_name("group"))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    if groups.is_empty() {
+        return tests
+            .iter()
+            .enumerate()
+            .filter_map(|(i, test)| {
+                test.points.map(|points| Subtask {
+                    score: points,
+                    test_cases: vec![i as i32 + 1],
+                    aggregation: SubtaskAggregation::Min,
+                })
+            })
+            .collect();
+    }
+
+    groups
+        .into_iter()
+        .filter_map(|group| {
+            let name = group.attribute("name")?;
+            let test_cases: Vec<i32> = tests
+                .iter()
+                .enumerate()
+                .filter(|(_, test)| test.group.as_deref() == Some(name))
+                .map(|(i, _)| i as i32 + 1)
+                .collect();
+            let score = match group.attribute("points") {
+                Some(points) => points.parse().unwrap_or(0.0),
+                None => test_cases
+                    .iter()
+                    .filter_map(|id| tests[*id as usize - 1].points)
+                    .sum(),
+            };
+
+            Some(Subtask {
+                score: score,
+                test_cases: test_cases,
+                aggregation: if group.attribute("points-policy") == Some("each-test") {
+                    SubtaskAggregation::Sum
+                } else {
+                    SubtaskAggregation::Min
+                },
+            })
+        })
+        .collect()
+}
+
+fn source_path<'a>(asset: Node<'a, '_>) -> &'a str {
+    child(asset, "source")
+        .and_then(|source| source.attribute("path"))
+        .unwrap_or("problem.xml")
+}
+
+/// Reads the testlib checker of the package, standard ones come with their sources too.
+fn read_checker(path: &str, checker: Node) -> Result<(&'static str, String), PackageError> {
+    let source_path = source_path(checker);
+    let source_type = child(checker, "source")
+        .and_then(|source| source.attribute("type"))
+        .unwrap_or_default();
+    let language = match checker_language(source_type) {
+        Some(language) => language,
+        None => {
+            let message = "The checker should be written in c or cpp.";
+            return Err(package_error(source_path, message.to_owned()));
+        }
+    };
+
+    match fs::read_to_string(format!("{}/{}", path, source_path)) {
+        Ok(src) => Ok((language, src)),
+        Err(e) => Err(package_error(source_path, e.to_string())),
+    }
+}
+
+/// Converts a Polygon package in `path` to a problem folder in `target`.
+fn convert_package(path: &str, target: &str) -> Result<(), PackageError> {
+    let xml = fs::read_to_string(format!("{}/problem.xml", path))
+        .map_err(|e| package_error("problem.xml", e.to_string()))?;
+    let document =
+        Document::parse(&xml).map_err(|e| package_error("problem.xml", e.to_string()))?;
+    let root = document.root_element();
+
+    let names: Vec<Node> = child(root, "names")
+        .map(|names| names.children().filter(|name| name.is_element()).collect())
+        .unwrap_or_default();
+    let name = names
+        .iter()
+        .find(|name| name.attribute("language") == Some("english"))
+        .or_else(|| names.first());
+    let title = name
+        .and_then(|name| name.attribute("value"))
+        .or_else(|| root.attribute("short-name"))
+        .unwrap_or_default()
+        .to_owned();
+    let language = name
+        .and_then(|name| name.attribute("language"))
+        .unwrap_or("english");
+
+    let testset = child(root, "judging")
+        .and_then(|judging| {
+            judging
+                .children()
+                .find(|testset| testset.attribute("name") == Some("tests"))
+        })
+        .ok_or_else(|| package_error("problem.xml", "Testset tests is missing.".to_owned()))?;
+    let parse_limit = |name: &str| -> Result<i32, PackageError> {
+        child_text(testset, name)
+            .and_then(|limit| limit.trim().parse().ok())
+            .ok_or_else(|| package_error("problem.xml", format!("{} is missing.", name)))
+    };
+    let max_cpu_time = parse_limit("time-limit")?;
+    let max_memory = parse_limit("memory-limit")?;
+
+    let input_pattern = child_text(testset, "input-path-pattern").unwrap_or("tests/%02d");
+    let answer_pattern = child_text(testset, "answer-path-pattern").unwrap_or("tests/%02d.a");
+    let tests: Vec<PolygonTest> = child(testset, "tests")
+        .map(|tests| {
+            tests
+                .children()
+                .filter(|test| test.has_tag_name("test"))
+                .map(|test| PolygonTest {
+                    is_sample: test.attribute("sample") == Some("true"),
+                    points: test
+                        .attribute("points")
+                        .and_then(|points| points.parse().ok()),
+                    group: test.attribute("group").map(|group| group.to_owned()),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+
+    let mut settings = super::new_settings(max_cpu_time, max_memory);
+    settings.subtasks = read_subtasks(testset, &tests);
+
+    let assets = child(root, "assets");
+    // interactors here judge submissions by themselves, without the checker of the package
+    if let Some(interactor) = assets.and_then(|assets| child(assets, "interactor")) {
+        let message = "Interactive problems are not supported.";
+        return Err(package_error(source_path(interactor), message.to_owned()));
+    }
+    let checker = match assets.and_then(|assets| child(assets, "checker")) {
+        Some(checker) => Some(read_checker(path, checker)?),
+        None => None,
+    };
+    settings.is_spj = checker.is_some();
+
+    fs::create_dir_all(format!("{}/TestCases", target))
+        .map_err(|e| package_error("TestCases", e.to_string()))?;
+    let mut examples = Vec::new();
+    for (i, test) in tests.iter().enumerate() {
+        let id = i as i32 + 1;
+        let input_path = format_pattern(input_pattern, id);
+        let answer_path = format_pattern(answer_pattern, id);
+
+        let input = fs::read_to_string(format!("{}/{}", path, input_path)).map_err(|_| {
+            let message = "Test is missing, export the full package with generated tests.";
+            package_error(&input_path, message.to_owned())
+        })?;
+        let answer = fs::read_to_string(format!("{}/{}", path, answer_path)).map_err(|_| {
+            let message = "Answer is missing, export the full package with generated tests.";
+            package_error(&answer_path, message.to_owned())
+        })?;
+
+        fs::write(format!("{}/TestCases/{}.in", target, id), &input)
+            .map_err(|e| package_error(&input_path, e.to_string()))?;
+        fs::write(format!("{}/TestCases/{}.out", target, id), &answer)
+            .map_err(|e| package_error(&answer_path, e.to_string()))?;
+
+        if test.is_sample {
+            examples.push(Example {
+                input: input,
+                output: answer,
+            });
+        }
+    }
+
+    let tags: Vec<String> = child(root, "tags")
+        .map(|tags| {
+            tags.children()
+                .filter_map(|tag| tag.attribute("value"))
+                .map(|tag| tag.to_owned())
+                .collect()
+        })
+        .unwrap_or_default();
+
+    package::write_folder(
+        target,
+        &ProblemInfo {
+            title: title,
+            tags: super::known_tags(tags),
+            difficulty: super::DEFAULT_DIFFICULTY,
+        },
+        &ProblemContents {
+            description: read_description(path, language),
+            example_count: examples.len() as i32,
+            examples: examples,
+        },
+        &settings,
+        checker
+            .as_ref()
+            .map(|(language, src)| (*language, src.as_str())),
+    )
+    .map_err(|e| package_error("problem.xml", e.to_string()))
+}
+
+/// Converts every package in `path` to a folder in `target`, the package is
+/// `path` itself if it has `problem.xml`, or each folder of it which has one.
+/// Packages failed to convert are returned.
+pub fn convert(path: &str, target: &str) -> ServiceResult<Vec<CreateProblemsResult>> {
+    let mut packages = Vec::new();
+    if Path::new(&format!("{}/problem.xml", path)).exists() {
+        packages.push((path.to_owned(), "problem".to_owned()));
+    } else {
+        for entry in fs::read_dir(path)? {
+            let dir = entry?;
+            if dir.path().join("problem.xml").exists() {
+                packages.push((
+                    dir.path().into_os_string().into_string().unwrap(),
+                    dir.file_name().to_str().unwrap().to_owned(),
+                ));
+            }
+        }
+    }
+
+    if packages.is_empty() {
+        let hint = "No problem.xml found in the package.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let mut broken = Vec::new();
+    for (package_path, name) in packages {
+        let problem_target = format!("{}/{}", target, name);
+        if let Err(error) = convert_package(&package_path, &problem_target) {
+            fs::remove_dir_all(&problem_target).unwrap_or({});
+            broken.push(super::broken(name, error));
+        }
+    }
+
+    Ok(broken)
+}
@@ -0,0 +1,48 @@
+use super::checker::source_version;
+use crate::services::language::get_enabled;
+use diesel::prelude::*;
+use judge_client::models::InteractorConfig;
+use server_core::errors::{ServiceError, ServiceResult};
+use std::fs;
+
+/// Arguments follow testlib interactors: input, output to the checker and answer.
+const INTERACTOR_COMMAND: &str = "{exe_path} {in_file_path} {user_out_file_path} {ans_file_path}";
+
+/// Builds the interactor from `interactor_src.c` or `interactor_src.cpp` in the test cases
+/// in `path`, it is compiled like a checker of the same language.
+pub fn get_for_judge(conn: &PgConnection, path: &str) -> ServiceResult<InteractorConfig> {
+    for language in &["cpp", "c"] {
+        let src = match fs::read_to_string(format!("{}/interactor_src.{}", path, language)) {
+            Ok(src) => src,
+            Err(_) => continue,
+        };
+
+        let mut checker_config = match get_enabled(conn, language)?.checker_config {
+            Some(checker_config) => checker_config,
+            None => {
+                let hint = format!("Interactors can not be written in {}.", language);
+                return Err(ServiceError::BadRequest(hint));
+            }
+        };
+        checker_config.compile.src_name = checker_config
+            .compile
+            .src_name
+            .replace("spj-", "interactor-");
+        checker_config.compile.exe_name = checker_config
+            .compile
+            .exe_name
+            .replace("spj-", "interactor-");
+        checker_config.run.exe_name = checker_config.run.exe_name.replace("spj-", "interactor-");
+        checker_config.run.command = INTERACTOR_COMMAND.to_owned();
+
+        return Ok(InteractorConfig {
+            version: source_version(&src),
+            src: src,
+            compile_config: checker_config.compile,
+            run_config: checker_config.run,
+        });
+    }
+
+    let hint = "Interactor of the problem is not uploaded.".to_string();
+    Err(ServiceError::BadRequest(hint))
+}
@@ -1,123 +1,94 @@
+pub mod archive;
+pub mod checker;
+mod importer;
+pub mod interactor;
+mod package;
+pub mod test_case_version;
+pub mod test_data;
 mod utils;
 
 use crate::models::problems::*;
+use crate::models::test_case_versions::InsertTestCasesResult;
+use crate::models::test_data_programs::TestDataReport;
 use crate::models::utils::SizedList;
+use crate::services::language::get_enabled_list;
 use actix_files::NamedFile;
 use actix_web::web;
 use diesel::prelude::*;
+use judge_client::JudgeClient;
 use server_core::database::{db_connection, Pool};
 use server_core::errors::{ServiceError, ServiceResult};
 use std::fs;
-use std::io::prelude::*;
-use std::process::Command;
 use uuid::Uuid;
 
+/// Imports an uploaded zip of problem folders, nothing is created if any of them is broken.
 pub fn batch_create(
-    zip_buf: &[u8],
+    upload_path: String,
     pool: web::Data<Pool>,
 ) -> ServiceResult<Vec<CreateProblemsResult>> {
     let tmp_folder = String::from("data/tmp/") + &Uuid::new_v4().to_hyphenated().to_string();
-    let file_path = tmp_folder.clone() + "/raw.zip";
-    fs::create_dir_all(&tmp_folder)?;
-
-    let mut file = fs::File::create(file_path.clone())?;
-    file.write_all(zip_buf).expect("Error writing zip.");
-
-    let mut p = Command::new("unzip")
-        .args(&["-q", "-o", &file_path, "-d", &tmp_folder])
-        .spawn()?;
-    p.wait()?;
-
-    fs::remove_file(file_path)?;
-
-    let mut res = Vec::new();
-
-    for entry in fs::read_dir(tmp_folder.clone())? {
-        let dir = entry?;
-
-        match utils::read_insertable_problem(&dir.path().into_os_string().into_string().unwrap()) {
-            Ok(insertable_problem) => {
-                let mut target_problem = insertable_problem.clone();
-                let mut settings: ProblemSettings =
-                    serde_json::from_str(&insertable_problem.settings).unwrap();
-                match utils::prepare_test_cases(
-                    &(dir.path().into_os_string().into_string().unwrap() + "/TestCases"),
-                    settings.is_spj,
-                ) {
-                    Ok(test_case_count) => {
-                        settings.test_case_count = Some(test_case_count);
-                        target_problem.settings = serde_json::to_string(&settings).unwrap();
-                        info!("{:?}", target_problem);
-
-                        let conn = &db_connection(&pool)?;
-
-                        use crate::schema::problems as problems_schema;
-                        match diesel::insert_into(problems_schema::table)
-                            .values(&target_problem.clone())
-                            .execute(conn)
-                        {
-                            Ok(_) => {
-                                let id: i32 = problems_schema::table
-                                    .filter(problems_schema::title.eq(target_problem.title))
-                                    .select(problems_schema::id)
-                                    .first(conn)?;
-
-                                fs::remove_dir_all(format!("data/test_cases/{}", id)).unwrap_or({});
-
-                                fs::rename(
-                                    &(dir.path().into_os_string().into_string().unwrap()
-                                        + "/TestCases"),
-                                    format!("data/test_cases/{}", id),
-                                )?;
-
-                                res.push(CreateProblemsResult {
-                                    title: dir.file_name().to_str().unwrap().to_owned(),
-                                    is_success: true,
-                                    id: Some(id),
-                                });
-                            }
-                            Err(_) => {
-                                let max_id: i32 = problems_schema::table
-                                    .select(problems_schema::id)
-                                    .order(problems_schema::id.desc())
-                                    .first(conn)?;
-
-                                diesel::sql_query(format!(
-                                    "ALTER SEQUENCE problems_id_seq RESTART WITH {}",
-                                    max_id + 1
-                                ))
-                                .execute(conn)?;
-
-                                res.push(CreateProblemsResult {
-                                    title: dir.file_name().to_str().unwrap().to_owned(),
-                                    is_success: false,
-                                    id: None,
-                                });
-                            }
-                        }
-                    }
-                    Err(_) => {
-                        res.push(CreateProblemsResult {
-                            title: dir.file_name().to_str().unwrap().to_owned(),
-                            is_success: false,
-                            id: None,
-                        });
-                    }
-                }
-            }
-            Err(_) => {
-                res.push(CreateProblemsResult {
-                    title: dir.file_name().to_str().unwrap().to_owned(),
-                    is_success: false,
-                    id: None,
-                });
-            }
+    let extracted = archive::extract(&upload_path, &tmp_folder);
+    fs::remove_file(&upload_path)?;
+    extracted?;
+
+    let conn = &db_connection(&pool)?;
+    let res = package::import_folders(conn, &tmp_folder, Vec::new());
+
+    fs::remove_dir_all(&tmp_folder)?;
+
+    res
+}
+
+/// Imports problems of other formats, they are converted to problem folders first
+/// and reported the same way as `batch_create`.
+pub fn import(
+    format: ImportFormat,
+    upload_path: String,
+    pool: web::Data<Pool>,
+) -> ServiceResult<Vec<CreateProblemsResult>> {
+    let tmp_folder = String::from("data/tmp/") + &Uuid::new_v4().to_hyphenated().to_string();
+    let converted_folder = tmp_folder.clone() + "/converted";
+    fs::create_dir_all(&converted_folder)?;
+
+    let broken = match format {
+        ImportFormat::Polygon => {
+            let raw_folder = tmp_folder.clone() + "/raw";
+            archive::extract(&upload_path, &raw_folder)
+                .and_then(|_| importer::polygon::convert(&raw_folder, &converted_folder))
         }
-    }
+        ImportFormat::Fps => fs::read(&upload_path)
+            .map_err(ServiceError::from)
+            .and_then(|buf| importer::fps::convert(&buf, &converted_folder)),
+    };
+    fs::remove_file(&upload_path)?;
+
+    let res = match broken {
+        Ok(broken) => {
+            let conn = &db_connection(&pool)?;
+            package::import_folders(conn, &converted_folder, broken)
+        }
+        Err(e) => Err(e),
+    };
 
     fs::remove_dir_all(&tmp_folder)?;
 
-    Ok(res)
+    res
+}
+
+/// Packs problems the same way `batch_create` reads them, one folder per problem.
+pub fn export(ids: Vec<i32>, pool: web::Data<Pool>) -> ServiceResult<NamedFile> {
+    let tmp_folder = String::from("data/tmp/") + &Uuid::new_v4().to_hyphenated().to_string();
+    let target_path = tmp_folder.clone() + ".zip";
+
+    let conn = &db_connection(&pool)?;
+    let written = ids
+        .iter()
+        .try_for_each(|id| package::write_problem(conn, *id, &format!("{}/{}", tmp_folder, id)))
+        .and_then(|_| archive::compress(&tmp_folder, &target_path, &|_| true));
+    fs::remove_dir_all(&tmp_folder)?;
+    written?;
+
+    archive::open_download(&target_path, "problems.zip")
 }
 
 pub fn change_release_state(
@@ -272,7 +243,10 @@ pub fn get(id: i32, pool: web::Data<Pool>) -> ServiceResult<Problem> {
         .filter(problems_schema::id.eq(id))
         .first(conn)?;
 
-    Ok(Problem::from(problem))
+    let mut problem = Problem::from(problem);
+    problem.effective_limits = problem.settings.effective_limits(&get_enabled_list(conn)?);
+
+    Ok(problem)
 }
 
 pub fn delete(id: i32, pool: web::Data<Pool>) -> ServiceResult<()> {
@@ -319,6 +293,17 @@ pub fn delete(id: i32, pool: web::Data<Pool>) -> ServiceResult<()> {
     diesel::delete(submissions_schema::table.filter(submissions_schema::id.eq_any(submission_ids)))
         .execute(conn)?;
 
+    use crate::schema::test_case_versions as test_case_versions_schema;
+    diesel::delete(
+        test_case_versions_schema::table.filter(test_case_versions_schema::problem_id.eq(id)),
+    )
+    .execute(conn)?;
+    use crate::schema::test_data_programs as test_data_programs_schema;
+    diesel::delete(
+        test_data_programs_schema::table.filter(test_data_programs_schema::problem_id.eq(id)),
+    )
+    .execute(conn)?;
+
     diesel::delete(problems_schema::table.filter(problems_schema::id.eq(id))).execute(conn)?;
 
     let max_id: i32 = problems_schema::table
@@ -332,7 +317,7 @@ pub fn delete(id: i32, pool: web::Data<Pool>) -> ServiceResult<()> {
     ))
     .execute(conn)?;
 
-    fs::remove_dir_all(&format!("data/test_cases/{}", id))?;
+    fs::remove_dir_all(&test_case_version::folder(id, None))?;
 
     Ok(())
 }
@@ -395,76 +380,124 @@ pub fn update(
     Ok(())
 }
 
-pub fn insert_test_cases(id: i32, zip_buf: &[u8], pool: web::Data<Pool>) -> ServiceResult<()> {
-    let tmp_folder = String::from("data/tmp/") + &Uuid::new_v4().to_hyphenated().to_string();
-    let file_path = tmp_folder.clone() + "/test_cases.zip";
-    fs::create_dir_all(&tmp_folder)?;
+/// Stores checked test cases in `path` as a new version and switches the problem to it.
+fn save_test_cases(
+    conn: &PgConnection,
+    problem: &RawProblem,
+    path: &str,
+    settings: &ProblemSettings,
+    report: Option<&TestDataReport>,
+) -> ServiceResult<String> {
+    test_case_version::adopt_legacy(conn, problem)?;
+    let version = test_case_version::store(
+        conn,
+        problem.id,
+        path,
+        settings.test_case_count.unwrap_or(0),
+        report,
+    )?;
 
-    let mut file = fs::File::create(file_path.clone())?;
-    file.write_all(zip_buf).expect("Error writing zip.");
-
-    let mut p = Command::new("unzip")
-        .args(&["-o", &file_path, "-d", &tmp_folder])
-        .spawn()?;
-    p.wait()?;
-
-    fs::remove_file(file_path)?;
-
-    let conn = &db_connection(&pool)?;
     use crate::schema::problems as problems_schema;
+    conn.transaction::<_, ServiceError, _>(|| {
+        diesel::update(problems_schema::table.filter(problems_schema::id.eq(problem.id)))
+            .set((
+                problems_schema::settings.eq(serde_json::to_string(settings).unwrap()),
+                problems_schema::test_case_version.eq(Some(version.clone())),
+            ))
+            .execute(conn)?;
+
+        checker::import(
+            conn,
+            problem.id,
+            &test_case_version::folder(problem.id, Some(&version)),
+        )
+    })?;
 
-    let problem: RawProblem = problems_schema::table
-        .filter(problems_schema::id.eq(id))
-        .first(conn)?;
+    Ok(version)
+}
 
+/// Checks test cases in `path` with the programs of the problem before saving them,
+/// those failing the checks are saved only if `force` is set.
+fn check_and_save_test_cases(
+    conn: &PgConnection,
+    judge_client: &JudgeClient,
+    problem: &RawProblem,
+    path: &str,
+    force: bool,
+) -> ServiceResult<InsertTestCasesResult> {
     let mut settings: ProblemSettings = serde_json::from_str(&problem.settings).unwrap();
 
-    match utils::prepare_test_cases(&(tmp_folder.clone() + "/TestCases"), settings.is_spj) {
-        Ok(test_case_count) => {
-            fs::remove_dir_all(format!("data/test_cases/{}", id)).unwrap_or({});
-
-            fs::rename(
-                &(tmp_folder.clone() + "/TestCases"),
-                format!("data/test_cases/{}", id),
-            )?;
-
-            settings.test_case_count = Some(test_case_count);
+    let report = test_data::check(conn, judge_client, problem.id, &settings, path)?;
+    if !force && report.as_ref().map_or(false, |report| !report.is_passed) {
+        return Ok(InsertTestCasesResult {
+            is_saved: false,
+            version: None,
+            report: report,
+        });
+    }
 
-            diesel::update(problems_schema::table.filter(problems_schema::id.eq(id)))
-                .set(problems_schema::settings.eq(serde_json::to_string(&settings).unwrap()))
-                .execute(conn)?;
-        }
+    match utils::prepare_test_cases(path, &settings) {
+        Ok(test_case_count) => settings.test_case_count = Some(test_case_count),
         Err(_) => {
             let hint = "Test case insert failed.".to_string();
             return Err(ServiceError::BadRequest(hint));
         }
     }
+    let version = save_test_cases(conn, problem, path, &settings, report.as_ref())?;
+
+    Ok(InsertTestCasesResult {
+        is_saved: true,
+        version: Some(version),
+        report: report,
+    })
+}
+
+/// Uploaded test cases become a new version, the data in use is kept for rolling back.
+pub fn insert_test_cases(
+    id: i32,
+    upload_path: String,
+    force: bool,
+    pool: web::Data<Pool>,
+    judge_client: web::Data<JudgeClient>,
+) -> ServiceResult<InsertTestCasesResult> {
+    let tmp_folder = String::from("data/tmp/") + &Uuid::new_v4().to_hyphenated().to_string();
+    let extracted = archive::extract(&upload_path, &tmp_folder);
+    fs::remove_file(&upload_path)?;
+    extracted?;
+
+    let conn = &db_connection(&pool)?;
+    use crate::schema::problems as problems_schema;
+
+    let res = problems_schema::table
+        .filter(problems_schema::id.eq(id))
+        .first::<RawProblem>(conn)
+        .map_err(ServiceError::from)
+        .and_then(|problem| {
+            check_and_save_test_cases(
+                conn,
+                &judge_client,
+                &problem,
+                &(tmp_folder.clone() + "/TestCases"),
+                force,
+            )
+        });
 
     fs::remove_dir_all(&tmp_folder)?;
 
-    Ok(())
+    res
 }
 
-pub fn get_test_cases(id: i32) -> ServiceResult<NamedFile> {
-    let file_path = format!("data/test_cases/{}", id);
-    let target_path = format!("data/tmp/{}.zip", id);
-
-    fs::remove_file(target_path.clone()).unwrap_or({});
-
-    let mut p = Command::new("zip")
-        .args(&[
-            "-q",
-            "-r",
-            "-j",
-            &target_path,
-            &file_path,
-            "-i",
-            "*.in",
-            "-i",
-            "*.out",
-        ])
-        .spawn()?;
-    p.wait()?;
-
-    Ok(NamedFile::open(target_path)?)
+pub fn get_test_cases(id: i32, pool: web::Data<Pool>) -> ServiceResult<NamedFile> {
+    let conn = &db_connection(&pool)?;
+    let test_cases_folder = test_case_version::current_folder(conn, id)?;
+
+    let target_path =
+        String::from("data/tmp/") + &Uuid::new_v4().to_hyphenated().to_string() + ".zip";
+
+    archive::compress(&test_cases_folder, &target_path, &|path| {
+        path.extension()
+            .map_or(false, |extension| extension == "in" || extension == "out")
+    })?;
+
+    archive::open_download(&target_path, &format!("{}.zip", id))
 }
@@ -0,0 +1,491 @@
+use super::{checker, test_case_version, utils};
+use crate::models::checkers::Checker;
+use crate::models::languages::Language;
+use crate::models::problems::*;
+use crate::services::language::{get_enabled, get_enabled_list};
+use crate::statics::PROBLEM_TAG_NAME_CACHE;
+use diesel::prelude::*;
+use server_core::errors::{ServiceError, ServiceResult};
+use server_core::utils::time::get_cur_naive_date_time;
+use std::fs;
+use std::path::{Component, Path};
+
+/// Version of packages written by `export`, packages of newer versions are refused.
+pub const FORMAT_VERSION: i32 = 1;
+
+/// A problem folder of a package which is checked but not saved yet.
+pub struct PackageProblem {
+    pub name: String,
+    pub path: String,
+    pub problem: InsertableProblem,
+    pub settings: ProblemSettings,
+    pub checker: Option<(String, String)>,
+}
+
+fn describe(error: ServiceError) -> String {
+    match error {
+        ServiceError::BadRequest(hint) => hint,
+        error => error.to_string(),
+    }
+}
+
+fn read_manifest(path: &str, errors: &mut Vec<PackageError>) -> Option<PackageManifest> {
+    let manifest_path = path.to_string() + "/Manifest.toml";
+    if !Path::new(&manifest_path).exists() {
+        return Some(PackageManifest {
+            format_version: 0,
+            checker: None,
+        });
+    }
+
+    let manifest: PackageManifest = match fs::read_to_string(&manifest_path)
+        .map_err(|e| e.to_string())
+        .and_then(|contents| toml::from_str(&contents).map_err(|e| e.to_string()))
+    {
+        Ok(manifest) => manifest,
+        Err(message) => {
+            errors.push(PackageError {
+                file: "Manifest.toml".to_owned(),
+                message: message,
+            });
+            return None;
+        }
+    };
+
+    if manifest.format_version > FORMAT_VERSION {
+        errors.push(PackageError {
+            file: "Manifest.toml".to_owned(),
+            message: format!(
+                "Format version {} is newer than the supported {}.",
+                manifest.format_version, FORMAT_VERSION
+            ),
+        });
+        return None;
+    }
+
+    Some(manifest)
+}
+
+fn find_tag_ids(tags: &[String], errors: &mut Vec<PackageError>) -> Vec<i32> {
+    let lock = PROBLEM_TAG_NAME_CACHE.read().unwrap();
+
+    let mut tag_ids = Vec::new();
+    for tag in tags {
+        match lock.iter().find(|(_, name)| *name == tag) {
+            Some((id, _)) => tag_ids.push(*id),
+            None => errors.push(PackageError {
+                file: "Info.toml".to_owned(),
+                message: format!("Unknown tag {}.", tag),
+            }),
+        }
+    }
+
+    tag_ids
+}
+
+fn read_checker(
+    conn: &PgConnection,
+    path: &str,
+    package_checker: &PackageChecker,
+    errors: &mut Vec<PackageError>,
+) -> Option<(String, String)> {
+    match get_enabled(conn, &package_checker.language) {
+        Ok(language) if language.checker_config.is_some() => (),
+        _ => {
+            errors.push(PackageError {
+                file: "Manifest.toml".to_owned(),
+                message: format!(
+                    "Checkers can not be written in {}.",
+                    package_checker.language
+                ),
+            });
+            return None;
+        }
+    }
+
+    // the manifest comes with the package, it must not point out of the problem folder
+    let file = Path::new(&package_checker.file);
+    if file.is_absolute()
+        || file
+            .components()
+            .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        errors.push(PackageError {
+            file: "Manifest.toml".to_owned(),
+            message: format!(
+                "Checker file {} should be a path in the problem folder.",
+                package_checker.file
+            ),
+        });
+        return None;
+    }
+
+    match fs::read_to_string(format!("{}/{}", path, package_checker.file)) {
+        Ok(src) => Some((package_checker.language.clone(), src)),
+        Err(e) => {
+            errors.push(PackageError {
+                file: package_checker.file.clone(),
+                message: e.to_string(),
+            });
+            None
+        }
+    }
+}
+
+/// Checks every file of a problem folder, all errors found are returned together.
+pub fn read_problem(
+    conn: &PgConnection,
+    path: &str,
+    name: &str,
+) -> Result<PackageProblem, Vec<PackageError>> {
+    let mut errors = Vec::new();
+
+    let manifest = read_manifest(path, &mut errors);
+
+    let info = match utils::read_info(&(path.to_string() + "/Info.toml")) {
+        Ok(info) => Some(info),
+        Err(e) => {
+            errors.push(PackageError {
+                file: "Info.toml".to_owned(),
+                message: e.to_string(),
+            });
+            None
+        }
+    };
+    let tag_ids = match &info {
+        Some(info) => find_tag_ids(&info.tags, &mut errors),
+        None => Vec::new(),
+    };
+
+    let description = match utils::read_description(&(path.to_string() + "/Description.md")) {
+        Ok(description) => description,
+        Err(e) => {
+            errors.push(PackageError {
+                file: "Description.md".to_owned(),
+                message: e.to_string(),
+            });
+            None
+        }
+    };
+
+    let examples_path = path.to_string() + "/Examples";
+    let (examples, example_count) = match utils::read_examples(&examples_path) {
+        Ok(examples) => examples,
+        Err(e) => {
+            errors.push(PackageError {
+                file: "Examples".to_owned(),
+                message: e.to_string(),
+            });
+            (Vec::new(), 0)
+        }
+    };
+    // examples are read until the first missing file, an input left means its answer is missing
+    if Path::new(&format!("{}/{}.in", examples_path, example_count + 1)).exists() {
+        errors.push(PackageError {
+            file: format!("Examples/{}.out", example_count + 1),
+            message: "Answer of the example is missing.".to_owned(),
+        });
+    }
+
+    let settings = match utils::read_settings(&(path.to_string() + "/Settings.toml")) {
+        Ok(settings) => Some(settings),
+        Err(e) => {
+            errors.push(PackageError {
+                file: "Settings.toml".to_owned(),
+                message: e.to_string(),
+            });
+            None
+        }
+    };
+
+    let checker = match manifest
+        .as_ref()
+        .and_then(|manifest| manifest.checker.as_ref())
+    {
+        Some(package_checker) => read_checker(conn, path, package_checker, &mut errors),
+        None => None,
+    };
+
+    let mut settings = match settings {
+        Some(settings) => settings,
+        None => return Err(errors),
+    };
+    match utils::prepare_test_cases(&(path.to_string() + "/TestCases"), &settings) {
+        Ok(test_case_count) => settings.test_case_count = Some(test_case_count),
+        Err(e) => errors.push(PackageError {
+            file: "TestCases".to_owned(),
+            message: describe(e),
+        }),
+    }
+
+    match info {
+        Some(info) if errors.is_empty() => Ok(PackageProblem {
+            name: name.to_owned(),
+            path: path.to_owned(),
+            problem: InsertableProblem {
+                title: info.title,
+                tags: tag_ids,
+                difficulty: info.difficulty,
+                contents: serde_json::to_string(&ProblemContents {
+                    description: description,
+                    example_count: example_count,
+                    examples: examples,
+                })
+                .unwrap(),
+                settings: serde_json::to_string(&settings).unwrap(),
+                is_released: false,
+            },
+            settings: settings,
+            checker: checker,
+        }),
+        _ => Err(errors),
+    }
+}
+
+/// Saves a checked problem, its test cases are moved out of the package as its first version.
+/// Its rows are rolled back by the transaction of the caller on failure, its files are removed here.
+pub fn save(
+    conn: &PgConnection,
+    package_problem: PackageProblem,
+    languages: &[Language],
+) -> ServiceResult<CreateProblemsResult> {
+    use crate::schema::problems as problems_schema;
+    let id = diesel::insert_into(problems_schema::table)
+        .values(&package_problem.problem)
+        .get_result::<RawProblem>(conn)?
+        .id;
+
+    if let Err(e) = save_test_data(conn, id, &package_problem) {
+        fs::remove_dir_all(test_case_version::folder(id, None)).unwrap_or({});
+        return Err(e);
+    }
+
+    Ok(CreateProblemsResult {
+        title: package_problem.name,
+        is_success: true,
+        id: Some(id),
+        effective_limits: Some(package_problem.settings.effective_limits(languages)),
+        errors: Vec::new(),
+    })
+}
+
+fn save_test_data(
+    conn: &PgConnection,
+    id: i32,
+    package_problem: &PackageProblem,
+) -> ServiceResult<()> {
+    let test_cases_path = package_problem.path.clone() + "/TestCases";
+    match &package_problem.checker {
+        Some((language, src)) => checker::save(
+            conn,
+            &Checker {
+                problem_id: id,
+                language: language.clone(),
+                version: checker::source_version(src),
+                src: src.clone(),
+                update_time: get_cur_naive_date_time(),
+            },
+        )?,
+        None => checker::import(conn, id, &test_cases_path)?,
+    }
+
+    let version = test_case_version::store(
+        conn,
+        id,
+        &test_cases_path,
+        package_problem.settings.test_case_count.unwrap_or(0),
+        None,
+    )?;
+    use crate::schema::problems as problems_schema;
+    diesel::update(problems_schema::table.filter(problems_schema::id.eq(id)))
+        .set(problems_schema::test_case_version.eq(Some(version)))
+        .execute(conn)?;
+
+    Ok(())
+}
+
+/// Writes everything of a problem folder but its test cases, `checker` is its language and source.
+pub fn write_folder(
+    path: &str,
+    info: &ProblemInfo,
+    contents: &ProblemContents,
+    settings: &ProblemSettings,
+    checker: Option<(&str, &str)>,
+) -> ServiceResult<()> {
+    fs::create_dir_all(path.to_string() + "/Examples")?;
+    fs::create_dir_all(path.to_string() + "/TestCases")?;
+
+    let manifest = PackageManifest {
+        format_version: FORMAT_VERSION,
+        checker: checker.map(|(language, _)| PackageChecker {
+            language: language.to_owned(),
+            file: format!("Checker/checker.{}", language),
+        }),
+    };
+    fs::write(
+        path.to_string() + "/Manifest.toml",
+        toml::to_string(&manifest).unwrap(),
+    )?;
+    fs::write(
+        path.to_string() + "/Info.toml",
+        toml::to_string(info).unwrap(),
+    )?;
+    // settings are written through a value so that tables like the limits go last
+    fs::write(
+        path.to_string() + "/Settings.toml",
+        toml::to_string(&toml::Value::try_from(settings).unwrap()).unwrap(),
+    )?;
+
+    if let Some(description) = &contents.description {
+        fs::write(path.to_string() + "/Description.md", description)?;
+    }
+    for (i, example) in contents.examples.iter().enumerate() {
+        fs::write(format!("{}/Examples/{}.in", path, i + 1), &example.input)?;
+        fs::write(format!("{}/Examples/{}.out", path, i + 1), &example.output)?;
+    }
+
+    if let (Some(checker_file), Some((_, src))) = (manifest.checker, checker) {
+        fs::create_dir_all(path.to_string() + "/Checker")?;
+        fs::write(format!("{}/{}", path, checker_file.file), src)?;
+    }
+
+    Ok(())
+}
+
+/// Writes a problem into `path` as `read_problem` expects it.
+pub fn write_problem(conn: &PgConnection, id: i32, path: &str) -> ServiceResult<()> {
+    use crate::schema::problems as problems_schema;
+    let problem = Problem::from(
+        problems_schema::table
+            .filter(problems_schema::id.eq(id))
+            .first::<RawProblem>(conn)?,
+    );
+
+    let checker = checker::get_for_judge(conn, id).ok();
+    write_folder(
+        path,
+        &problem.info,
+        &problem.contents,
+        &problem.settings,
+        checker
+            .as_ref()
+            .map(|checker| (checker.language.as_str(), checker.src.as_str())),
+    )?;
+
+    // info is made again on import, legacy checkers are in the manifest now
+    for entry in fs::read_dir(test_case_version::current_folder(conn, id)?)? {
+        let entry = entry?;
+        let file_name = entry.file_name().into_string().unwrap();
+        if !entry.file_type()?.is_file() || file_name == "info" || file_name.starts_with("spj_src.")
+        {
+            continue;
+        }
+
+        fs::copy(entry.path(), format!("{}/TestCases/{}", path, file_name))?;
+    }
+
+    Ok(())
+}
+
+/// Imports every problem folder in `path`, nothing is saved if any of them
+/// or any of the problems in `broken` is broken, or if any of them fails to save.
+pub fn import_folders(
+    conn: &PgConnection,
+    path: &str,
+    mut broken: Vec<CreateProblemsResult>,
+) -> ServiceResult<Vec<CreateProblemsResult>> {
+    let mut package_problems = Vec::new();
+    for entry in fs::read_dir(path)? {
+        let dir = entry?;
+        if !dir.file_type()?.is_dir() {
+            continue;
+        }
+
+        let name = dir.file_name().to_str().unwrap().to_owned();
+        match read_problem(
+            conn,
+            &dir.path().into_os_string().into_string().unwrap(),
+            &name,
+        ) {
+            Ok(package_problem) => package_problems.push(package_problem),
+            Err(errors) => broken.push(CreateProblemsResult {
+                title: name,
+                is_success: false,
+                id: None,
+                effective_limits: None,
+                errors: errors,
+            }),
+        }
+    }
+
+    if !broken.is_empty() {
+        broken.extend(
+            package_problems
+                .into_iter()
+                .map(|package_problem| CreateProblemsResult {
+                    title: package_problem.name,
+                    is_success: false,
+                    id: None,
+                    effective_limits: None,
+                    errors: Vec::new(),
+                }),
+        );
+        return Ok(broken);
+    }
+
+    let languages = get_enabled_list(conn)?;
+    let names: Vec<String> = package_problems
+        .iter()
+        .map(|package_problem| package_problem.name.clone())
+        .collect();
+    let mut saved_ids = Vec::new();
+    let mut failed_name = None;
+    let saving = conn.transaction::<_, ServiceError, _>(|| {
+        let mut res = Vec::new();
+        for package_problem in package_problems {
+            let name = package_problem.name.clone();
+            match save(conn, package_problem, &languages) {
+                Ok(result) => {
+                    saved_ids.extend(result.id);
+                    res.push(result);
+                }
+                Err(e) => {
+                    failed_name = Some(name);
+                    return Err(e);
+                }
+            }
+        }
+
+        Ok(res)
+    });
+
+    let error = match saving {
+        Ok(res) => return Ok(res),
+        Err(error) => error,
+    };
+
+    // the rows are rolled back but not the files, ids taken from the sequence are left as gaps
+    for id in saved_ids {
+        fs::remove_dir_all(test_case_version::folder(id, None)).unwrap_or({});
+    }
+
+    let message = format!("Failed to save the problem: {}", describe(error));
+    Ok(names
+        .into_iter()
+        .map(|name| CreateProblemsResult {
+            errors: if failed_name.as_ref() == Some(&name) {
+                // saving does not fail for a file of the folder
+                vec![PackageError {
+                    file: String::new(),
+                    message: message.clone(),
+                }]
+            } else {
+                Vec::new()
+            },
+            title: name,
+            is_success: false,
+            id: None,
+            effective_limits: None,
+        })
+        .collect())
+}
@@ -0,0 +1,258 @@
+use super::{checker, utils};
+use crate::models::problems::{ProblemSettings, RawProblem};
+use crate::models::test_case_versions::*;
+use crate::models::test_data_programs::TestDataReport;
+use actix_web::web;
+use diesel::prelude::*;
+use server_core::database::{db_connection, Pool};
+use server_core::errors::{ServiceError, ServiceResult};
+use server_core::utils::time::get_cur_naive_date_time;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::Path;
+use uuid::Uuid;
+
+/// Where judge servers find the test data, relative to the shared test case folder.
+/// Versions are kept in the folder of the problem, beside the legacy unversioned files.
+pub fn test_case_id(problem_id: i32, version: Option<&str>) -> String {
+    match version {
+        Some(version) => format!("{}/{}", problem_id, version),
+        None => problem_id.to_string(),
+    }
+}
+
+pub fn folder(problem_id: i32, version: Option<&str>) -> String {
+    format!("data/test_cases/{}", test_case_id(problem_id, version))
+}
+
+/// Folder of the test data the problem uses now.
+pub fn current_folder(conn: &PgConnection, problem_id: i32) -> ServiceResult<String> {
+    use crate::schema::problems as problems_schema;
+    let version: Option<String> = problems_schema::table
+        .filter(problems_schema::id.eq(problem_id))
+        .select(problems_schema::test_case_version)
+        .first(conn)?;
+
+    Ok(folder(problem_id, version.as_deref()))
+}
+
+fn hash_file(path: &Path) -> ServiceResult<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let size = file.read(&mut buf)?;
+        if size == 0 {
+            break;
+        }
+        hasher.update(&buf[..size]);
+    }
+
+    Ok(hex::encode(hasher.finalize()))
+}
+
+/// Hashes of the files in `path` by their names, judge servers do not look into sub folders.
+fn hash_files(path: &str) -> ServiceResult<BTreeMap<String, String>> {
+    let mut files = BTreeMap::new();
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        files.insert(
+            entry.file_name().into_string().unwrap(),
+            hash_file(&entry.path())?,
+        );
+    }
+
+    Ok(files)
+}
+
+fn hash_version(files: &BTreeMap<String, String>) -> String {
+    let mut hasher = Sha256::new();
+    for (name, hash) in files {
+        hasher.update(format!("{} {}\n", name, hash).as_bytes());
+    }
+
+    hex::encode(hasher.finalize())
+}
+
+/// Moves the test cases in `path` into a version of the problem named by their hashes,
+/// data uploaded before is reused. The problem keeps its current version until it is
+/// pointed to the returned one, so a failure here never touches data in use.
+pub(super) fn store(
+    conn: &PgConnection,
+    problem_id: i32,
+    path: &str,
+    test_case_count: i32,
+    report: Option<&TestDataReport>,
+) -> ServiceResult<String> {
+    let files = hash_files(path)?;
+    let version = hash_version(&files);
+
+    let target = folder(problem_id, Some(&version));
+    if !Path::new(&target).exists() {
+        fs::create_dir_all(folder(problem_id, None))?;
+        // a rename either moves the whole folder or nothing
+        fs::rename(path, &target)?;
+    }
+
+    use crate::schema::test_case_versions as test_case_versions_schema;
+    diesel::insert_into(test_case_versions_schema::table)
+        .values(&RawTestCaseVersion {
+            problem_id: problem_id,
+            version: version.clone(),
+            files: serde_json::to_string(&files).unwrap(),
+            test_case_count: test_case_count,
+            create_time: get_cur_naive_date_time(),
+            report: report.map(|report| serde_json::to_string(report).unwrap()),
+        })
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+
+    Ok(version)
+}
+
+/// Keeps test data uploaded before versioning as a version, so that it can be rolled back to.
+pub(super) fn adopt_legacy(conn: &PgConnection, problem: &RawProblem) -> ServiceResult<()> {
+    let legacy_folder = folder(problem.id, None);
+    if problem.test_case_version.is_some() || !Path::new(&legacy_folder).exists() {
+        return Ok(());
+    }
+
+    // judging may still read the legacy files, so they are copied rather than moved
+    let tmp_folder = String::from("data/tmp/") + &Uuid::new_v4().to_hyphenated().to_string();
+    fs::create_dir_all(&tmp_folder)?;
+    let mut copied = false;
+    for entry in fs::read_dir(&legacy_folder)? {
+        let entry = entry?;
+        if entry.file_type()?.is_file() {
+            fs::copy(entry.path(), Path::new(&tmp_folder).join(entry.file_name()))?;
+            copied = true;
+        }
+    }
+
+    let settings: ProblemSettings = serde_json::from_str(&problem.settings).unwrap();
+    let res = if copied {
+        store(
+            conn,
+            problem.id,
+            &tmp_folder,
+            settings.test_case_count.unwrap_or(0),
+            None,
+        )
+        .map(|_| ())
+    } else {
+        Ok(())
+    };
+    fs::remove_dir_all(&tmp_folder).unwrap_or({});
+
+    res
+}
+
+pub fn get_list(problem_id: i32, pool: web::Data<Pool>) -> ServiceResult<Vec<TestCaseVersion>> {
+    let conn = &db_connection(&pool)?;
+
+    use crate::schema::problems as problems_schema;
+    let current: Option<String> = problems_schema::table
+        .filter(problems_schema::id.eq(problem_id))
+        .select(problems_schema::test_case_version)
+        .first(conn)?;
+
+    use crate::schema::test_case_versions as test_case_versions_schema;
+    let raw_versions: Vec<RawTestCaseVersion> = test_case_versions_schema::table
+        .filter(test_case_versions_schema::problem_id.eq(problem_id))
+        .order(test_case_versions_schema::create_time.desc())
+        .load(conn)?;
+
+    Ok(raw_versions
+        .into_iter()
+        .map(|raw| {
+            let mut version = TestCaseVersion::from(raw);
+            version.is_current = current.as_ref() == Some(&version.version);
+            version
+        })
+        .collect())
+}
+
+fn get_version(
+    conn: &PgConnection,
+    problem_id: i32,
+    version: &str,
+) -> ServiceResult<RawTestCaseVersion> {
+    use crate::schema::test_case_versions as test_case_versions_schema;
+    match test_case_versions_schema::table
+        .filter(test_case_versions_schema::problem_id.eq(problem_id))
+        .filter(test_case_versions_schema::version.eq(version))
+        .first(conn)
+        .optional()?
+    {
+        Some(raw) => Ok(raw),
+        None => {
+            let hint = format!("Test case version {} is not found.", version);
+            Err(ServiceError::BadRequest(hint))
+        }
+    }
+}
+
+/// Compares the files of version `from` with those of version `to` by their hashes.
+pub fn diff(
+    problem_id: i32,
+    from: String,
+    to: String,
+    pool: web::Data<Pool>,
+) -> ServiceResult<TestCaseVersionDiff> {
+    let conn = &db_connection(&pool)?;
+
+    let old_files = TestCaseVersion::from(get_version(conn, problem_id, &from)?).files;
+    let new_files = TestCaseVersion::from(get_version(conn, problem_id, &to)?).files;
+
+    let mut diff = TestCaseVersionDiff {
+        added: Vec::new(),
+        removed: Vec::new(),
+        changed: Vec::new(),
+        unchanged: Vec::new(),
+    };
+    for (name, hash) in &new_files {
+        match old_files.get(name) {
+            None => diff.added.push(name.clone()),
+            Some(old_hash) if old_hash != hash => diff.changed.push(name.clone()),
+            Some(_) => diff.unchanged.push(name.clone()),
+        }
+    }
+    diff.removed = old_files
+        .keys()
+        .filter(|name| !new_files.contains_key(*name))
+        .cloned()
+        .collect();
+
+    Ok(diff)
+}
+
+/// Points the problem back to a stored version, its subtasks have to fit the version.
+pub fn rollback(problem_id: i32, version: String, pool: web::Data<Pool>) -> ServiceResult<()> {
+    let conn = &db_connection(&pool)?;
+
+    let raw_version = get_version(conn, problem_id, &version)?;
+
+    use crate::schema::problems as problems_schema;
+    let problem: RawProblem = problems_schema::table
+        .filter(problems_schema::id.eq(problem_id))
+        .first(conn)?;
+    let mut settings: ProblemSettings = serde_json::from_str(&problem.settings).unwrap();
+    utils::check_subtasks(&settings.subtasks, raw_version.test_case_count)?;
+    settings.test_case_count = Some(raw_version.test_case_count);
+
+    conn.transaction::<_, ServiceError, _>(|| {
+        diesel::update(problems_schema::table.filter(problems_schema::id.eq(problem_id)))
+            .set((
+                problems_schema::settings.eq(serde_json::to_string(&settings).unwrap()),
+                problems_schema::test_case_version.eq(Some(version.clone())),
+            ))
+            .execute(conn)?;
+
+        checker::import(conn, problem_id, &folder(problem_id, Some(&version)))
+    })
+}
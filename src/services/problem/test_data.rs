@@ -0,0 +1,372 @@
+use super::{checker, interactor};
+use crate::judge_actor::scheduler::schedule_judge_servers;
+use crate::models::problems::{ProblemSettings, ProblemType};
+use crate::models::submissions::{JudgeResult, JudgeResultData, JudgeSettings, TestCase};
+use crate::models::test_data_programs::*;
+use crate::services::language::get_enabled;
+use actix_web::web;
+use diesel::prelude::*;
+use judge_client::JudgeClient;
+use server_core::database::{db_connection, Pool};
+use server_core::errors::{ServiceError, ServiceResult};
+use server_core::utils::time::get_cur_naive_date_time;
+use std::fs;
+use std::io;
+
+/// Bytes of test cases sent along in one judge request, larger data is sent in batches.
+const BATCH_SIZE: usize = 16 * 1024 * 1024;
+
+/// Result of a test case the judge server returned nothing for, it fails the check.
+const NO_RESULT: &str = "NO_RESULT";
+
+pub fn save_program(
+    problem_id: i32,
+    kind: TestDataProgramKind,
+    language: String,
+    src: String,
+    pool: web::Data<Pool>,
+) -> ServiceResult<TestDataProgram> {
+    let conn = &db_connection(&pool)?;
+
+    use crate::schema::problems as problems_schema;
+    problems_schema::table
+        .filter(problems_schema::id.eq(problem_id))
+        .select(problems_schema::id)
+        .first::<i32>(conn)?;
+    get_enabled(conn, &language)?;
+
+    let program = TestDataProgram {
+        problem_id: problem_id,
+        kind: kind.as_str().to_owned(),
+        language: language,
+        src: src,
+        update_time: get_cur_naive_date_time(),
+    };
+
+    use crate::schema::test_data_programs as test_data_programs_schema;
+    diesel::insert_into(test_data_programs_schema::table)
+        .values(&program)
+        .on_conflict((
+            test_data_programs_schema::problem_id,
+            test_data_programs_schema::kind,
+        ))
+        .do_update()
+        .set(&program)
+        .execute(conn)?;
+
+    Ok(program)
+}
+
+pub fn get_programs(problem_id: i32, pool: web::Data<Pool>) -> ServiceResult<Vec<TestDataProgram>> {
+    let conn = &db_connection(&pool)?;
+
+    use crate::schema::test_data_programs as test_data_programs_schema;
+    let programs = test_data_programs_schema::table
+        .filter(test_data_programs_schema::problem_id.eq(problem_id))
+        .load(conn)?;
+
+    Ok(programs)
+}
+
+pub fn delete_program(
+    problem_id: i32,
+    kind: TestDataProgramKind,
+    pool: web::Data<Pool>,
+) -> ServiceResult<()> {
+    let conn = &db_connection(&pool)?;
+
+    use crate::schema::test_data_programs as test_data_programs_schema;
+    diesel::delete(
+        test_data_programs_schema::table
+            .filter(test_data_programs_schema::problem_id.eq(problem_id))
+            .filter(test_data_programs_schema::kind.eq(kind.as_str())),
+    )
+    .execute(conn)?;
+
+    Ok(())
+}
+
+fn program_name(kind: TestDataProgramKind) -> String {
+    kind.as_str().replace('_', " ")
+}
+
+/// Judge settings running `program` under the limits of the problem,
+/// test cases are sent along by `run`.
+fn build_settings(
+    conn: &PgConnection,
+    program: &TestDataProgram,
+    settings: &ProblemSettings,
+) -> ServiceResult<JudgeSettings> {
+    let language = get_enabled(conn, &program.language)?;
+    let limit = settings.effective_limit(&language);
+
+    Ok(JudgeSettings {
+        language_config: language.config,
+        src: program.src.clone(),
+        max_cpu_time: limit.max_cpu_time,
+        max_memory: limit.max_memory,
+        test_case_id: None,
+        test_case: None,
+        spj_version: None,
+        spj_config: None,
+        spj_compile_config: None,
+        spj_src: None,
+        output: true,
+        progress_callback: None,
+        stop_on_failure: false,
+        interactor: None,
+    })
+}
+
+/// Splits test cases into batches of at most `BATCH_SIZE` bytes, a larger test case
+/// makes a batch by itself. Returns the batches with the index of their first test cases.
+fn batches(test_cases: Vec<TestCase>) -> Vec<(usize, Vec<TestCase>)> {
+    let mut batches: Vec<(usize, Vec<TestCase>)> = Vec::new();
+    let mut batch_size = 0;
+    for (index, test_case) in test_cases.into_iter().enumerate() {
+        let size = test_case.input.len() + test_case.output.len();
+        match batches.last_mut() {
+            Some((_, batch)) if batch_size + size <= BATCH_SIZE => {
+                batch.push(test_case);
+                batch_size += size;
+            }
+            _ => {
+                batches.push((index, vec![test_case]));
+                batch_size = size;
+            }
+        }
+    }
+
+    batches
+}
+
+/// Runs a program on test cases on a judge server, compile errors are returned to the uploader.
+/// Returns the results with the indexes of their test cases.
+fn run(
+    judge_client: &JudgeClient,
+    kind: TestDataProgramKind,
+    settings: &JudgeSettings,
+    test_cases: Vec<TestCase>,
+) -> ServiceResult<Vec<(usize, JudgeResultData)>> {
+    let mut results = Vec::new();
+    for (first_index, batch) in batches(test_cases) {
+        let server = match schedule_judge_servers().into_iter().next() {
+            Some(server) => server,
+            None => {
+                let hint = format!(
+                    "No judge server available to run the {}.",
+                    program_name(kind)
+                );
+                return Err(ServiceError::BadRequest(hint));
+            }
+        };
+
+        let mut settings = settings.clone();
+        settings.test_case = Some(batch);
+        let result = match judge_client.judge(&server.url, &server.token, &settings) {
+            Ok(raw) => JudgeResult::from(raw),
+            Err(e) => {
+                let hint = format!("Failed to run the {}: {}", program_name(kind), e);
+                return Err(ServiceError::InternalServerErrorWithHint(hint));
+            }
+        };
+
+        match result.details {
+            Some(details) => {
+                // test cases sent along are named from 1 in the order they are sent
+                for detail in details {
+                    let index = detail
+                        .test_case
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|id| id.checked_sub(1));
+                    if let Some(index) = index {
+                        results.push((first_index + index, detail));
+                    }
+                }
+            }
+            None => {
+                let hint = format!(
+                    "{} of the {}: {}",
+                    result.err.unwrap_or_default(),
+                    program_name(kind),
+                    result.err_reason.unwrap_or_default()
+                );
+                return Err(ServiceError::BadRequest(hint));
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+/// Reads a test case to send along, `None` if there is no such file.
+/// Test cases are sent as text, so they have to be encoded in utf-8.
+fn read_test_case_file(path: &str, name: &str) -> ServiceResult<Option<String>> {
+    let content = match fs::read(format!("{}/{}", path, name)) {
+        Ok(content) => content,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+
+    match String::from_utf8(content) {
+        Ok(content) => Ok(Some(content)),
+        Err(_) => {
+            let hint = format!(
+                "{} is not utf-8 text, it can not be checked by the validator or the reference solution.",
+                name
+            );
+            Err(ServiceError::BadRequest(hint))
+        }
+    }
+}
+
+fn has_run(detail: &JudgeResultData) -> bool {
+    detail.result == "SUCCESS" || detail.result == "WRONG_ANSWER"
+}
+
+/// Runs the validator and the reference solution of a problem on the test cases in `path`,
+/// missing outputs of batch problems without checkers are generated by the reference solution.
+/// Returns `None` if the problem has neither of them.
+pub(super) fn check(
+    conn: &PgConnection,
+    judge_client: &JudgeClient,
+    problem_id: i32,
+    settings: &ProblemSettings,
+    path: &str,
+) -> ServiceResult<Option<TestDataReport>> {
+    use crate::schema::test_data_programs as test_data_programs_schema;
+    let programs: Vec<TestDataProgram> = test_data_programs_schema::table
+        .filter(test_data_programs_schema::problem_id.eq(problem_id))
+        .load(conn)?;
+
+    if programs.is_empty() {
+        return Ok(None);
+    }
+
+    let mut inputs = Vec::new();
+    while let Some(input) = read_test_case_file(path, &format!("{}.in", inputs.len() + 1))? {
+        inputs.push(input);
+    }
+    if inputs.is_empty() {
+        return Ok(None);
+    }
+
+    let find_program = |kind: TestDataProgramKind| {
+        programs
+            .iter()
+            .find(|program| program.kind == kind.as_str())
+    };
+    let mut reports: Vec<TestCaseReport> = (1..=inputs.len())
+        .map(|id| TestCaseReport {
+            test_case: id as i32,
+            validator_result: None,
+            solution_result: None,
+            is_generated: false,
+            is_passed: true,
+        })
+        .collect();
+
+    if let Some(validator) = find_program(TestDataProgramKind::Validator) {
+        let test_cases = inputs
+            .iter()
+            .map(|input| TestCase {
+                input: input.clone(),
+                output: String::new(),
+            })
+            .collect();
+        let judge_settings = build_settings(conn, validator, settings)?;
+
+        for (index, detail) in run(
+            judge_client,
+            TestDataProgramKind::Validator,
+            &judge_settings,
+            test_cases,
+        )? {
+            if let Some(report) = reports.get_mut(index) {
+                // validators print nothing, they reject an input by exiting with an error
+                report.validator_result = Some(match detail.result.as_str() {
+                    _ if has_run(&detail) => "VALID".to_owned(),
+                    "RUNTIME_ERROR" => "INVALID".to_owned(),
+                    result => result.to_owned(),
+                });
+                report.is_passed &= has_run(&detail);
+            }
+        }
+        for report in reports
+            .iter_mut()
+            .filter(|report| report.validator_result.is_none())
+        {
+            report.validator_result = Some(NO_RESULT.to_owned());
+            report.is_passed = false;
+        }
+    }
+
+    if let Some(solution) = find_program(TestDataProgramKind::ReferenceSolution) {
+        let can_generate = settings.problem_type == ProblemType::Batch && !settings.is_spj;
+        let outputs: Vec<Option<String>> = (1..=inputs.len())
+            .map(|id| read_test_case_file(path, &format!("{}.out", id)))
+            .collect::<ServiceResult<_>>()?;
+        let test_cases = inputs
+            .iter()
+            .zip(&outputs)
+            .map(|(input, output)| TestCase {
+                input: input.clone(),
+                output: output.clone().unwrap_or_default(),
+            })
+            .collect();
+
+        let mut judge_settings = build_settings(conn, solution, settings)?;
+        if settings.is_spj {
+            let (checker, checker_config) = checker::get_for_judge_with_config(conn, problem_id)?;
+            judge_settings.spj_version = Some(checker.version);
+            judge_settings.spj_config = Some(checker_config.run);
+            judge_settings.spj_compile_config = Some(checker_config.compile);
+            judge_settings.spj_src = Some(checker.src);
+        }
+        if settings.problem_type == ProblemType::Interactive {
+            judge_settings.interactor = Some(interactor::get_for_judge(conn, path)?);
+        }
+
+        for (index, detail) in run(
+            judge_client,
+            TestDataProgramKind::ReferenceSolution,
+            &judge_settings,
+            test_cases,
+        )? {
+            if let Some(report) = reports.get_mut(index) {
+                let id = report.test_case as usize;
+                if can_generate && outputs[id - 1].is_none() {
+                    if has_run(&detail) {
+                        fs::write(
+                            format!("{}/{}.out", path, id),
+                            detail.output.clone().unwrap_or_default(),
+                        )?;
+                        report.is_generated = true;
+                    }
+                    report.is_passed &= has_run(&detail);
+                    report.solution_result = Some(if has_run(&detail) {
+                        "SUCCESS".to_owned()
+                    } else {
+                        detail.result
+                    });
+                } else {
+                    report.is_passed &= detail.result == "SUCCESS";
+                    report.solution_result = Some(detail.result);
+                }
+            }
+        }
+        for report in reports
+            .iter_mut()
+            .filter(|report| report.solution_result.is_none())
+        {
+            report.solution_result = Some(NO_RESULT.to_owned());
+            report.is_passed = false;
+        }
+    }
+
+    Ok(Some(TestDataReport {
+        is_passed: reports.iter().all(|report| report.is_passed),
+        test_cases: reports,
+    }))
+}
@@ -6,8 +6,9 @@ use server_core::errors::{ServiceError, ServiceResult};
 use std::collections::BTreeMap;
 use std::fs::File;
 use std::io::prelude::*;
+use std::path::Path;
 
-fn read_settings(path: &str) -> std::io::Result<problems::ProblemSettings> {
+pub fn read_settings(path: &str) -> std::io::Result<problems::ProblemSettings> {
     let mut file = File::open(path)?;
     let mut contents = String::new();
     file.read_to_string(&mut contents)?;
@@ -17,7 +18,7 @@ fn read_settings(path: &str) -> std::io::Result<problems::ProblemSettings> {
     Ok(settings)
 }
 
-fn read_info(path: &str) -> std::io::Result<problems::ProblemInfo> {
+pub fn read_info(path: &str) -> std::io::Result<problems::ProblemInfo> {
     let mut file = File::open(path)?;
     let mut contents = String::new();
     file.read_to_string(&mut contents)?;
@@ -27,7 +28,7 @@ fn read_info(path: &str) -> std::io::Result<problems::ProblemInfo> {
     Ok(info)
 }
 
-fn read_description(path: &str) -> std::io::Result<Option<String>> {
+pub fn read_description(path: &str) -> std::io::Result<Option<String>> {
     let mut file = match File::open(path) {
         Ok(file) => file,
         Err(_) => {
@@ -40,7 +41,7 @@ fn read_description(path: &str) -> std::io::Result<Option<String>> {
     Ok(Some(contents))
 }
 
-fn read_examples(path: &str) -> std::io::Result<(Vec<problems::Example>, i32)> {
+pub fn read_examples(path: &str) -> std::io::Result<(Vec<problems::Example>, i32)> {
     let mut examples = Vec::new();
 
     let mut count = 0;
@@ -77,33 +78,6 @@ fn read_examples(path: &str) -> std::io::Result<(Vec<problems::Example>, i32)> {
     Ok((examples, count))
 }
 
-pub fn read_insertable_problem(path: &str) -> ServiceResult<problems::InsertableProblem> {
-    let info_path = path.to_string() + "/Info.toml";
-    let description_path = path.to_string() + "/Description.md";
-    let examples_path = path.to_string() + "/Examples";
-    let settings_path = path.to_string() + "/Settings.toml";
-
-    let info = read_info(&info_path)?;
-    let description = read_description(&description_path)?;
-    let (examples, example_count) = read_examples(&examples_path)?;
-    let settings = read_settings(&settings_path)?;
-
-    let contents = problems::ProblemContents {
-        description: description,
-        example_count: example_count,
-        examples: examples,
-    };
-
-    Ok(problems::InsertableProblem {
-        title: info.title,
-        tags: Vec::<i32>::new(),
-        difficulty: info.difficulty,
-        contents: serde_json::to_string(&contents).unwrap(),
-        settings: serde_json::to_string(&settings).unwrap(),
-        is_released: false,
-    })
-}
-
 fn hash_token<D: Digest>(key: &str, output: &mut [u8]) {
     let mut hasher = D::new();
     hasher.update(key.as_bytes());
@@ -129,9 +103,19 @@ struct NormalTestCaseInfo {
 struct SpjTestCaseInfo {
     input_name: String,
     input_size: i32,
+    /// Answer passed to the checker, if any
+    answer_name: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct InteractiveTestCaseInfo {
+    input_name: String,
+    input_size: i32,
+    /// Answer passed to the interactor, if any
+    answer_name: Option<String>,
 }
 
-fn prepare_normal_test_cases(path: &str) -> ServiceResult<i32> {
+fn prepare_normal_test_cases(path: &str, subtasks: &[problems::Subtask]) -> ServiceResult<i32> {
     let mut count = 0;
     let mut test_cases: BTreeMap<String, NormalTestCaseInfo> = BTreeMap::new();
 
@@ -177,11 +161,13 @@ fn prepare_normal_test_cases(path: &str) -> ServiceResult<i32> {
         let hint = String::from("Need at least one test case.");
         return Err(ServiceError::BadRequest(hint));
     }
+    check_subtasks(subtasks, count)?;
 
     let info = serde_json::json!({
         "test_case_number": count,
         "spj": false,
         "test_cases": test_cases,
+        "subtasks": subtasks,
     });
 
     let mut file = File::create(&(path.to_string() + "/info"))?;
@@ -190,15 +176,11 @@ fn prepare_normal_test_cases(path: &str) -> ServiceResult<i32> {
     Ok(count)
 }
 
-fn prepare_spj_test_case(path: &str) -> ServiceResult<i32> {
+fn prepare_spj_test_case(path: &str, subtasks: &[problems::Subtask]) -> ServiceResult<i32> {
     let mut count = 0;
     let mut test_cases: BTreeMap<String, SpjTestCaseInfo> = BTreeMap::new();
 
     loop {
-        // check if spj_src.c exists
-        if count == 0 {
-            File::open(path.to_string() + "/spj_src.c")?;
-        }
         let name = (count + 1).to_string() + ".in";
         let mut file = match File::open(path.to_string() + "/" + &name) {
             Ok(file) => file,
@@ -209,11 +191,21 @@ fn prepare_spj_test_case(path: &str) -> ServiceResult<i32> {
         };
         let mut content = String::new();
         file.read_to_string(&mut content).unwrap();
+
+        let answer_name = (count + 1).to_string() + ".out";
+        let answer_name = if Path::new(&(path.to_string() + "/" + &answer_name)).exists() {
+            Some(answer_name)
+        } else {
+            None
+        };
+
+        // keys of special judge test cases start from 0, see `ProblemSettings::test_case_id`
         test_cases.insert(
             count.to_string(),
             SpjTestCaseInfo {
                 input_name: name,
                 input_size: content.len() as i32,
+                answer_name: answer_name,
             },
         );
 
@@ -224,11 +216,13 @@ fn prepare_spj_test_case(path: &str) -> ServiceResult<i32> {
         let hint = String::from("Need at least one test case.");
         return Err(ServiceError::BadRequest(hint));
     }
+    check_subtasks(subtasks, count)?;
 
     let info = serde_json::json!({
         "test_case_number": count,
         "spj": true,
         "test_cases": test_cases,
+        "subtasks": subtasks,
     });
 
     let mut file = File::create(path.to_string() + "/" + "info").expect("Error creating info");
@@ -238,10 +232,101 @@ fn prepare_spj_test_case(path: &str) -> ServiceResult<i32> {
     Ok(count)
 }
 
-pub fn prepare_test_cases(path: &str, is_spj: bool) -> ServiceResult<i32> {
-    if is_spj {
-        Ok(prepare_spj_test_case(path)?)
+fn prepare_interactive_test_cases(
+    path: &str,
+    subtasks: &[problems::Subtask],
+) -> ServiceResult<i32> {
+    if !["c", "cpp"]
+        .iter()
+        .any(|language| Path::new(&format!("{}/interactor_src.{}", path, language)).exists())
+    {
+        let hint = String::from("Need interactor_src.c or interactor_src.cpp.");
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let mut count = 0;
+    let mut test_cases: BTreeMap<String, InteractiveTestCaseInfo> = BTreeMap::new();
+
+    loop {
+        let id = count + 1;
+        let input_name = id.to_string() + ".in";
+        let mut input_file = match File::open(path.to_string() + "/" + &input_name) {
+            Ok(file) => file,
+            Err(_) => {
+                info!("Can't find file {}", path.to_string() + "/" + &input_name);
+                break;
+            }
+        };
+        let mut input_content = String::new();
+        input_file.read_to_string(&mut input_content)?;
+
+        let answer_name = id.to_string() + ".out";
+        let answer_name = if Path::new(&(path.to_string() + "/" + &answer_name)).exists() {
+            Some(answer_name)
+        } else {
+            None
+        };
+
+        test_cases.insert(
+            id.to_string(),
+            InteractiveTestCaseInfo {
+                input_name: input_name,
+                input_size: input_content.len() as i32,
+                answer_name: answer_name,
+            },
+        );
+
+        count += 1;
+    }
+
+    if count == 0 {
+        let hint = String::from("Need at least one test case.");
+        return Err(ServiceError::BadRequest(hint));
+    }
+    check_subtasks(subtasks, count)?;
+
+    let info = serde_json::json!({
+        "test_case_number": count,
+        "spj": false,
+        "interactive": true,
+        "test_cases": test_cases,
+        "subtasks": subtasks,
+    });
+
+    let mut file = File::create(&(path.to_string() + "/info"))?;
+    file.write_all(info.to_string().as_bytes())?;
+
+    Ok(count)
+}
+
+pub(super) fn check_subtasks(
+    subtasks: &[problems::Subtask],
+    test_case_count: i32,
+) -> ServiceResult<()> {
+    for subtask in subtasks {
+        if subtask.test_cases.is_empty() || subtask.score < 0.0 {
+            let hint = String::from("Subtask needs test cases and a non-negative score.");
+            return Err(ServiceError::BadRequest(hint));
+        }
+        if let Some(id) = subtask
+            .test_cases
+            .iter()
+            .find(|id| **id < 1 || **id > test_case_count)
+        {
+            let hint = format!("Subtask refers to missing test case {}.", id);
+            return Err(ServiceError::BadRequest(hint));
+        }
+    }
+
+    Ok(())
+}
+
+pub fn prepare_test_cases(path: &str, settings: &problems::ProblemSettings) -> ServiceResult<i32> {
+    if settings.problem_type == problems::ProblemType::Interactive {
+        Ok(prepare_interactive_test_cases(path, &settings.subtasks)?)
+    } else if settings.is_spj {
+        Ok(prepare_spj_test_case(path, &settings.subtasks)?)
     } else {
-        Ok(prepare_normal_test_cases(path)?)
+        Ok(prepare_normal_test_cases(path, &settings.subtasks)?)
     }
 }
@@ -0,0 +1,303 @@
+use crate::models::problem_sets::*;
+use crate::models::problems::ProblemSettings;
+use crate::models::region_links::scale_score;
+use crate::models::submissions::RawSubmission;
+use crate::services::rank::export::escape_csv;
+use actix_web::web;
+use chrono::NaiveDateTime;
+use diesel::pg::expression::dsl::any;
+use diesel::prelude::*;
+use server_core::database::{db_connection, Pool};
+use server_core::errors::{ServiceError, ServiceResult};
+use std::collections::HashMap;
+
+/// Out results reported as the verdict of a rejected submission, the first found wins.
+const VERDICT_ORDER: [&str; 7] = [
+    "WRONG_ANSWER",
+    "RUNTIME_ERROR",
+    "CPU_TIME_LIMIT_EXCEEDED",
+    "REAL_TIME_LIMIT_EXCEEDED",
+    "MEMORY_LIMIT_EXCEEDED",
+    "INTERACTOR_ERROR",
+    "SYSTEM_ERROR",
+];
+
+fn get_verdict(submission: &RawSubmission) -> Option<String> {
+    if submission.is_accepted == Some(true) {
+        return Some("ACCEPTED".to_owned());
+    }
+    if let Some(err) = &submission.err {
+        return Some(err.clone());
+    }
+
+    let out_results = submission.out_results.clone().unwrap_or_default();
+    VERDICT_ORDER
+        .iter()
+        .find(|verdict| out_results.iter().any(|out_result| out_result == *verdict))
+        .map(|verdict| verdict.to_string())
+        .or_else(|| {
+            out_results
+                .into_iter()
+                .find(|out_result| out_result != "SUCCESS")
+        })
+}
+
+struct LinkedProblem {
+    inner_id: i32,
+    problem_id: i32,
+    title: String,
+    link_score: Option<i32>,
+    settings: ProblemSettings,
+}
+
+fn build_cell(
+    problem: &LinkedProblem,
+    submissions: &[RawSubmission],
+    deadline: Option<NaiveDateTime>,
+) -> GradeCell {
+    let is_late = |submission: &RawSubmission| match deadline {
+        Some(deadline) => submission.submit_time > deadline,
+        None => false,
+    };
+
+    let mut cell = GradeCell {
+        inner_id: problem.inner_id,
+        verdict: submissions.last().and_then(get_verdict),
+        score: None,
+        first_accepted_time: None,
+        try_times: submissions.len() as i32,
+        late_try_times: submissions
+            .iter()
+            .filter(|submission| is_late(submission))
+            .count() as i32,
+        is_late: false,
+    };
+
+    let mut on_time_score: Option<f64> = None;
+    for submission in submissions {
+        if submission.is_accepted == Some(true) && cell.first_accepted_time.is_none() {
+            cell.verdict = get_verdict(submission);
+            cell.first_accepted_time = Some(submission.submit_time);
+        }
+        if let Some(score) = submission.score {
+            let score = scale_score(problem.link_score, score, &problem.settings);
+            if cell.score.map_or(true, |best| score > best) {
+                cell.score = Some(score);
+            }
+            if !is_late(submission) && on_time_score.map_or(true, |best| score > best) {
+                on_time_score = Some(score);
+            }
+        }
+    }
+
+    cell.is_late = match (cell.first_accepted_time, deadline) {
+        (Some(first_accepted_time), Some(deadline)) => first_accepted_time > deadline,
+        _ => cell.score.is_some() && on_time_score < cell.score,
+    };
+
+    cell
+}
+
+/// Builds a students × problems matrix of a problem set,
+/// students are members of `group_id` or everyone who submitted.
+pub fn get_grades(
+    region: String,
+    group_id: Option<i32>,
+    deadline: Option<NaiveDateTime>,
+    pool: web::Data<Pool>,
+) -> ServiceResult<GradeMatrix> {
+    let conn = &db_connection(&pool)?;
+
+    let settings = match super::utils::get_settings(conn, &region)? {
+        Some(settings) => settings,
+        None => {
+            let hint = "Region is not a problem set.".to_string();
+            return Err(ServiceError::BadRequest(hint));
+        }
+    };
+    let deadline = deadline.or(settings.close_time);
+
+    use crate::schema::problems as problems_schema;
+    use crate::schema::region_links as region_links_schema;
+    let problems: Vec<LinkedProblem> = region_links_schema::table
+        .inner_join(
+            problems_schema::table.on(problems_schema::id.eq(region_links_schema::problem_id)),
+        )
+        .filter(region_links_schema::region.eq(region.clone()))
+        .order(region_links_schema::inner_id.asc())
+        .select((
+            region_links_schema::inner_id,
+            region_links_schema::problem_id,
+            problems_schema::title,
+            region_links_schema::score,
+            problems_schema::settings,
+        ))
+        .load::<(i32, i32, String, Option<i32>, String)>(conn)?
+        .into_iter()
+        .map(|(inner_id, problem_id, title, link_score, settings)| {
+            let settings = serde_json::from_str::<ProblemSettings>(&settings).map_err(|e| {
+                let hint = format!("Settings of problem {} are malformed: {}", problem_id, e);
+                ServiceError::InternalServerErrorWithHint(hint)
+            })?;
+
+            Ok(LinkedProblem {
+                inner_id,
+                problem_id,
+                title,
+                link_score,
+                settings,
+            })
+        })
+        .collect::<ServiceResult<_>>()?;
+
+    use crate::schema::submissions as submissions_schema;
+    let user_ids: Vec<i32> = if let Some(group_id) = group_id {
+        use crate::schema::group_links as group_links_schema;
+        group_links_schema::table
+            .filter(group_links_schema::group_id.eq(group_id))
+            .select(group_links_schema::user_id)
+            .load(conn)?
+    } else {
+        submissions_schema::table
+            .filter(submissions_schema::region.eq(region.clone()))
+            .select(submissions_schema::user_id)
+            .distinct()
+            .load(conn)?
+    };
+
+    let mut submissions: HashMap<(i32, i32), Vec<RawSubmission>> = HashMap::new();
+    for submission in submissions_schema::table
+        .filter(submissions_schema::region.eq(region.clone()))
+        .filter(submissions_schema::user_id.eq(any(user_ids.clone())))
+        .filter(submissions_schema::state.eq("Finished".to_owned()))
+        .order(submissions_schema::submit_time.asc())
+        .load::<RawSubmission>(conn)?
+    {
+        submissions
+            .entry((submission.user_id, submission.problem_id))
+            .or_insert_with(Vec::new)
+            .push(submission);
+    }
+
+    use crate::schema::users as users_schema;
+    let users: Vec<(i32, String, Option<String>, Option<String>)> = users_schema::table
+        .filter(users_schema::id.eq(any(user_ids)))
+        .order(users_schema::student_number.asc())
+        .then_order_by(users_schema::username.asc())
+        .select((
+            users_schema::id,
+            users_schema::username,
+            users_schema::real_name,
+            users_schema::student_number,
+        ))
+        .load(conn)?;
+
+    let rows = users
+        .into_iter()
+        .map(|(user_id, username, real_name, student_number)| {
+            let cells: Vec<GradeCell> = problems
+                .iter()
+                .map(|problem| {
+                    build_cell(
+                        problem,
+                        submissions
+                            .get(&(user_id, problem.problem_id))
+                            .map(|submissions| submissions.as_slice())
+                            .unwrap_or(&[]),
+                        deadline,
+                    )
+                })
+                .collect();
+
+            GradeRow {
+                user_id,
+                username,
+                real_name,
+                student_number,
+                total_accepted: cells
+                    .iter()
+                    .filter(|cell| cell.first_accepted_time.is_some())
+                    .count() as i32,
+                total_score: cells.iter().filter_map(|cell| cell.score).sum(),
+                cells,
+            }
+        })
+        .collect();
+
+    Ok(GradeMatrix {
+        region,
+        group_id,
+        deadline,
+        problems: problems
+            .iter()
+            .map(|problem| GradeProblem {
+                inner_id: problem.inner_id,
+                problem_id: problem.problem_id,
+                title: problem.title.clone(),
+            })
+            .collect(),
+        rows,
+    })
+}
+
+fn format_score(score: Option<f64>) -> String {
+    match score {
+        Some(score) => format!("{}", (score * 100.0).round() / 100.0),
+        None => String::new(),
+    }
+}
+
+/// Flattens a grade matrix into csv, one row per student.
+pub fn to_csv(matrix: &GradeMatrix) -> String {
+    let mut headers = vec![
+        "student_number".to_owned(),
+        "username".to_owned(),
+        "real_name".to_owned(),
+    ];
+    for problem in &matrix.problems {
+        let name = format!("{} {}", problem.inner_id, problem.title);
+        headers.push(format!("{} verdict", name));
+        headers.push(format!("{} score", name));
+        headers.push(format!("{} first accepted", name));
+        headers.push(format!("{} tries", name));
+        if matrix.deadline.is_some() {
+            headers.push(format!("{} late", name));
+        }
+    }
+    headers.push("solved".to_owned());
+    headers.push("total score".to_owned());
+
+    let mut rows = vec![headers];
+    for row in &matrix.rows {
+        let mut fields = vec![
+            row.student_number.clone().unwrap_or_default(),
+            row.username.clone(),
+            row.real_name.clone().unwrap_or_default(),
+        ];
+        for cell in &row.cells {
+            fields.push(cell.verdict.clone().unwrap_or_default());
+            fields.push(format_score(cell.score));
+            fields.push(
+                cell.first_accepted_time
+                    .map(|time| time.format("%Y-%m-%d %H:%M:%S").to_string())
+                    .unwrap_or_default(),
+            );
+            fields.push(cell.try_times.to_string());
+            if matrix.deadline.is_some() {
+                fields.push(if cell.is_late { "late" } else { "" }.to_owned());
+            }
+        }
+        fields.push(row.total_accepted.to_string());
+        fields.push(format_score(Some(row.total_score)));
+        rows.push(fields);
+    }
+
+    // spreadsheets take the byte order mark as a hint of utf-8
+    let mut csv = String::from("\u{feff}");
+    for row in rows {
+        let fields: Vec<String> = row.iter().map(|field| escape_csv(field)).collect();
+        csv += &fields.join(",");
+        csv += "\r\n";
+    }
+    csv
+}
@@ -1,6 +1,10 @@
+pub mod grades;
+pub mod utils;
+
 use server_core::database::{db_connection, Pool};
-use server_core::errors::ServiceResult;
+use server_core::errors::{ServiceError, ServiceResult};
 
+use crate::models::access_control_list::AccessControlListColumn;
 use crate::models::problem_sets::*;
 
 use crate::models::regions::*;
@@ -13,10 +17,13 @@ pub fn create(
     title: String,
     introduction: Option<String>,
     can_view_testcases: bool,
+    settings: ProblemSetSettings,
     pool: web::Data<Pool>,
 ) -> ServiceResult<()> {
     let conn = &db_connection(&pool)?;
 
+    utils::check_settings_legal(&settings)?;
+
     use crate::schema::regions as regions_schema;
     diesel::insert_into(regions_schema::table)
         .values(&Region {
@@ -27,10 +34,11 @@ pub fn create(
 
     use crate::schema::problem_sets as problem_sets_schema;
     diesel::insert_into(problem_sets_schema::table)
-        .values(&ProblemSetInfo {
+        .values(&RawProblemSet {
             region: region,
             title: title,
             introduction: introduction,
+            settings: serde_json::to_string(&settings).unwrap(),
         })
         .execute(conn)?;
 
@@ -61,14 +69,17 @@ pub fn get_set_list(
 
     let total: i64 = target.clone().count().get_result(conn)?;
 
-    let res = target
+    let raw_problem_sets: Vec<RawProblemSet> = target
         .offset(offset.into())
         .limit(limit.into())
         .load(conn)?;
 
     Ok(SizedList {
         total: total,
-        list: res,
+        list: raw_problem_sets
+            .into_iter()
+            .map(ProblemSetInfo::from)
+            .collect(),
     })
 }
 
@@ -99,10 +110,15 @@ pub fn update(
     new_title: Option<String>,
     new_introduction: Option<String>,
     new_can_view_testcases: Option<bool>,
+    new_settings: Option<ProblemSetSettings>,
     pool: web::Data<Pool>,
 ) -> ServiceResult<()> {
     let conn = &db_connection(&pool)?;
 
+    if let Some(settings) = &new_settings {
+        utils::check_settings_legal(settings)?;
+    }
+
     use crate::schema::regions as regions_schema;
     diesel::update(regions_schema::table.filter(regions_schema::name.eq(region.clone())))
         .set(RegionForm {
@@ -117,8 +133,55 @@ pub fn update(
     .set(ProblemSetForm {
         title: new_title,
         introduction: new_introduction,
+        settings: new_settings.map(|settings| serde_json::to_string(&settings).unwrap()),
     })
     .execute(conn)?;
 
     Ok(())
 }
+
+pub fn insert_groups(
+    region: String,
+    group_ids: Vec<i32>,
+    pool: web::Data<Pool>,
+) -> ServiceResult<()> {
+    let conn = &db_connection(&pool)?;
+
+    if utils::get_settings(conn, &region)?.is_none() {
+        let hint = "Region is not a problem set.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let mut res = Vec::new();
+    for group_id in group_ids {
+        res.push(AccessControlListColumn {
+            self_type: "group".to_string(),
+            id: group_id,
+            region: region.clone(),
+            is_unrated: Some(false),
+            is_manager: false,
+        });
+    }
+
+    use crate::schema::access_control_list as access_control_list_schema;
+    diesel::insert_into(access_control_list_schema::table)
+        .values(&res)
+        .execute(conn)?;
+
+    Ok(())
+}
+
+pub fn delete_group(region: String, group_id: i32, pool: web::Data<Pool>) -> ServiceResult<()> {
+    let conn = &db_connection(&pool)?;
+
+    use crate::schema::access_control_list as access_control_list_schema;
+    diesel::delete(
+        access_control_list_schema::table
+            .filter(access_control_list_schema::region.eq(region))
+            .filter(access_control_list_schema::self_type.eq("group"))
+            .filter(access_control_list_schema::id.eq(group_id)),
+    )
+    .execute(conn)?;
+
+    Ok(())
+}
@@ -0,0 +1,44 @@
+use crate::models::problem_sets::*;
+use diesel::prelude::*;
+use server_core::errors::{ServiceError, ServiceResult};
+
+/// Settings of a problem set, none if the region is not a problem set.
+pub fn get_settings(
+    conn: &PgConnection,
+    region: &str,
+) -> ServiceResult<Option<ProblemSetSettings>> {
+    use crate::schema::problem_sets as problem_sets_schema;
+    let raw: Option<RawProblemSet> = problem_sets_schema::table
+        .filter(problem_sets_schema::region.eq(region.to_owned()))
+        .first(conn)
+        .optional()?;
+
+    Ok(raw.map(|raw| ProblemSetInfo::from(raw).settings))
+}
+
+pub fn check_settings_legal(settings: &ProblemSetSettings) -> ServiceResult<()> {
+    if let (Some(open_time), Some(close_time)) = (settings.open_time, settings.close_time) {
+        if open_time >= close_time {
+            let hint = "Open time should be earlier than close time.".to_string();
+            return Err(ServiceError::BadRequest(hint));
+        }
+    }
+    if let Some(late_close_time) = settings.late_close_time {
+        if settings
+            .close_time
+            .map_or(true, |close_time| late_close_time < close_time)
+        {
+            let hint = "Late close time needs a close time before it.".to_string();
+            return Err(ServiceError::BadRequest(hint));
+        }
+    }
+    if settings.late_penalty_factor.is_nan()
+        || settings.late_penalty_factor < 0.0
+        || settings.late_penalty_factor > 1.0
+    {
+        let hint = "Late penalty factor should be between 0 and 1.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,571 @@
+use crate::models::contests::*;
+use crate::models::submissions::RawSubmission;
+use crate::services::contest as contest_service;
+use actix_web::web;
+use chrono::*;
+use diesel::pg::expression::dsl::any;
+use diesel::prelude::*;
+use server_core::database::{db_connection, Pool};
+use server_core::errors::ServiceResult;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportFormat {
+    Csv,
+    Html,
+    /// Event feed of the ICPC Contest Data Service, one json object per line
+    Cds,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnratedPolicy {
+    /// Keeps unrated participants with a mark
+    Mark,
+    Exclude,
+}
+
+impl Default for UnratedPolicy {
+    fn default() -> Self {
+        UnratedPolicy::Mark
+    }
+}
+
+pub struct RankExport {
+    pub content_type: &'static str,
+    pub file_name: String,
+    pub body: String,
+}
+
+/// A rank flattened into rows, shared by csv and html exports.
+struct RankTable {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+struct Participant {
+    user_id: i32,
+    username: String,
+    real_name: Option<String>,
+    rank: Option<i32>,
+    is_unrated: bool,
+    cells: Vec<String>,
+}
+
+pub fn export(
+    region: String,
+    format: ExportFormat,
+    unrated_policy: UnratedPolicy,
+    pool: web::Data<Pool>,
+) -> ServiceResult<RankExport> {
+    let conn = &db_connection(&pool)?;
+
+    use crate::schema::contests as contests_schema;
+    let contest = Contest::from(
+        contests_schema::table
+            .filter(contests_schema::region.eq(region.clone()))
+            .first::<RawContest>(conn)?,
+    );
+
+    if format == ExportFormat::Cds {
+        return Ok(RankExport {
+            content_type: "application/x-ndjson",
+            file_name: format!("{}.ndjson", region),
+            body: build_event_feed(&contest, unrated_policy, conn)?,
+        });
+    }
+
+    let table = build_rank_table(&contest, unrated_policy, conn, pool)?;
+    Ok(match format {
+        ExportFormat::Csv => RankExport {
+            content_type: "text/csv; charset=utf-8",
+            file_name: format!("{}.csv", region),
+            body: to_csv(&table),
+        },
+        _ => RankExport {
+            content_type: "text/html; charset=utf-8",
+            file_name: format!("{}.html", region),
+            body: to_html(&contest.title, &table),
+        },
+    })
+}
+
+fn build_rank_table(
+    contest: &Contest,
+    unrated_policy: UnratedPolicy,
+    conn: &PgConnection,
+    pool: web::Data<Pool>,
+) -> ServiceResult<RankTable> {
+    let region = contest.region.clone();
+
+    let (mut headers, participants): (Vec<String>, Vec<Participant>) = match contest.settings.rule {
+        ContestRule::Acm => {
+            let rank = contest_service::get_acm_rank(region, pool)?;
+            let mut headers = Vec::new();
+            if let Some(column) = rank.columns.first() {
+                for problem_block in &column.problem_block {
+                    headers.push(format!("{} tries", problem_block.inner_id));
+                    headers.push(format!("{} time", problem_block.inner_id));
+                }
+            }
+            headers.push("solved".to_owned());
+            headers.push("penalty".to_owned());
+
+            let participants = rank
+                .columns
+                .into_iter()
+                .map(|column| {
+                    let mut cells = Vec::new();
+                    for problem_block in &column.problem_block {
+                        cells.push(problem_block.try_times.to_string());
+                        cells.push(match problem_block.is_accepted {
+                            // minutes since the start, as on paper scoreboards
+                            Some(true) => (problem_block
+                                .last_submit_time
+                                .unwrap()
+                                .signed_duration_since(contest.start_time)
+                                .num_minutes())
+                            .to_string(),
+                            _ => String::new(),
+                        });
+                    }
+                    cells.push(column.total_accepted.to_string());
+                    cells.push((column.time_cost / 60).to_string());
+
+                    Participant {
+                        user_id: column.user_id,
+                        username: column.username,
+                        real_name: column.real_name,
+                        rank: column.rank,
+                        is_unrated: column.is_unrated == Some(true),
+                        cells,
+                    }
+                })
+                .collect();
+
+            (headers, participants)
+        }
+        ContestRule::Oi => {
+            let rank = contest_service::get_oi_rank(region, pool)?;
+            let mut headers = Vec::new();
+            if let Some(column) = rank.columns.first() {
+                for problem_block in &column.problem_block {
+                    headers.push(format!("{} score", problem_block.inner_id));
+                }
+            }
+            headers.push("total score".to_owned());
+
+            let participants = rank
+                .columns
+                .into_iter()
+                .map(|column| {
+                    let mut cells: Vec<String> = column
+                        .problem_block
+                        .iter()
+                        .map(|problem_block| format_score(problem_block.score))
+                        .collect();
+                    cells.push(format_score(Some(column.total_score)));
+
+                    Participant {
+                        user_id: column.user_id,
+                        username: column.username,
+                        real_name: column.real_name,
+                        rank: column.rank,
+                        is_unrated: column.is_unrated == Some(true),
+                        cells,
+                    }
+                })
+                .collect();
+
+            (headers, participants)
+        }
+        ContestRule::Ioi => {
+            let rank = contest_service::get_ioi_rank(region, pool)?;
+            let mut headers = Vec::new();
+            if let Some(column) = rank.columns.first() {
+                for problem_block in &column.problem_block {
+                    headers.push(format!("{} score", problem_block.inner_id));
+                    headers.push(format!("{} tries", problem_block.inner_id));
+                }
+            }
+            headers.push("total score".to_owned());
+
+            let participants = rank
+                .columns
+                .into_iter()
+                .map(|column| {
+                    let mut cells = Vec::new();
+                    for problem_block in &column.problem_block {
+                        cells.push(format_score(problem_block.score));
+                        cells.push(problem_block.try_times.to_string());
+                    }
+                    cells.push(format_score(Some(column.total_score)));
+
+                    Participant {
+                        user_id: column.user_id,
+                        username: column.username,
+                        real_name: column.real_name,
+                        rank: column.rank,
+                        is_unrated: column.is_unrated == Some(true),
+                        cells,
+                    }
+                })
+                .collect();
+
+            (headers, participants)
+        }
+    };
+
+    let student_numbers = get_student_numbers(
+        conn,
+        participants
+            .iter()
+            .map(|participant| participant.user_id)
+            .collect(),
+    )?;
+
+    let mut table_headers: Vec<String> = vec![
+        "rank".to_owned(),
+        "username".to_owned(),
+        "real_name".to_owned(),
+        "student_number".to_owned(),
+    ];
+    if unrated_policy == UnratedPolicy::Mark {
+        table_headers.push("unrated".to_owned());
+    }
+    table_headers.append(&mut headers);
+
+    let mut rows = Vec::new();
+    for participant in participants {
+        if participant.is_unrated && unrated_policy == UnratedPolicy::Exclude {
+            continue;
+        }
+
+        let mut row = vec![
+            participant
+                .rank
+                .map(|rank| rank.to_string())
+                .unwrap_or_default(),
+            participant.username,
+            participant.real_name.unwrap_or_default(),
+            student_numbers
+                .get(&participant.user_id)
+                .cloned()
+                .flatten()
+                .unwrap_or_default(),
+        ];
+        if unrated_policy == UnratedPolicy::Mark {
+            row.push(if participant.is_unrated { "*" } else { "" }.to_owned());
+        }
+        row.extend(participant.cells);
+        rows.push(row);
+    }
+
+    Ok(RankTable {
+        headers: table_headers,
+        rows,
+    })
+}
+
+fn format_score(score: Option<f64>) -> String {
+    match score {
+        Some(score) => format!("{}", (score * 100.0).round() / 100.0),
+        None => String::new(),
+    }
+}
+
+fn get_student_numbers(
+    conn: &PgConnection,
+    user_ids: Vec<i32>,
+) -> ServiceResult<HashMap<i32, Option<String>>> {
+    use crate::schema::users as users_schema;
+    Ok(users_schema::table
+        .filter(users_schema::id.eq(any(user_ids)))
+        .select((users_schema::id, users_schema::student_number))
+        .load::<(i32, Option<String>)>(conn)?
+        .into_iter()
+        .collect())
+}
+
+/// Quotes a csv field if needed, shared by other csv exports.
+pub fn escape_csv(field: &str) -> String {
+    if field.contains(|c| c == ',' || c == '"' || c == '\n' || c == '\r') {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+fn to_csv(table: &RankTable) -> String {
+    let mut csv = String::new();
+    for row in std::iter::once(&table.headers).chain(table.rows.iter()) {
+        let fields: Vec<String> = row.iter().map(|field| escape_csv(field)).collect();
+        csv += &fields.join(",");
+        csv += "\r\n";
+    }
+    csv
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+fn to_html(title: &str, table: &RankTable) -> String {
+    let mut html = String::new();
+    html += "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n";
+    html += &format!("<title>{}</title>\n", escape_html(title));
+    html += "<style>table { border-collapse: collapse; } \
+             th, td { border: 1px solid #999; padding: 2px 6px; text-align: center; }</style>\n";
+    html += "</head>\n<body>\n";
+    html += &format!("<h1>{}</h1>\n", escape_html(title));
+    html += &format!(
+        "<p>Generated at {}</p>\n",
+        server_core::utils::time::get_cur_naive_date_time()
+    );
+    html += "<table>\n<tr>";
+    for header in &table.headers {
+        html += &format!("<th>{}</th>", escape_html(header));
+    }
+    html += "</tr>\n";
+    for row in &table.rows {
+        html += "<tr>";
+        for field in row {
+            html += &format!("<td>{}</td>", escape_html(field));
+        }
+        html += "</tr>\n";
+    }
+    html += "</table>\n</body>\n</html>\n";
+    html
+}
+
+fn format_time(time: NaiveDateTime) -> String {
+    match Local.from_local_datetime(&time).earliest() {
+        Some(time) => time.to_rfc3339_opts(SecondsFormat::Millis, false),
+        None => time.format("%Y-%m-%dT%H:%M:%S%.3f").to_string(),
+    }
+}
+
+/// Formats a duration as `h:mm:ss.uuu`, as relative times in the event feed.
+fn format_duration(duration: Duration) -> String {
+    let millis = duration.num_milliseconds();
+    let sign = if millis < 0 { "-" } else { "" };
+    let millis = millis.abs();
+    format!(
+        "{}{}:{:02}:{:02}.{:03}",
+        sign,
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        millis % 1000
+    )
+}
+
+/// Judgement type ids of the event feed for the verdict of a submission.
+fn judgement_type_id(submission: &RawSubmission) -> &'static str {
+    if submission.err.as_deref() == Some("CompileError") {
+        return "CE";
+    }
+    if submission.is_accepted == Some(true) {
+        return "AC";
+    }
+
+    let out_results = submission.out_results.clone().unwrap_or_default();
+    let contains = |result: &str| out_results.iter().any(|out_result| out_result == result);
+    if contains("CPU_TIME_LIMIT_EXCEEDED") || contains("REAL_TIME_LIMIT_EXCEEDED") {
+        "TLE"
+    } else if contains("MEMORY_LIMIT_EXCEEDED") {
+        "MLE"
+    } else if contains("RUNTIME_ERROR") {
+        "RTE"
+    } else {
+        "WA"
+    }
+}
+
+fn build_event_feed(
+    contest: &Contest,
+    unrated_policy: UnratedPolicy,
+    conn: &PgConnection,
+) -> ServiceResult<String> {
+    let mut events: Vec<serde_json::Value> = Vec::new();
+    let mut push_event = |event_type: &str, id: String, data: serde_json::Value| {
+        events.push(serde_json::json!({
+            "type": event_type,
+            "id": id,
+            "op": "create",
+            "data": data,
+        }));
+    };
+
+    let mut contest_data = serde_json::json!({
+        "id": contest.region,
+        "name": contest.title,
+        "formal_name": contest.title,
+        "start_time": format_time(contest.start_time),
+        "scoreboard_type": if contest.settings.rule == ContestRule::Acm { "pass-fail" } else { "score" },
+    });
+    if let Some(end_time) = contest.end_time {
+        contest_data["duration"] = serde_json::json!(format_duration(
+            end_time.signed_duration_since(contest.start_time)
+        ));
+        if let Some(seal_time) = contest.seal_time {
+            contest_data["scoreboard_freeze_duration"] =
+                serde_json::json!(format_duration(end_time.signed_duration_since(seal_time)));
+        }
+    }
+    if contest.settings.rule == ContestRule::Acm {
+        contest_data["penalty_time"] =
+            serde_json::json!(contest.settings.acm_penalty.penalty_minutes);
+    }
+    push_event("contest", contest.region.clone(), contest_data);
+
+    for (id, name, penalty, solved) in &[
+        ("AC", "Accepted", false, true),
+        ("WA", "Wrong Answer", true, false),
+        ("TLE", "Time Limit Exceeded", true, false),
+        ("MLE", "Memory Limit Exceeded", true, false),
+        ("RTE", "Run-Time Error", true, false),
+        (
+            "CE",
+            "Compile Error",
+            contest.settings.acm_penalty.count_compile_error,
+            false,
+        ),
+    ] {
+        push_event(
+            "judgement-types",
+            id.to_string(),
+            serde_json::json!({
+                "id": id,
+                "name": name,
+                "penalty": penalty,
+                "solved": solved,
+            }),
+        );
+    }
+
+    use crate::schema::problems as problems_schema;
+    use crate::schema::region_links as region_links_schema;
+    let problems: Vec<(i32, i32, String)> = region_links_schema::table
+        .inner_join(
+            problems_schema::table.on(problems_schema::id.eq(region_links_schema::problem_id)),
+        )
+        .filter(region_links_schema::region.eq(contest.region.clone()))
+        .order(region_links_schema::inner_id.asc())
+        .select((
+            region_links_schema::inner_id,
+            region_links_schema::problem_id,
+            problems_schema::title,
+        ))
+        .load(conn)?;
+
+    let mut inner_ids: HashMap<i32, i32> = HashMap::new();
+    for (ordinal, (inner_id, problem_id, title)) in problems.iter().enumerate() {
+        inner_ids.insert(*problem_id, *inner_id);
+        push_event(
+            "problems",
+            inner_id.to_string(),
+            serde_json::json!({
+                "id": inner_id.to_string(),
+                "label": inner_id.to_string(),
+                "name": title,
+                "ordinal": ordinal,
+            }),
+        );
+    }
+
+    use crate::schema::access_control_list as access_control_list_schema;
+    use crate::schema::users as users_schema;
+    let teams: Vec<(i32, String, Option<String>, Option<bool>)> = access_control_list_schema::table
+        .inner_join(users_schema::table.on(users_schema::id.eq(access_control_list_schema::id)))
+        .filter(access_control_list_schema::region.eq(contest.region.clone()))
+        .filter(access_control_list_schema::self_type.eq("user"))
+        .filter(access_control_list_schema::is_manager.eq(false))
+        .select((
+            users_schema::id,
+            users_schema::username,
+            users_schema::real_name,
+            access_control_list_schema::is_unrated,
+        ))
+        .load(conn)?;
+
+    let mut team_ids = Vec::new();
+    for (user_id, username, real_name, is_unrated) in teams {
+        let is_unrated = is_unrated == Some(true);
+        if is_unrated && unrated_policy == UnratedPolicy::Exclude {
+            continue;
+        }
+        team_ids.push(user_id);
+        push_event(
+            "teams",
+            user_id.to_string(),
+            serde_json::json!({
+                "id": user_id.to_string(),
+                "name": username.clone(),
+                "display_name": real_name.unwrap_or(username),
+                "hidden": is_unrated,
+            }),
+        );
+    }
+
+    use crate::schema::submissions as submissions_schema;
+    let submissions: Vec<RawSubmission> = submissions_schema::table
+        .filter(submissions_schema::region.eq(contest.region.clone()))
+        .filter(submissions_schema::user_id.eq(any(team_ids)))
+        .filter(submissions_schema::state.eq("Finished".to_owned()))
+        .order(submissions_schema::submit_time.asc())
+        .load(conn)?;
+
+    for submission in &submissions {
+        let inner_id = match inner_ids.get(&submission.problem_id) {
+            Some(inner_id) => inner_id,
+            None => continue,
+        };
+        let id = submission.id.to_string();
+        push_event(
+            "submissions",
+            id.clone(),
+            serde_json::json!({
+                "id": id,
+                "team_id": submission.user_id.to_string(),
+                "problem_id": inner_id.to_string(),
+                "language_id": submission.language,
+                "time": format_time(submission.submit_time),
+                "contest_time": format_duration(
+                    submission.submit_time.signed_duration_since(contest.start_time)
+                ),
+            }),
+        );
+
+        let finish_time = submission.finish_time.unwrap_or(submission.submit_time);
+        let mut judgement = serde_json::json!({
+            "id": id,
+            "submission_id": id,
+            "judgement_type_id": judgement_type_id(submission),
+            "start_time": format_time(submission.submit_time),
+            "start_contest_time": format_duration(
+                submission.submit_time.signed_duration_since(contest.start_time)
+            ),
+            "end_time": format_time(finish_time),
+            "end_contest_time": format_duration(
+                finish_time.signed_duration_since(contest.start_time)
+            ),
+        });
+        if let Some(score) = submission.score {
+            judgement["score"] = serde_json::json!(score);
+        }
+        push_event("judgements", id, judgement);
+    }
+
+    let mut feed = String::new();
+    for event in events {
+        feed += &event.to_string();
+        feed += "\n";
+    }
+    Ok(feed)
+}
@@ -1,24 +1,58 @@
 use crate::models::access_control_list::*;
 use crate::models::contests::*;
+use crate::models::problems::ProblemSettings;
 use crate::models::ranks::*;
 use crate::models::region_links::*;
 use crate::models::submissions::*;
 use crate::services::region::utils::*;
-use crate::statics::ACM_RANK_CACHE;
+use crate::statics::{ACM_RANK_APPLY_LOCK, ACM_RANK_CACHE, IOI_RANK_CACHE, OI_RANK_CACHE};
+use chrono::NaiveDateTime;
+use diesel::pg::expression::dsl::any;
 use diesel::prelude::*;
-use server_core::errors::ServiceResult;
+use server_core::errors::{ServiceError, ServiceResult};
 use server_core::utils::time::get_cur_naive_date_time;
+use std::collections::HashMap;
 
-pub fn update_acm_rank_cache(
+/// Drops cached ranks of a region, they are built again on next visit.
+pub fn invalidate_rank_caches(region: &str) {
+    ACM_RANK_CACHE.write().unwrap().remove(region);
+    OI_RANK_CACHE.write().unwrap().remove(region);
+    IOI_RANK_CACHE.write().unwrap().remove(region);
+}
+
+/// Rebuilds the whole acm rank of a region from the database if `need_update` holds
+/// for the cached one.
+///
+/// Ranks are kept up to date by `apply_acm_submission` as submissions finish,
+/// rebuilding only builds the first rank, reveals the final one and repairs it.
+/// It is checked and built under the write lock, so concurrent visits rebuild it once
+/// and submissions applied meanwhile are not lost.
+pub fn update_acm_rank_cache<F>(
     region: String,
     conn: &PgConnection,
     is_final: bool,
-) -> ServiceResult<()> {
-    log::info!("Updating acm rank");
+    need_update: F,
+) -> ServiceResult<()>
+where
+    F: Fn(Option<&ACMRank>) -> bool,
+{
     if &get_self_type(region.clone(), conn)? != "contest" {
         return Ok(());
     }
 
+    let mut lock = ACM_RANK_CACHE.write().unwrap();
+    if !need_update(lock.get(&region)) {
+        return Ok(());
+    }
+
+    log::info!("Rebuilding acm rank of {}", region);
+    let rank = build_acm_rank(region.clone(), conn, is_final)?;
+    lock.insert(region, rank);
+
+    Ok(())
+}
+
+fn build_acm_rank(region: String, conn: &PgConnection, is_final: bool) -> ServiceResult<ACMRank> {
     use crate::schema::contests as contests_schema;
     let contest = Contest::from(
         contests_schema::table
@@ -29,11 +63,18 @@ pub fn update_acm_rank_cache(
     let mut rank = ACMRank {
         region: region.clone(),
         last_updated_time: get_cur_naive_date_time(),
+        is_final,
+        rules: contest.settings.acm_penalty.clone(),
         columns: Vec::new(),
     };
 
-    use crate::schema::access_control_list as access_control_list_schema;
+    use crate::schema::region_links as region_links_schema;
+    let region_links: Vec<RegionLink> = region_links_schema::table
+        .filter(region_links_schema::region.eq(region.clone()))
+        .order(region_links_schema::inner_id.asc())
+        .load(conn)?;
 
+    use crate::schema::access_control_list as access_control_list_schema;
     let access_control_list: Vec<AccessControlListColumn> = access_control_list_schema::table
         .filter(access_control_list_schema::region.eq(region.clone()))
         .filter(access_control_list_schema::self_type.eq("user"))
@@ -42,62 +83,206 @@ pub fn update_acm_rank_cache(
 
     for access_control_list_colunm in access_control_list {
         rank.columns.push(build_acm_rank_column(
-            contest.clone(),
+            &contest,
+            &region_links,
             access_control_list_colunm,
             conn,
             is_final,
         )?);
     }
 
-    let slice = rank.columns.as_mut_slice();
-    slice.sort_by(|colume_a, colume_b| {
-        if colume_a.total_accepted != colume_b.total_accepted {
-            colume_a
-                .total_accepted
-                .cmp(&colume_b.total_accepted)
-                .reverse()
-        } else {
-            colume_a.time_cost.cmp(&colume_b.time_cost)
+    mark_first_accepted(&mut rank);
+    sort_acm_rank(&mut rank);
+
+    Ok(rank)
+}
+
+/// Replays the unfreezing of an acm rank in the order of an ICPC resolver:
+/// the lowest placed user with sealed problems reveals the first of them,
+/// until no sealed problem is left.
+pub fn build_resolver_replay(region: String, conn: &PgConnection) -> ServiceResult<ResolverReplay> {
+    use crate::schema::contests as contests_schema;
+    let contest = Contest::from(
+        contests_schema::table
+            .filter(contests_schema::region.eq(region.clone()))
+            .first::<RawContest>(conn)?,
+    );
+
+    // both ranks are read from one snapshot, so that they have the same users and problems
+    let (frozen, unfrozen) = conn
+        .build_transaction()
+        .repeatable_read()
+        .read_only()
+        .run::<_, ServiceError, _>(|| {
+            Ok((
+                build_acm_rank(region.clone(), conn, false)?,
+                build_acm_rank(region, conn, true)?,
+            ))
+        })?;
+    let mismatch = || {
+        let hint = "The frozen and the final rank do not match.".to_string();
+        ServiceError::InternalServerErrorWithHint(hint)
+    };
+
+    let mut final_blocks: HashMap<(i32, i32), ACMProblemBlock> = HashMap::new();
+    for rank_column in &unfrozen.columns {
+        for problem_block in &rank_column.problem_block {
+            final_blocks.insert(
+                (rank_column.user_id, problem_block.inner_id),
+                problem_block.clone(),
+            );
         }
-    });
+    }
+
+    let mut rank = frozen.clone();
+    let mut events = Vec::new();
+    loop {
+        let position_before = match rank.columns.iter().rposition(|column| {
+            column
+                .problem_block
+                .iter()
+                .any(|problem_block| problem_block.is_sealed)
+        }) {
+            Some(position) => position,
+            None => break,
+        };
+
+        let rank_column = &mut rank.columns[position_before];
+        let user_id = rank_column.user_id;
+        let problem_block = rank_column
+            .problem_block
+            .iter_mut()
+            .find(|problem_block| problem_block.is_sealed)
+            .ok_or_else(mismatch)?;
+        let inner_id = problem_block.inner_id;
+        *problem_block = final_blocks
+            .get(&(user_id, inner_id))
+            .ok_or_else(mismatch)?
+            .clone();
+        summarize_acm_column(rank_column, &contest);
+
+        mark_first_accepted(&mut rank);
+        sort_acm_rank(&mut rank);
+
+        let position_after = rank
+            .columns
+            .iter()
+            .position(|column| column.user_id == user_id)
+            .ok_or_else(mismatch)?;
+        let rank_column = &rank.columns[position_after];
+        let problem_block = rank_column
+            .problem_block
+            .iter()
+            .find(|problem_block| problem_block.inner_id == inner_id)
+            .ok_or_else(mismatch)?;
+        events.push(ResolverEvent {
+            user_id,
+            inner_id,
+            is_accepted: problem_block.is_accepted,
+            try_times: problem_block.try_times,
+            total_accepted: rank_column.total_accepted,
+            time_cost: rank_column.time_cost,
+            position_before: position_before as i32,
+            position_after: position_after as i32,
+            rank: rank_column.rank,
+        });
+    }
+
+    Ok(ResolverReplay { frozen, events })
+}
+
+/// Brings the cached acm rank up to date with a finished submission,
+/// only the block of its user and problem is built again.
+pub fn apply_acm_submission(conn: &PgConnection, submission: &Submission) -> ServiceResult<()> {
+    let region = match &submission.region {
+        Some(region) => region.clone(),
+        None => return Ok(()),
+    };
+
+    // built from the database on next visit
+    if !ACM_RANK_CACHE.read().unwrap().contains_key(&region) {
+        return Ok(());
+    }
+
+    use crate::schema::contests as contests_schema;
+    let contest = Contest::from(
+        contests_schema::table
+            .filter(contests_schema::region.eq(region.clone()))
+            .first::<RawContest>(conn)?,
+    );
+    if contest.settings.rule != ContestRule::Acm {
+        return Ok(());
+    }
 
-    // assgin rank
-    let mut rank_count = 0;
     use crate::schema::region_links as region_links_schema;
-    let mut last_total_accepted = region_links_schema::table
+    let region_link: RegionLink = region_links_schema::table
         .filter(region_links_schema::region.eq(region.clone()))
-        .count()
-        .get_result::<i64>(conn)? as i32
-        + 1;
-    let mut last_time_cost = 0;
-    for colume in slice.iter_mut() {
-        if colume.is_unrated == Some(true) {
-            continue;
-        }
-        if colume.total_accepted < last_total_accepted {
-            rank_count += 1;
-        } else if colume.time_cost > last_time_cost {
-            rank_count += 1;
-        }
+        .filter(region_links_schema::problem_id.eq(submission.problem_id))
+        .first(conn)?;
 
-        last_total_accepted = colume.total_accepted;
-        last_time_cost = colume.time_cost;
-        colume.rank = Some(rank_count);
+    // submissions are loaded outside of the rank lock, applies are kept in order
+    // so that a block loaded earlier never overwrites a newer one
+    let _apply_lock = ACM_RANK_APPLY_LOCK.lock().unwrap();
+    let submissions = load_acm_submissions(&region_link, submission.user_id, conn)?;
+
+    let mut lock = ACM_RANK_CACHE.write().unwrap();
+    let rank = match lock.get_mut(&region) {
+        Some(rank) => rank,
+        None => return Ok(()),
+    };
+    let problem_block = fold_acm_problem_block(&contest, &region_link, submissions, rank.is_final);
+    let rank_column = match rank
+        .columns
+        .iter_mut()
+        .find(|column| column.user_id == submission.user_id)
+    {
+        Some(rank_column) => rank_column,
+        // managers are not ranked
+        None => return Ok(()),
+    };
+    match rank_column
+        .problem_block
+        .iter_mut()
+        .find(|block| block.inner_id == region_link.inner_id)
+    {
+        Some(block) => *block = problem_block,
+        None => return Ok(()),
     }
-    rank.columns = slice.to_vec();
+    summarize_acm_column(rank_column, &contest);
 
-    ACM_RANK_CACHE.write().unwrap().insert(region, rank);
+    mark_first_accepted(rank);
+    sort_acm_rank(rank);
+    rank.last_updated_time = get_cur_naive_date_time();
 
     Ok(())
 }
 
+/// Whether a finished submission counts as a try under `rules`, by default judged
+/// submissions count and ones failed before being judged do not.
+fn is_counted_try(submission: &RawSubmission, rules: &ACMPenaltyRules) -> bool {
+    match submission.err.as_deref() {
+        Some("CompileError") => rules.count_compile_error,
+        // nothing was judged
+        Some(_) => false,
+        None => {
+            let is_system_error = submission
+                .out_results
+                .as_ref()
+                .map_or(false, |out_results| {
+                    out_results.iter().any(|result| result == "SYSTEM_ERROR")
+                });
+            submission.is_accepted.is_some() && (!is_system_error || rules.count_system_error)
+        }
+    }
+}
+
 fn build_acm_rank_column(
-    contest: Contest,
+    contest: &Contest,
+    region_links: &[RegionLink],
     access_control_list_column: AccessControlListColumn,
     conn: &PgConnection,
     is_final: bool,
 ) -> ServiceResult<ACMRankColumn> {
-    let region = access_control_list_column.region;
     let user_id = access_control_list_column.id;
     let is_unrated = access_control_list_column.is_unrated;
 
@@ -114,81 +299,521 @@ fn build_acm_rank_column(
         real_name,
         total_accepted: 0,
         time_cost: 0,
+        last_accepted_time: None,
         is_unrated,
         problem_block: Vec::new(),
     };
 
+    for region_link in region_links {
+        rank_column.problem_block.push(build_acm_problem_block(
+            contest,
+            region_link,
+            user_id,
+            conn,
+            is_final,
+        )?);
+    }
+    summarize_acm_column(&mut rank_column, contest);
+
+    Ok(rank_column)
+}
+
+/// Builds the block of a user on a problem, first blood is marked afterwards.
+fn build_acm_problem_block(
+    contest: &Contest,
+    region_link: &RegionLink,
+    user_id: i32,
+    conn: &PgConnection,
+    is_final: bool,
+) -> ServiceResult<ACMProblemBlock> {
+    let submissions = load_acm_submissions(region_link, user_id, conn)?;
+
+    Ok(fold_acm_problem_block(
+        contest,
+        region_link,
+        submissions,
+        is_final,
+    ))
+}
+
+/// Finished submissions of a user on a problem in the order they were submitted.
+fn load_acm_submissions(
+    region_link: &RegionLink,
+    user_id: i32,
+    conn: &PgConnection,
+) -> ServiceResult<Vec<RawSubmission>> {
+    use crate::schema::submissions as submissions_schema;
+    let submissions = submissions_schema::table
+        .filter(submissions_schema::user_id.eq(user_id))
+        .filter(submissions_schema::region.eq(region_link.region.clone()))
+        .filter(submissions_schema::problem_id.eq(region_link.problem_id))
+        .filter(submissions_schema::state.eq("Finished".to_owned()))
+        .order(submissions_schema::submit_time.asc())
+        .load(conn)?;
+
+    Ok(submissions)
+}
+
+fn fold_acm_problem_block(
+    contest: &Contest,
+    region_link: &RegionLink,
+    submissions: Vec<RawSubmission>,
+    is_final: bool,
+) -> ACMProblemBlock {
+    let mut problem_block = ACMProblemBlock {
+        inner_id: region_link.inner_id,
+        is_accepted: None,
+        is_first_accepted: false,
+        is_sealed: false,
+        try_times: 0,
+        last_submit_time: None,
+    };
+
+    for submission in submissions {
+        if !is_counted_try(&submission, &contest.settings.acm_penalty) {
+            continue;
+        }
+
+        let submit_state = get_contest_state(contest.clone(), submission.submit_time);
+
+        if submit_state == ContestState::Preparing {
+            continue;
+        } else if submit_state == ContestState::SealedRunning && !is_final {
+            problem_block.is_accepted = None;
+            problem_block.is_sealed = true;
+            problem_block.try_times += 1;
+            problem_block.last_submit_time = Some(submission.submit_time);
+            if submission.is_accepted == Some(true) {
+                break;
+            }
+        } else if submit_state == ContestState::Running
+            || (submit_state == ContestState::SealedRunning && is_final)
+        {
+            problem_block.is_accepted = submission.is_accepted;
+            problem_block.is_sealed = false;
+            problem_block.try_times += 1;
+            problem_block.last_submit_time = Some(submission.submit_time);
+            if submission.is_accepted == Some(true) {
+                break;
+            }
+        } else {
+            break;
+        }
+    }
+
+    problem_block
+}
+
+/// Counts accepted problems and penalty time of a column from its blocks.
+fn summarize_acm_column(rank_column: &mut ACMRankColumn, contest: &Contest) {
+    let rules = &contest.settings.acm_penalty;
+
+    rank_column.total_accepted = 0;
+    rank_column.time_cost = 0;
+    rank_column.last_accepted_time = None;
+    for problem_block in &rank_column.problem_block {
+        if problem_block.is_accepted != Some(true) {
+            continue;
+        }
+        let submit_time = problem_block.last_submit_time.unwrap();
+
+        let accepted_time = submit_time.timestamp() - contest.start_time.timestamp();
+        let accepted_time = match rules.penalty_unit {
+            PenaltyUnit::Second => accepted_time,
+            PenaltyUnit::Minute => accepted_time / 60 * 60,
+        };
+        rank_column.total_accepted += 1;
+        rank_column.time_cost +=
+            rules.penalty_minutes * 60 * (problem_block.try_times as i64 - 1) + accepted_time;
+        if rank_column.last_accepted_time < Some(submit_time) {
+            rank_column.last_accepted_time = Some(submit_time);
+        }
+    }
+}
+
+/// Marks the earliest visible accepted block of each problem.
+fn mark_first_accepted(rank: &mut ACMRank) {
+    let mut first_accepted_times: HashMap<i32, NaiveDateTime> = HashMap::new();
+    for rank_column in &rank.columns {
+        for problem_block in &rank_column.problem_block {
+            if problem_block.is_accepted != Some(true) {
+                continue;
+            }
+            let submit_time = problem_block.last_submit_time.unwrap();
+            let first_accepted_time = first_accepted_times
+                .entry(problem_block.inner_id)
+                .or_insert(submit_time);
+            if submit_time < *first_accepted_time {
+                *first_accepted_time = submit_time;
+            }
+        }
+    }
+
+    for rank_column in rank.columns.iter_mut() {
+        for problem_block in rank_column.problem_block.iter_mut() {
+            problem_block.is_first_accepted = problem_block.is_accepted == Some(true)
+                && problem_block.last_submit_time
+                    == first_accepted_times.get(&problem_block.inner_id).copied();
+        }
+    }
+}
+
+/// Sorts columns by the rules of the rank and assigns ranks to rated users.
+fn sort_acm_rank(rank: &mut ACMRank) {
+    let rules = rank.rules.clone();
+    let compare = |colume_a: &ACMRankColumn, colume_b: &ACMRankColumn| {
+        let ordering = colume_a
+            .total_accepted
+            .cmp(&colume_b.total_accepted)
+            .reverse()
+            .then(colume_a.time_cost.cmp(&colume_b.time_cost));
+        if rules.tie_break_by_last_accepted {
+            ordering.then(
+                colume_a
+                    .last_accepted_time
+                    .cmp(&colume_b.last_accepted_time),
+            )
+        } else {
+            ordering
+        }
+    };
+
+    rank.columns
+        .sort_by(|colume_a, colume_b| compare(colume_a, colume_b));
+
+    // assgin rank
+    let mut rank_count = 0;
+    let mut last_rated: Option<ACMRankColumn> = None;
+    for colume in rank.columns.iter_mut() {
+        colume.rank = None;
+        if colume.is_unrated == Some(true) {
+            continue;
+        }
+        match &last_rated {
+            Some(last) if compare(last, colume) == std::cmp::Ordering::Equal => (),
+            _ => rank_count += 1,
+        }
+
+        colume.rank = Some(rank_count);
+        last_rated = Some(colume.clone());
+    }
+}
+
+struct ScoredProblem {
+    inner_id: i32,
+    problem_id: i32,
+    link_score: Option<i32>,
+    settings: ProblemSettings,
+}
+
+struct Participant {
+    user_id: i32,
+    username: String,
+    real_name: Option<String>,
+    is_unrated: Option<bool>,
+}
+
+/// Everything score based ranks need, submissions are grouped by user and problem.
+struct ScoreRankData {
+    contest: Contest,
+    problems: Vec<ScoredProblem>,
+    participants: Vec<Participant>,
+    submissions: HashMap<(i32, i32), Vec<RawSubmission>>,
+}
+
+fn load_score_rank_data(region: &str, conn: &PgConnection) -> ServiceResult<ScoreRankData> {
+    use crate::schema::contests as contests_schema;
+    let contest = Contest::from(
+        contests_schema::table
+            .filter(contests_schema::region.eq(region.to_owned()))
+            .first::<RawContest>(conn)?,
+    );
+
+    use crate::schema::problems as problems_schema;
     use crate::schema::region_links as region_links_schema;
-    let region_links: Vec<RegionLink> = region_links_schema::table
-        .filter(region_links_schema::region.eq(region.clone()))
+    let problems = region_links_schema::table
+        .inner_join(
+            problems_schema::table.on(problems_schema::id.eq(region_links_schema::problem_id)),
+        )
+        .filter(region_links_schema::region.eq(region.to_owned()))
         .order(region_links_schema::inner_id.asc())
+        .select((
+            region_links_schema::inner_id,
+            region_links_schema::problem_id,
+            region_links_schema::score,
+            problems_schema::settings,
+        ))
+        .load::<(i32, i32, Option<i32>, String)>(conn)?
+        .into_iter()
+        .map(
+            |(inner_id, problem_id, link_score, settings)| ScoredProblem {
+                inner_id,
+                problem_id,
+                link_score,
+                settings: serde_json::from_str::<ProblemSettings>(&settings).unwrap(),
+            },
+        )
+        .collect();
+
+    use crate::schema::access_control_list as access_control_list_schema;
+    let access_control_list: Vec<AccessControlListColumn> = access_control_list_schema::table
+        .filter(access_control_list_schema::region.eq(region.to_owned()))
+        .filter(access_control_list_schema::self_type.eq("user"))
+        .filter(access_control_list_schema::is_manager.eq(false))
         .load(conn)?;
 
-    for region_link in region_links {
-        let mut problem_block = ACMProblemBlock {
-            inner_id: region_link.inner_id,
-            is_accepted: None,
-            is_first_accepted: false,
-            is_sealed: false,
-            try_times: 0,
-            last_submit_time: None,
-        };
+    use crate::schema::users as users_schema;
+    let names: HashMap<i32, (String, Option<String>)> = users_schema::table
+        .filter(
+            users_schema::id.eq(any(access_control_list
+                .iter()
+                .map(|column| column.id)
+                .collect::<Vec<i32>>())),
+        )
+        .select((
+            users_schema::id,
+            users_schema::username,
+            users_schema::real_name,
+        ))
+        .load::<(i32, String, Option<String>)>(conn)?
+        .into_iter()
+        .map(|(id, username, real_name)| (id, (username, real_name)))
+        .collect();
 
-        use crate::schema::submissions as submissions_schema;
-        let submissions: Vec<RawSubmission> = submissions_schema::table
-            .filter(submissions_schema::user_id.eq(user_id))
-            .filter(submissions_schema::region.eq(region.clone()))
-            .filter(submissions_schema::problem_id.eq(region_link.problem_id))
-            .filter(submissions_schema::is_accepted.is_not_null())
-            .order(submissions_schema::submit_time.asc())
-            .load(conn)?;
+    let participants = access_control_list
+        .into_iter()
+        .filter_map(|column| {
+            names
+                .get(&column.id)
+                .map(|(username, real_name)| Participant {
+                    user_id: column.id,
+                    username: username.clone(),
+                    real_name: real_name.clone(),
+                    is_unrated: column.is_unrated,
+                })
+        })
+        .collect();
 
-        for submission in submissions {
-            let submit_state = get_contest_state(contest.clone(), submission.submit_time);
+    use crate::schema::submissions as submissions_schema;
+    let mut submissions: HashMap<(i32, i32), Vec<RawSubmission>> = HashMap::new();
+    for submission in submissions_schema::table
+        .filter(submissions_schema::region.eq(region.to_owned()))
+        .filter(submissions_schema::state.eq("Finished".to_owned()))
+        .order(submissions_schema::submit_time.asc())
+        .load::<RawSubmission>(conn)?
+    {
+        submissions
+            .entry((submission.user_id, submission.problem_id))
+            .or_insert_with(Vec::new)
+            .push(submission);
+    }
 
-            if submit_state == ContestState::Preparing {
-                continue;
-            } else if submit_state == ContestState::SealedRunning && !is_final {
-                problem_block.is_accepted = None;
-                problem_block.is_sealed = true;
-                problem_block.try_times += 1;
-                problem_block.last_submit_time = Some(submission.submit_time);
-                if submission.is_accepted.unwrap() {
-                    break;
+    Ok(ScoreRankData {
+        contest,
+        problems,
+        participants,
+        submissions,
+    })
+}
+
+/// Ranks columns sorted by score, equal scores share a rank and unrated users get none.
+fn assign_score_ranks(scores: &[(f64, Option<bool>)]) -> Vec<Option<i32>> {
+    let mut ranks = Vec::new();
+    let mut rank_count = 0;
+    let mut last_score = None;
+    for (score, is_unrated) in scores {
+        if *is_unrated == Some(true) {
+            ranks.push(None);
+            continue;
+        }
+        if last_score.map_or(true, |last_score: f64| *score < last_score) {
+            rank_count += 1;
+        }
+        last_score = Some(*score);
+        ranks.push(Some(rank_count));
+    }
+    ranks
+}
+
+pub fn update_oi_rank_cache(
+    region: String,
+    conn: &PgConnection,
+    is_final: bool,
+) -> ServiceResult<()> {
+    log::info!("Updating oi rank");
+    let data = load_score_rank_data(&region, conn)?;
+
+    let mut columns = Vec::new();
+    for participant in data.participants {
+        let mut column = OIRankColumn {
+            rank: None,
+            user_id: participant.user_id,
+            username: participant.username,
+            real_name: participant.real_name,
+            total_score: 0.0,
+            is_unrated: participant.is_unrated,
+            problem_block: Vec::new(),
+        };
+
+        for problem in &data.problems {
+            let mut problem_block = OIProblemBlock {
+                inner_id: problem.inner_id,
+                score: None,
+                submit_times: 0,
+                last_submit_time: None,
+            };
+
+            let mut last_score = None;
+            if let Some(submissions) = data
+                .submissions
+                .get(&(participant.user_id, problem.problem_id))
+            {
+                for submission in submissions {
+                    match get_contest_state(data.contest.clone(), submission.submit_time) {
+                        ContestState::Preparing | ContestState::Ended => continue,
+                        _ => (),
+                    }
+                    problem_block.submit_times += 1;
+                    problem_block.last_submit_time = Some(submission.submit_time);
+                    last_score = Some(scale_score(
+                        problem.link_score,
+                        submission.score.unwrap_or(0.0),
+                        &problem.settings,
+                    ));
                 }
-            } else if submit_state == ContestState::Running
-                || (submit_state == ContestState::SealedRunning && is_final)
+            }
+
+            if is_final {
+                problem_block.score = last_score;
+                column.total_score += last_score.unwrap_or(0.0);
+            }
+            column.problem_block.push(problem_block);
+        }
+
+        columns.push(column);
+    }
+
+    if is_final {
+        columns.sort_by(|column_a, column_b| {
+            column_b
+                .total_score
+                .partial_cmp(&column_a.total_score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+        let ranks = assign_score_ranks(
+            &columns
+                .iter()
+                .map(|column| (column.total_score, column.is_unrated))
+                .collect::<Vec<_>>(),
+        );
+        for (column, rank) in columns.iter_mut().zip(ranks) {
+            column.rank = rank;
+        }
+    }
+
+    OI_RANK_CACHE.write().unwrap().insert(
+        region.clone(),
+        OIRank {
+            region,
+            last_updated_time: get_cur_naive_date_time(),
+            is_final,
+            columns,
+        },
+    );
+
+    Ok(())
+}
+
+pub fn update_ioi_rank_cache(
+    region: String,
+    conn: &PgConnection,
+    is_final: bool,
+) -> ServiceResult<()> {
+    log::info!("Updating ioi rank");
+    let data = load_score_rank_data(&region, conn)?;
+
+    let mut columns = Vec::new();
+    for participant in data.participants {
+        let mut column = IOIRankColumn {
+            rank: None,
+            user_id: participant.user_id,
+            username: participant.username,
+            real_name: participant.real_name,
+            total_score: 0.0,
+            is_unrated: participant.is_unrated,
+            problem_block: Vec::new(),
+        };
+
+        for problem in &data.problems {
+            let mut problem_block = IOIProblemBlock {
+                inner_id: problem.inner_id,
+                score: None,
+                is_sealed: false,
+                try_times: 0,
+                best_submit_time: None,
+            };
+
+            if let Some(submissions) = data
+                .submissions
+                .get(&(participant.user_id, problem.problem_id))
             {
-                problem_block.is_accepted = submission.is_accepted;
-                problem_block.is_sealed = false;
-                problem_block.try_times += 1;
-                problem_block.last_submit_time = Some(submission.submit_time);
-                if submission.is_accepted.unwrap() {
-                    rank_column.total_accepted += 1;
-                    rank_column.time_cost += 20 * 60 * (problem_block.try_times as i64 - 1)
-                        + submission.submit_time.timestamp()
-                        - contest.start_time.timestamp();
-
-                    if submissions_schema::table
-                        .filter(submissions_schema::region.eq(region.clone()))
-                        .filter(submissions_schema::problem_id.eq(region_link.problem_id))
-                        .filter(submissions_schema::is_accepted.eq(Some(true)))
-                        .filter(submissions_schema::submit_time.lt(submission.submit_time))
-                        .count()
-                        .get_result::<i64>(conn)?
-                        == 0
+                for submission in submissions {
+                    let submit_state =
+                        get_contest_state(data.contest.clone(), submission.submit_time);
+                    if submit_state == ContestState::Preparing
+                        || submit_state == ContestState::Ended
                     {
-                        problem_block.is_first_accepted = true;
+                        continue;
+                    }
+                    problem_block.try_times += 1;
+                    if submit_state == ContestState::SealedRunning && !is_final {
+                        problem_block.is_sealed = true;
+                        continue;
+                    }
+
+                    let score = scale_score(
+                        problem.link_score,
+                        submission.score.unwrap_or(0.0),
+                        &problem.settings,
+                    );
+                    if problem_block.score.map_or(true, |best| score > best) {
+                        problem_block.score = Some(score);
+                        problem_block.best_submit_time = Some(submission.submit_time);
                     }
-                    break;
                 }
-            } else {
-                break;
             }
+
+            column.total_score += problem_block.score.unwrap_or(0.0);
+            column.problem_block.push(problem_block);
         }
 
-        rank_column.problem_block.push(problem_block);
+        columns.push(column);
     }
 
-    Ok(rank_column)
+    columns.sort_by(|column_a, column_b| {
+        column_b
+            .total_score
+            .partial_cmp(&column_a.total_score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    let ranks = assign_score_ranks(
+        &columns
+            .iter()
+            .map(|column| (column.total_score, column.is_unrated))
+            .collect::<Vec<_>>(),
+    );
+    for (column, rank) in columns.iter_mut().zip(ranks) {
+        column.rank = rank;
+    }
+
+    IOI_RANK_CACHE.write().unwrap().insert(
+        region.clone(),
+        IOIRank {
+            region,
+            last_updated_time: get_cur_naive_date_time(),
+            columns,
+        },
+    );
+
+    Ok(())
 }
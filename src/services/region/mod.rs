@@ -5,6 +5,10 @@ use crate::models::problems::*;
 use crate::models::region_links::*;
 use crate::models::regions::*;
 use crate::models::utils::SizedList;
+use crate::services::contest::utils::is_verdict_hidden;
+use crate::services::language::get_enabled_list;
+use crate::services::problem::test_case_version;
+use crate::services::rank::utils::invalidate_rank_caches;
 use crate::statics::RESULT_STATISTICS_CACHE;
 use actix_files::NamedFile;
 use actix_web::web;
@@ -86,6 +90,8 @@ pub fn insert_problems(
         }
     }
 
+    invalidate_rank_caches(&region);
+
     Ok(res)
 }
 
@@ -136,7 +142,7 @@ pub fn get_linked_problem_column_list(
         .inner_join(
             problems_schema::table.on(problems_schema::id.eq(region_links_schema::problem_id)),
         )
-        .filter(region_links_schema::region.eq(region))
+        .filter(region_links_schema::region.eq(region.clone()))
         .filter(
             region_links_schema::inner_id
                 .nullable()
@@ -172,6 +178,8 @@ pub fn get_linked_problem_column_list(
         problems_schema::tags,
         problems_schema::difficulty,
         problems_schema::is_released,
+        region_links_schema::score,
+        problems_schema::settings,
     ));
 
     let columns: Vec<RawLinkedProblemColumn> = match id_order {
@@ -194,10 +202,18 @@ pub fn get_linked_problem_column_list(
             .load(conn)?,
     };
 
+    let is_verdict_hidden = is_verdict_hidden(conn, &region)?;
     let out_columns = {
         let mut res = Vec::new();
         for column in columns {
-            res.push(get_column_from_raw(conn, column, user_id)?);
+            let mut column = get_column_from_raw(conn, column, user_id)?;
+            if is_verdict_hidden {
+                column.is_accepted = false;
+                column.score = None;
+                column.accept_times = 0;
+                column.error_times = 0;
+            }
+            res.push(column);
         }
         res
     };
@@ -229,7 +245,10 @@ pub fn get_linked_problem(
         .filter(problems_schema::id.eq(problem_id))
         .first(conn)?;
 
-    Ok(Problem::from(problem))
+    let mut problem = Problem::from(problem);
+    problem.effective_limits = problem.settings.effective_limits(&get_enabled_list(conn)?);
+
+    Ok(problem)
 }
 
 pub fn create_submission(
@@ -324,6 +343,7 @@ pub fn delete_problem(region: String, inner_id: i32, pool: web::Data<Pool>) -> S
         let mut result_statistics = RESULT_STATISTICS_CACHE.write().unwrap();
         result_statistics.remove(&(region.to_owned(), problem_id));
     }
+    invalidate_rank_caches(&region);
 
     if region_links_schema::table
         .filter(region_links_schema::problem_id.eq(problem_id))
@@ -357,10 +377,11 @@ pub fn get_linked_problem_test_case(
         .select(region_links_schema::problem_id)
         .first(conn)?;
 
+    let test_cases_folder = test_case_version::current_folder(conn, problem_id)?;
     let file_path = if input {
-        format!("data/test_cases/{}/{}.in", problem_id, test_case_id)
+        format!("{}/{}.in", test_cases_folder, test_case_id)
     } else {
-        format!("data/test_cases/{}/{}.out", problem_id, test_case_id)
+        format!("{}/{}.out", test_cases_folder, test_case_id)
     };
 
     Ok(NamedFile::open(file_path)?)
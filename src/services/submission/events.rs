@@ -0,0 +1,184 @@
+use crate::models::submissions::{JudgeProgress, RawSubmission, SubmissionEvent};
+use crate::services::contest::utils::is_verdict_hidden;
+use crate::statics::SUBMISSION_SUBSCRIBERS;
+use actix_web::web::Bytes;
+use diesel::prelude::*;
+use futures::channel::mpsc::{channel, Receiver, Sender};
+use server_core::errors::ServiceResult;
+use uuid::Uuid;
+
+/// Messages buffered for a subscriber which has not read them yet,
+/// one falling further behind is dropped instead of buffered without limit.
+const SUBSCRIBER_BUFFER_SIZE: usize = 256;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubscriptionFilter {
+    /// Submissions of a user
+    User(i32),
+    /// Submissions in a region
+    Region(String),
+}
+
+impl SubscriptionFilter {
+    fn matches(&self, event: &SubmissionEvent) -> bool {
+        match self {
+            SubscriptionFilter::User(user_id) => event.user_id == *user_id,
+            SubscriptionFilter::Region(region) => event.region.as_ref() == Some(region),
+        }
+    }
+}
+
+pub struct SubmissionSubscriber {
+    pub filter: SubscriptionFilter,
+    pub sender: Sender<Bytes>,
+}
+
+pub fn subscribe(filter: SubscriptionFilter) -> Receiver<Bytes> {
+    let (mut sender, receiver) = channel(SUBSCRIBER_BUFFER_SIZE);
+    // let the client know the stream is open
+    let _ = sender.try_send(Bytes::from_static(b": connected\n\n"));
+
+    let mut lock = SUBMISSION_SUBSCRIBERS.write().unwrap();
+    lock.push(SubmissionSubscriber { filter, sender });
+
+    receiver
+}
+
+pub fn has_subscribers() -> bool {
+    !SUBMISSION_SUBSCRIBERS.read().unwrap().is_empty()
+}
+
+/// Sends `event` to matched subscribers, subscribers gone are dropped on the way.
+pub fn publish(event: &SubmissionEvent) {
+    let message = Bytes::from(format!(
+        "event: submission\ndata: {}\n\n",
+        serde_json::to_string(event).unwrap()
+    ));
+
+    send(&message, |subscriber| subscriber.filter.matches(event));
+}
+
+/// Sends `message` to the subscribers `is_target` holds for, drops the ones gone or
+/// too slow to keep up, their streams end once the buffered messages are read.
+fn send<F>(message: &Bytes, is_target: F)
+where
+    F: Fn(&SubmissionSubscriber) -> bool,
+{
+    let mut lock = SUBMISSION_SUBSCRIBERS.write().unwrap();
+    for subscriber in lock.iter_mut().filter(|subscriber| is_target(subscriber)) {
+        if subscriber.sender.try_send(message.clone()).is_err() {
+            subscriber.sender.close_channel();
+        }
+    }
+    lock.retain(|subscriber| !subscriber.sender.is_closed());
+}
+
+/// Keeps connections alive through proxies and finds out closed ones,
+/// called periodically from main.
+pub fn ping() {
+    send(&Bytes::from_static(b": ping\n\n"), |_| true);
+}
+
+fn queue_position(conn: &PgConnection, raw: &RawSubmission) -> ServiceResult<i64> {
+    use crate::schema::submissions as submissions_schema;
+
+    let ahead: i64 = submissions_schema::table
+        .filter(submissions_schema::state.eq("Waiting".to_owned()))
+        .filter(submissions_schema::submit_time.lt(raw.submit_time))
+        .count()
+        .get_result(conn)?;
+
+    Ok(ahead + 1)
+}
+
+/// Publishes the current state of a submission.
+pub fn notify(conn: &PgConnection, id: Uuid) -> ServiceResult<()> {
+    if !has_subscribers() {
+        return Ok(());
+    }
+
+    use crate::schema::submissions as submissions_schema;
+    let raw: RawSubmission = submissions_schema::table
+        .filter(submissions_schema::id.eq(id))
+        .first(conn)?;
+
+    let queue_position = if raw.state == "Waiting" {
+        Some(queue_position(conn, &raw)?)
+    } else {
+        None
+    };
+
+    let is_hidden = match &raw.region {
+        Some(region) => is_verdict_hidden(conn, region)?,
+        None => false,
+    };
+    let mut event = SubmissionEvent::from(raw);
+    event.queue_position = queue_position;
+    if is_hidden {
+        event.hide_verdict();
+    }
+    publish(&event);
+
+    Ok(())
+}
+
+/// Publishes test cases finished so far of a Pending submission.
+pub fn notify_progress(conn: &PgConnection, progress: &JudgeProgress) -> ServiceResult<()> {
+    if !has_subscribers() {
+        return Ok(());
+    }
+
+    use crate::schema::submissions as submissions_schema;
+    let raw: RawSubmission = submissions_schema::table
+        .filter(submissions_schema::id.eq(progress.submission_id))
+        .first(conn)?;
+
+    let is_hidden = match &raw.region {
+        Some(region) => is_verdict_hidden(conn, region)?,
+        None => false,
+    };
+    let mut event = SubmissionEvent::from(raw);
+    event.progress = Some(progress.clone());
+    if is_hidden {
+        event.hide_verdict();
+    }
+    publish(&event);
+
+    Ok(())
+}
+
+/// Publishes new queue positions of waiting submissions,
+/// called whenever a submission leaves the queue.
+pub fn notify_queue_positions(conn: &PgConnection) -> ServiceResult<()> {
+    if !has_subscribers() {
+        return Ok(());
+    }
+
+    use crate::schema::submissions as submissions_schema;
+    let waiting: Vec<(Uuid, i32, i32, Option<String>)> = submissions_schema::table
+        .filter(submissions_schema::state.eq("Waiting".to_owned()))
+        .order(submissions_schema::submit_time.asc())
+        .select((
+            submissions_schema::id,
+            submissions_schema::problem_id,
+            submissions_schema::user_id,
+            submissions_schema::region,
+        ))
+        .load(conn)?;
+
+    for (index, (id, problem_id, user_id, region)) in waiting.into_iter().enumerate() {
+        publish(&SubmissionEvent {
+            submission_id: id,
+            problem_id: problem_id,
+            user_id: user_id,
+            region: region,
+            state: "Waiting".to_owned(),
+            queue_position: Some(index as i64 + 1),
+            result: None,
+            score: None,
+            progress: None,
+        });
+    }
+
+    Ok(())
+}
@@ -1,7 +1,8 @@
 use crate::judge_actor::{handler::StartJudge, JudgeActorAddr};
 use crate::models::utils::SizedList;
 use crate::models::*;
-use crate::statics::WAITING_QUEUE;
+use crate::services::contest::utils::is_verdict_hidden;
+use crate::services::problem_set;
 use actix_web::web;
 use diesel::pg::expression::dsl::any;
 use diesel::prelude::*;
@@ -9,10 +10,13 @@ use server_core::database::{db_connection, Pool};
 use server_core::errors::ServiceResult;
 use server_core::utils::time::get_cur_naive_date_time;
 use std::collections::HashMap;
-use std::fs::File;
-use std::io::prelude::*;
 use uuid::Uuid;
 
+pub mod events;
+pub mod progress;
+pub mod rejudge;
+pub mod utils;
+
 pub fn create(
     region: Option<String>,
     problem_id: i32,
@@ -23,56 +27,22 @@ pub fn create(
     judge_actor: web::Data<JudgeActorAddr>,
 ) -> ServiceResult<Uuid> {
     let id = Uuid::new_v4();
-    let language_config = languages::get_lang_config(&language);
 
     let conn = &db_connection(&pool)?;
-    use crate::schema::problems as problems_schema;
     use crate::schema::submissions as submissions_schema;
 
-    let raw_problem: problems::RawProblem = problems_schema::table
-        .filter(problems_schema::id.eq(problem_id))
-        .first(conn)?;
-    let problem = problems::Problem::from(raw_problem);
-    let mut spj_src = None;
-    if problem.settings.is_spj {
-        let mut file = File::open(format!("data/test_cases/{}/spj_src.cpp", problem.id))?;
-        let mut contents = String::new();
-        file.read_to_string(&mut contents)?;
-        spj_src = Some(contents);
-    }
-
-    let settings = submissions::JudgeSettings {
-        language_config: language_config,
-        src: src,
-        max_cpu_time: if &language == "c" || &language == "cpp" {
-            problem.settings.high_performance_max_cpu_time
-        } else {
-            problem.settings.other_max_cpu_time
-        },
-        max_memory: if &language == "c" || &language == "cpp" {
-            problem.settings.high_performance_max_memory
-        } else {
-            problem.settings.other_max_memory
-        },
-        test_case_id: Some(problem.id.to_string()),
-        test_case: None,
-        spj_version: Some("1".to_owned()),
-        spj_config: if problem.settings.is_spj {
-            Some(languages::spj_config())
-        } else {
-            None
-        },
-        spj_compile_config: if problem.settings.is_spj {
-            Some(languages::spj_compile_config())
-        } else {
-            None
-        },
-        spj_src: spj_src,
-        output: !problem.settings.opaque_output,
-    };
+    let (settings, test_case_version) =
+        utils::build_judge_settings(conn, problem_id, src, &language)?;
 
     let settings_string = serde_json::to_string(&settings).unwrap();
 
+    let submit_time = get_cur_naive_date_time();
+    let is_late = match &region {
+        Some(region) => problem_set::utils::get_settings(conn, region)?
+            .map_or(false, |settings| settings.is_late(submit_time)),
+        None => false,
+    };
+
     diesel::insert_into(submissions_schema::table)
         .values(&submissions::InsertableSubmission {
             id: id,
@@ -82,19 +52,21 @@ pub fn create(
             state: String::from("Waiting"),
             settings: settings_string,
             result: None,
-            submit_time: get_cur_naive_date_time(),
+            submit_time: submit_time,
             is_accepted: None,
             finish_time: None,
             max_time: None,
             max_memory: None,
             language: Some(language),
             err: None,
+            is_late: is_late,
+            test_case_version: test_case_version,
         })
         .execute(conn)?;
 
-    {
-        let mut lock = WAITING_QUEUE.write().unwrap();
-        lock.push_back(id);
+    match events::notify(conn, id) {
+        Ok(_) => (),
+        Err(_) => log::error!("Error publishing submission event."),
     }
 
     judge_actor.addr.do_send(StartJudge());
@@ -102,7 +74,11 @@ pub fn create(
     Ok(id)
 }
 
-pub fn get(id: Uuid, pool: web::Data<Pool>) -> ServiceResult<submissions::Submission> {
+pub fn get(
+    id: Uuid,
+    is_admin: bool,
+    pool: web::Data<Pool>,
+) -> ServiceResult<submissions::Submission> {
     let conn = &db_connection(&pool)?;
 
     use crate::schema::submissions as submissions_schema;
@@ -111,7 +87,14 @@ pub fn get(id: Uuid, pool: web::Data<Pool>) -> ServiceResult<submissions::Submis
         .filter(submissions_schema::id.eq(id))
         .first(conn)?;
 
-    Ok(submissions::Submission::from(raw))
+    let mut submission = submissions::Submission::from(raw);
+    if let Some(region) = &submission.region {
+        if !is_admin && is_verdict_hidden(conn, region)? {
+            submission.hide_verdict();
+        }
+    }
+
+    Ok(submission)
 }
 
 pub fn get_list(
@@ -120,6 +103,7 @@ pub fn get_list(
     user_id_filter: Option<i32>,
     limit: i32,
     offset: i32,
+    is_admin: bool,
     pool: web::Data<Pool>,
 ) -> ServiceResult<SizedList<submissions::SlimSubmission>> {
     let conn = &db_connection(&pool)?;
@@ -156,9 +140,25 @@ pub fn get_list(
 
     let mut slim_submissions = Vec::new();
     let mut user_ids = Vec::new();
+    let mut verdict_hidden_cache: HashMap<String, bool> = HashMap::new();
     for raw_submission in raw_submissions {
         user_ids.push(raw_submission.user_id);
-        slim_submissions.push(submissions::SlimSubmission::from(raw_submission));
+        let is_verdict_hidden = match &raw_submission.region {
+            Some(region) if !is_admin => match verdict_hidden_cache.get(region) {
+                Some(is_hidden) => *is_hidden,
+                None => {
+                    let is_hidden = is_verdict_hidden(conn, region)?;
+                    verdict_hidden_cache.insert(region.clone(), is_hidden);
+                    is_hidden
+                }
+            },
+            _ => false,
+        };
+        let mut slim_submission = submissions::SlimSubmission::from(raw_submission);
+        if is_verdict_hidden {
+            slim_submission.hide_verdict();
+        }
+        slim_submissions.push(slim_submission);
     }
 
     use crate::schema::users as users_schema;
@@ -0,0 +1,84 @@
+use super::events;
+use crate::models::*;
+use crate::statics::JUDGE_PROGRESS;
+use actix_web::web;
+use diesel::prelude::*;
+use judge_client::models::JudgeProgressReport;
+use server_core::database::{db_connection, Pool};
+use server_core::errors::{ServiceError, ServiceResult};
+use uuid::Uuid;
+
+/// Starts tracking a submission which is about to be sent to a judge server.
+pub fn start(conn: &PgConnection, id: Uuid, judge_server_id: i32) -> ServiceResult<()> {
+    use crate::schema::problems as problems_schema;
+    use crate::schema::submissions as submissions_schema;
+
+    let problem_id: i32 = submissions_schema::table
+        .filter(submissions_schema::id.eq(id))
+        .select(submissions_schema::problem_id)
+        .first(conn)?;
+    let problem = problems::Problem::from(
+        problems_schema::table
+            .filter(problems_schema::id.eq(problem_id))
+            .first::<problems::RawProblem>(conn)?,
+    );
+
+    let mut lock = JUDGE_PROGRESS.write().unwrap();
+    lock.insert(
+        id,
+        submissions::JudgeProgress {
+            submission_id: id,
+            judge_server_id: judge_server_id,
+            finished_cases: 0,
+            total_cases: problem.settings.test_case_count,
+            details: Vec::new(),
+        },
+    );
+
+    Ok(())
+}
+
+pub fn finish(id: Uuid) {
+    let mut lock = JUDGE_PROGRESS.write().unwrap();
+    lock.remove(&id);
+}
+
+/// Records a test case reported by the judge server `judge_server_id`.
+pub fn record(
+    report: JudgeProgressReport,
+    judge_server_id: i32,
+    pool: web::Data<Pool>,
+) -> ServiceResult<()> {
+    let id = Uuid::parse_str(&report.submission_id)?;
+
+    let progress = {
+        let mut lock = JUDGE_PROGRESS.write().unwrap();
+        let progress = match lock.get_mut(&id) {
+            Some(progress) if progress.judge_server_id == judge_server_id => progress,
+            _ => {
+                let hint = "Submission is not being judged by this judge server.".to_string();
+                return Err(ServiceError::BadRequest(hint));
+            }
+        };
+        // a retried callback replaces the detail reported before for its test case
+        let detail = submissions::JudgeResultData::from(report.data);
+        match progress
+            .details
+            .iter_mut()
+            .find(|reported| reported.test_case == detail.test_case)
+        {
+            Some(reported) => *reported = detail,
+            None => progress.details.push(detail),
+        }
+        progress.finished_cases = progress.details.len() as i32;
+        progress.clone()
+    };
+
+    let conn = &db_connection(&pool)?;
+    events::notify_progress(conn, &progress)
+}
+
+pub fn get(id: Uuid) -> ServiceResult<Option<submissions::JudgeProgress>> {
+    let lock = JUDGE_PROGRESS.read().unwrap();
+    Ok(lock.get(&id).cloned())
+}
@@ -0,0 +1,276 @@
+use super::{events, utils};
+use crate::judge_actor::{handler::StartJudge, JudgeActorAddr};
+use crate::models::rejudge_records::*;
+use crate::models::submissions::*;
+use crate::models::utils::SizedList;
+use crate::services::language::get_enabled;
+use crate::services::rank::utils::invalidate_rank_caches;
+use crate::statics::RESULT_STATISTICS_CACHE;
+use actix_web::web;
+use diesel::prelude::*;
+use server_core::database::{db_connection, Pool};
+use server_core::errors::{ServiceError, ServiceResult};
+use server_core::utils::time::get_cur_naive_date_time;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+/// Puts finished submissions matched by `filter` back to the queue,
+/// settings are rebuilt so that fixed test data and limits take effect.
+pub fn rejudge(
+    filter: RejudgeFilter,
+    operator_id: i32,
+    pool: web::Data<Pool>,
+    judge_actor: web::Data<JudgeActorAddr>,
+) -> ServiceResult<RejudgeSummary> {
+    if filter.submission_id.is_none() && filter.problem_id.is_none() && filter.region.is_none() {
+        let hint = "Rejudge needs a submission, a problem or a region.".to_string();
+        return Err(ServiceError::BadRequest(hint));
+    }
+
+    let conn = &db_connection(&pool)?;
+
+    use crate::schema::submissions as submissions_schema;
+    let raw_submissions: Vec<RawSubmission> = submissions_schema::table
+        .filter(submissions_schema::state.eq("Finished".to_owned()))
+        .filter(
+            submissions_schema::id
+                .nullable()
+                .eq(filter.submission_id)
+                .or(filter.submission_id.is_none()),
+        )
+        .filter(
+            submissions_schema::problem_id
+                .nullable()
+                .eq(filter.problem_id)
+                .or(filter.problem_id.is_none()),
+        )
+        .filter(
+            submissions_schema::region
+                .eq(filter.region.clone())
+                .or(filter.region.is_none()),
+        )
+        .filter(
+            submissions_schema::is_accepted
+                .eq(filter.is_accepted)
+                .or(filter.is_accepted.is_none()),
+        )
+        .filter(
+            submissions_schema::err
+                .eq(filter.err.clone())
+                .or(filter.err.is_none()),
+        )
+        .filter(
+            submissions_schema::submit_time
+                .nullable()
+                .ge(filter.start_time)
+                .or(filter.start_time.is_none()),
+        )
+        .filter(
+            submissions_schema::submit_time
+                .nullable()
+                .le(filter.end_time)
+                .or(filter.end_time.is_none()),
+        )
+        .order(submissions_schema::submit_time.asc())
+        .load(conn)?;
+
+    let raw_submissions: Vec<RawSubmission> = raw_submissions
+        .into_iter()
+        .filter(|raw| match &filter.out_result {
+            Some(out_result) => raw
+                .out_results
+                .as_ref()
+                .map_or(false, |out_results| out_results.contains(out_result)),
+            None => true,
+        })
+        .collect();
+
+    let rejudge_id = Uuid::new_v4();
+    let rejudge_time = get_cur_naive_date_time();
+    let mut affected: HashSet<(String, i32)> = HashSet::new();
+    let mut rejudged_ids = Vec::new();
+
+    conn.transaction::<_, ServiceError, _>(|| {
+        use crate::schema::rejudge_records as rejudge_records_schema;
+
+        for raw in &raw_submissions {
+            // a disabled language can not be judged and malformed settings have no source
+            // to judge, such submissions keep their verdicts
+            let mut skip_reason = match &raw.language {
+                Some(language) => match get_enabled(conn, language) {
+                    Ok(_) => None,
+                    Err(ServiceError::BadRequest(hint)) => Some(hint),
+                    Err(e) => return Err(e),
+                },
+                None => None,
+            };
+            let old_settings = match &raw.language {
+                Some(_) if skip_reason.is_none() => {
+                    match serde_json::from_str::<JudgeSettings>(&raw.settings) {
+                        Ok(old_settings) => Some(old_settings),
+                        Err(e) => {
+                            skip_reason = Some(format!("Judge settings are malformed: {}", e));
+                            None
+                        }
+                    }
+                }
+                _ => None,
+            };
+            if skip_reason.is_some() {
+                diesel::insert_into(rejudge_records_schema::table)
+                    .values(&InsertableRejudgeRecord {
+                        rejudge_id: rejudge_id,
+                        submission_id: raw.id,
+                        operator_id: operator_id,
+                        rejudge_time: rejudge_time,
+                        old_is_accepted: raw.is_accepted,
+                        old_err: raw.err.clone(),
+                        old_out_results: raw.out_results.clone(),
+                        finish_time: Some(rejudge_time),
+                        skip_reason: skip_reason,
+                    })
+                    .execute(conn)?;
+                continue;
+            }
+
+            let (settings, test_case_version) = match (&raw.language, old_settings) {
+                (Some(language), Some(old_settings)) => {
+                    let (settings, test_case_version) = utils::build_judge_settings(
+                        conn,
+                        raw.problem_id,
+                        old_settings.src,
+                        language,
+                    )?;
+                    (serde_json::to_string(&settings).unwrap(), test_case_version)
+                }
+                _ => (raw.settings.clone(), raw.test_case_version.clone()),
+            };
+
+            diesel::update(submissions_schema::table.filter(submissions_schema::id.eq(raw.id)))
+                .set((
+                    submissions_schema::state.eq("Waiting".to_owned()),
+                    submissions_schema::settings.eq(settings),
+                    submissions_schema::test_case_version.eq(test_case_version),
+                    submissions_schema::result.eq(None::<String>),
+                    submissions_schema::is_accepted.eq(None::<bool>),
+                    submissions_schema::finish_time.eq(None::<chrono::NaiveDateTime>),
+                    submissions_schema::max_time.eq(None::<i32>),
+                    submissions_schema::max_memory.eq(None::<i32>),
+                    submissions_schema::err.eq(None::<String>),
+                    submissions_schema::out_results.eq(None::<Vec<String>>),
+                    submissions_schema::pending_time.eq(None::<chrono::NaiveDateTime>),
+                    submissions_schema::score.eq(None::<f64>),
+                    submissions_schema::judge_attempts.eq(0),
+                ))
+                .execute(conn)?;
+
+            diesel::insert_into(rejudge_records_schema::table)
+                .values(&InsertableRejudgeRecord {
+                    rejudge_id: rejudge_id,
+                    submission_id: raw.id,
+                    operator_id: operator_id,
+                    rejudge_time: rejudge_time,
+                    old_is_accepted: raw.is_accepted,
+                    old_err: raw.err.clone(),
+                    old_out_results: raw.out_results.clone(),
+                    finish_time: None,
+                    skip_reason: None,
+                })
+                .execute(conn)?;
+
+            rejudged_ids.push(raw.id);
+            if let Some(region) = &raw.region {
+                affected.insert((region.clone(), raw.problem_id));
+            }
+        }
+
+        Ok(())
+    })?;
+
+    // statistics are counted again from the database on next visit,
+    // rejudged submissions are added back one by one as they finish
+    {
+        let mut result_statistics = RESULT_STATISTICS_CACHE.write().unwrap();
+        for (region, problem_id) in &affected {
+            result_statistics.remove(&(region.clone(), *problem_id));
+            invalidate_rank_caches(region);
+        }
+    }
+
+    for id in &rejudged_ids {
+        match events::notify(conn, *id) {
+            Ok(_) => (),
+            Err(_) => log::error!("Error publishing submission event."),
+        }
+    }
+
+    judge_actor.addr.do_send(StartJudge());
+
+    Ok(RejudgeSummary {
+        rejudge_id: rejudge_id,
+        count: rejudged_ids.len() as i32,
+        skipped_count: (raw_submissions.len() - rejudged_ids.len()) as i32,
+    })
+}
+
+/// Fills in the new verdict of a rejudged submission,
+/// returns whether the submission was being rejudged.
+pub fn finish_record(conn: &PgConnection, submission: &Submission) -> ServiceResult<bool> {
+    use crate::schema::rejudge_records as rejudge_records_schema;
+
+    let count = diesel::update(
+        rejudge_records_schema::table
+            .filter(rejudge_records_schema::submission_id.eq(submission.id))
+            .filter(rejudge_records_schema::finish_time.is_null()),
+    )
+    .set((
+        rejudge_records_schema::new_is_accepted.eq(submission.is_accepted),
+        rejudge_records_schema::new_err.eq(submission.err.clone()),
+        rejudge_records_schema::new_out_results.eq(submission
+            .out_results
+            .clone()
+            .map(|out_results| out_results.into_iter().collect::<Vec<String>>())),
+        rejudge_records_schema::finish_time.eq(submission.finish_time),
+    ))
+    .execute(conn)?;
+
+    Ok(count > 0)
+}
+
+pub fn get_record_list(
+    rejudge_id_filter: Option<Uuid>,
+    submission_id_filter: Option<Uuid>,
+    limit: i32,
+    offset: i32,
+    pool: web::Data<Pool>,
+) -> ServiceResult<SizedList<RejudgeRecord>> {
+    let conn = &db_connection(&pool)?;
+
+    use crate::schema::rejudge_records as rejudge_records_schema;
+    let target = rejudge_records_schema::table
+        .filter(
+            rejudge_records_schema::rejudge_id
+                .nullable()
+                .eq(rejudge_id_filter)
+                .or(rejudge_id_filter.is_none()),
+        )
+        .filter(
+            rejudge_records_schema::submission_id
+                .nullable()
+                .eq(submission_id_filter)
+                .or(submission_id_filter.is_none()),
+        );
+
+    let total: i64 = target.clone().count().get_result(conn)?;
+
+    let records: Vec<RejudgeRecord> = target
+        .offset(offset.into())
+        .limit(limit.into())
+        .order(rejudge_records_schema::id.desc())
+        .load(conn)?;
+
+    Ok(SizedList {
+        total: total,
+        list: records,
+    })
+}
@@ -0,0 +1,103 @@
+use crate::models::*;
+use crate::services::language::get_enabled;
+use crate::services::problem::{checker, interactor, test_case_version};
+use crate::services::problem_set;
+use diesel::prelude::*;
+use server_core::errors::ServiceResult;
+use uuid::Uuid;
+
+/// Builds what the judge server needs to judge `src` against the current problem data,
+/// along with the version of the test data it is judged against.
+pub fn build_judge_settings(
+    conn: &PgConnection,
+    problem_id: i32,
+    src: String,
+    language: &str,
+) -> ServiceResult<(submissions::JudgeSettings, Option<String>)> {
+    let language = get_enabled(conn, language)?;
+
+    use crate::schema::problems as problems_schema;
+    let raw_problem: problems::RawProblem = problems_schema::table
+        .filter(problems_schema::id.eq(problem_id))
+        .first(conn)?;
+    let version = raw_problem.test_case_version.clone();
+    let problem = problems::Problem::from(raw_problem);
+    let limit = problem.settings.effective_limit(&language);
+    let checker = if problem.settings.is_spj {
+        Some(checker::get_for_judge_with_config(conn, problem.id)?)
+    } else {
+        None
+    };
+    let interactor = if problem.settings.problem_type == problems::ProblemType::Interactive {
+        Some(interactor::get_for_judge(
+            conn,
+            &test_case_version::folder(problem.id, version.as_deref()),
+        )?)
+    } else {
+        None
+    };
+
+    let settings = submissions::JudgeSettings {
+        language_config: language.config,
+        src: src,
+        max_cpu_time: limit.max_cpu_time,
+        max_memory: limit.max_memory,
+        test_case_id: Some(test_case_version::test_case_id(
+            problem.id,
+            version.as_deref(),
+        )),
+        test_case: None,
+        spj_version: checker.as_ref().map(|(checker, _)| checker.version.clone()),
+        spj_config: checker
+            .as_ref()
+            .map(|(_, checker_config)| checker_config.run.clone()),
+        spj_compile_config: checker
+            .as_ref()
+            .map(|(_, checker_config)| checker_config.compile.clone()),
+        spj_src: checker.map(|(checker, _)| checker.src),
+        output: !problem.settings.opaque_output,
+        progress_callback: None,
+        stop_on_failure: problem.settings.stop_on_failure,
+        interactor: interactor,
+    };
+
+    Ok((settings, version))
+}
+
+/// Scores a judged submission against the subtasks of its problem,
+/// submissions without judged test cases are not scored.
+pub fn get_score(
+    conn: &PgConnection,
+    id: Uuid,
+    result: &submissions::JudgeResult,
+) -> ServiceResult<Option<f64>> {
+    let details = match &result.details {
+        Some(details) => details,
+        None => return Ok(None),
+    };
+
+    use crate::schema::problems as problems_schema;
+    use crate::schema::submissions as submissions_schema;
+    let (settings, region, is_late): (String, Option<String>, bool) = submissions_schema::table
+        .inner_join(
+            problems_schema::table.on(problems_schema::id.eq(submissions_schema::problem_id)),
+        )
+        .filter(submissions_schema::id.eq(id))
+        .select((
+            problems_schema::settings,
+            submissions_schema::region,
+            submissions_schema::is_late,
+        ))
+        .first(conn)?;
+    let settings: problems::ProblemSettings = serde_json::from_str(&settings).unwrap();
+    let score = settings.score(details);
+
+    // late submissions of a problem set lose part of their score
+    let late_penalty_factor = match region {
+        Some(region) if is_late => problem_set::utils::get_settings(conn, &region)?
+            .map_or(1.0, |settings| settings.late_penalty_factor),
+        _ => 1.0,
+    };
+
+    Ok(Some(score * late_penalty_factor))
+}
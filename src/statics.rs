@@ -1,22 +1,47 @@
 use crate::models::{
-    judge_servers::JudgeServerInfo, ranks::ACMRank, statistics::SubmissionStatistics,
+    judge_servers::{JudgeServerInfo, ScheduleConfig},
+    ranks::{ACMRank, IOIRank, OIRank},
+    statistics::SubmissionStatistics,
+    submissions::JudgeProgress,
     users::AuthConfig,
 };
+use crate::services::problem::archive::ArchiveLimits;
+use crate::services::submission::events::SubmissionSubscriber;
 use regex::Regex;
 use std::io::Read;
 use std::{
-    collections::{HashMap, VecDeque},
-    sync::RwLock,
+    collections::HashMap,
+    sync::{Mutex, RwLock},
 };
-use uuid::Uuid;
 
 lazy_static! {
-    pub static ref WAITING_QUEUE: RwLock<VecDeque::<Uuid>> = RwLock::new(VecDeque::new());
     pub static ref RESULT_STATISTICS_CACHE: RwLock<HashMap<(String, i32), SubmissionStatistics>> =
         RwLock::new(HashMap::new());
     pub static ref ACM_RANK_CACHE: RwLock<HashMap<String, ACMRank>> = RwLock::new(HashMap::new());
+    /// Keeps blocks loaded for finished submissions from being applied out of order.
+    pub static ref ACM_RANK_APPLY_LOCK: Mutex<()> = Mutex::new(());
+    pub static ref OI_RANK_CACHE: RwLock<HashMap<String, OIRank>> = RwLock::new(HashMap::new());
+    pub static ref IOI_RANK_CACHE: RwLock<HashMap<String, IOIRank>> = RwLock::new(HashMap::new());
     pub static ref JUDGE_SERVER_INFOS: RwLock<HashMap<String, JudgeServerInfo>> =
         RwLock::new(HashMap::new());
+    pub static ref JUDGE_PROGRESS: RwLock<HashMap<uuid::Uuid, JudgeProgress>> =
+        RwLock::new(HashMap::new());
+    pub static ref SUBMISSION_SUBSCRIBERS: RwLock<Vec<SubmissionSubscriber>> =
+        RwLock::new(Vec::new());
+    pub static ref SCHEDULE_CONFIG: ScheduleConfig = match std::fs::read_to_string("schedule.toml")
+    {
+        Ok(content) => match toml::from_str(&content) {
+            Ok(config) => config,
+            Err(e) => {
+                error!(
+                    "Invalid schedule.toml, using the default schedule config: {}",
+                    e
+                );
+                ScheduleConfig::default()
+            }
+        },
+        Err(_) => ScheduleConfig::default(),
+    };
     pub static ref RE_EMAIL: Regex =
         Regex::new(r"^\w+([-+.]\w+)*@\w+([-.]\w+)*\.\w+([-.]\w+)*$").unwrap();
     pub static ref RE_MOBILE: Regex =
@@ -30,5 +55,6 @@ lazy_static! {
     };
     pub static ref PROBLEM_TAG_NAME_CACHE: RwLock<HashMap<i32, String>> =
         RwLock::new(HashMap::new());
+    pub static ref ARCHIVE_LIMITS: RwLock<ArchiveLimits> = RwLock::new(ArchiveLimits::default());
     pub static ref ADMIN_EMAIL: RwLock<String> = RwLock::new(String::from("1987258436@qq.com"));
 }